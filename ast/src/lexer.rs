@@ -0,0 +1,490 @@
+//! Splitting CUE source into tokens.
+//!
+//! Comments are collected on the side rather than returned as tokens, and
+//! commas are inserted at the end of lines the way the CUE scanner does:
+//! after an identifier, a literal, `_|_`, `)`, `]`, `}`, `?`, `...` or an
+//! attribute.
+
+use super::{Comment, Span};
+
+/// Punctuation and operators, longest first.
+const PUNCT: [&str; 28] = [
+    "...", "&&", "||", "==", "!=", "<=", ">=", "=~", "!~", "&", "|", "+", "-", "*", "/", "<", ">",
+    "!", "=", "(", ")", "[", "]", "{", "}", ":", ".", "?",
+];
+
+/// The kind of a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Tok {
+    /// An identifier or keyword.
+    Ident,
+    /// An integer literal, including multipliers such as `1Ki`.
+    Int,
+    /// A floating point literal.
+    Float,
+    /// A string or bytes literal, with the spans of the expressions
+    /// interpolated into it.
+    String(Vec<Span>),
+    /// `_|_`.
+    Bottom,
+    /// An attribute, such as `@go(Name)`.
+    Attr,
+    /// A comma, written or inserted at the end of a line; inserted commas
+    /// have an empty span.
+    Comma,
+    /// Punctuation or an operator.
+    Punct(&'static str),
+    /// The end of the source.
+    Eof,
+}
+
+/// A token and the source it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Token {
+    /// The kind of the token.
+    pub(super) tok: Tok,
+    /// Where the token is.
+    pub(super) span: Span,
+}
+
+/// A syntax error: the byte offset it was found at and its message.
+pub(super) type ParseError = (usize, String);
+
+/// Splits `src[span]` into tokens, ending with [`Tok::Eof`], and adds the
+/// comments to `comments`.
+pub(super) fn tokenize(
+    src: &str,
+    span: Span,
+    comments: &mut Vec<Comment>,
+) -> Result<Vec<Token>, ParseError> {
+    let mut lexer = Lexer {
+        src,
+        pos: span.start,
+        end: span.end.min(src.len()),
+        comments,
+    };
+    let mut tokens = Vec::new();
+    loop {
+        if let Some(at) = lexer.skip_space()
+            && tokens.last().is_some_and(inserts_comma)
+        {
+            tokens.push(Token {
+                tok: Tok::Comma,
+                span: Span { start: at, end: at },
+            });
+        }
+        if lexer.pos >= lexer.end {
+            if tokens.last().is_some_and(inserts_comma) {
+                tokens.push(Token {
+                    tok: Tok::Comma,
+                    span: Span {
+                        start: lexer.end,
+                        end: lexer.end,
+                    },
+                });
+            }
+            tokens.push(Token {
+                tok: Tok::Eof,
+                span: Span {
+                    start: lexer.end,
+                    end: lexer.end,
+                },
+            });
+            return Ok(tokens);
+        }
+        tokens.push(lexer.token()?);
+    }
+}
+
+/// Returns `true` if a comma is inserted after `token` at the end of a
+/// line.
+fn inserts_comma(token: &Token) -> bool {
+    match token.tok {
+        Tok::Ident | Tok::Int | Tok::Float | Tok::String(_) | Tok::Bottom | Tok::Attr => true,
+        Tok::Punct(p) => matches!(p, ")" | "]" | "}" | "?" | "..."),
+        Tok::Comma | Tok::Eof => false,
+    }
+}
+
+/// Returns `true` if `b` may continue an identifier.
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'_' | b'$')
+}
+
+/// A cursor over the source being tokenized.
+struct Lexer<'a> {
+    /// The whole source.
+    src: &'a str,
+    /// Byte offset of the cursor.
+    pos: usize,
+    /// Byte offset the tokenized part of the source ends at.
+    end: usize,
+    /// The comments read so far.
+    comments: &'a mut Vec<Comment>,
+}
+
+impl<'a> Lexer<'a> {
+    /// Returns the byte `n` bytes after the cursor.
+    fn peek_at(
+        &self,
+        n: usize,
+    ) -> Option<u8> {
+        let at = self.pos.saturating_add(n);
+        if at >= self.end {
+            return None;
+        }
+        self.src.as_bytes().get(at).copied()
+    }
+
+    /// Returns the source from the cursor to the end.
+    fn rest(&self) -> &'a str {
+        self.src.get(self.pos..self.end).unwrap_or_default()
+    }
+
+    /// Moves the cursor `n` bytes on.
+    fn bump(
+        &mut self,
+        n: usize,
+    ) {
+        self.pos = self.pos.saturating_add(n).min(self.end);
+    }
+
+    /// Returns a token of kind `tok` from `start` to the cursor.
+    fn token_from(
+        &self,
+        tok: Tok,
+        start: usize,
+    ) -> Token {
+        Token {
+            tok,
+            span: Span {
+                start,
+                end: self.pos,
+            },
+        }
+    }
+
+    /// Skips blank space and comments, returning the offset of the first
+    /// newline skipped, if any.
+    fn skip_space(&mut self) -> Option<usize> {
+        let mut newline = None;
+        while let Some(c) = self.peek_at(0) {
+            match c {
+                b'\n' => {
+                    newline = newline.or(Some(self.pos));
+                    self.bump(1);
+                },
+                b' ' | b'\t' | b'\r' => self.bump(1),
+                b'/' if self.peek_at(1) == Some(b'/') => {
+                    let start = self.pos;
+                    let len = self.rest().find('\n').unwrap_or(self.rest().len());
+                    self.bump(len);
+                    let line = self.src.get(start..self.pos).unwrap_or_default();
+                    let text = line.get(2..).unwrap_or_default();
+                    self.comments.push(Comment {
+                        text: text
+                            .strip_prefix(' ')
+                            .unwrap_or(text)
+                            .trim_end()
+                            .to_string(),
+                        span: Span {
+                            start,
+                            end: self.pos,
+                        },
+                    });
+                },
+                _ => break,
+            }
+        }
+        newline
+    }
+
+    /// Reads the token at the cursor.
+    fn token(&mut self) -> Result<Token, ParseError> {
+        let start = self.pos;
+        let rest = self.rest();
+        if rest.starts_with("_|_") {
+            self.bump(3);
+            return Ok(self.token_from(Tok::Bottom, start));
+        }
+        let hashes = rest
+            .len()
+            .saturating_sub(rest.trim_start_matches('#').len());
+        if matches!(self.peek_at(hashes), Some(b'"' | b'\'')) {
+            return self.string(hashes);
+        }
+        let first = self.peek_at(0).unwrap_or_default();
+        if first.is_ascii_digit()
+            || (first == b'.' && self.peek_at(1).is_some_and(|b| b.is_ascii_digit()))
+        {
+            return Ok(self.number());
+        }
+        if first == b'#' || is_ident_byte(first) {
+            // Definitions and hidden definitions, `#A` and `_#A`.
+            let prefix = rest
+                .bytes()
+                .take_while(|&b| matches!(b, b'_' | b'#'))
+                .count();
+            let len = rest
+                .bytes()
+                .skip(prefix)
+                .take_while(|&b| is_ident_byte(b))
+                .count();
+            self.bump(prefix.saturating_add(len));
+            return Ok(self.token_from(Tok::Ident, start));
+        }
+        if first == b'@'
+            && self
+                .peek_at(1)
+                .is_some_and(|b| b == b'_' || b.is_ascii_alphabetic())
+        {
+            return self.attribute();
+        }
+        if first == b',' {
+            self.bump(1);
+            return Ok(self.token_from(Tok::Comma, start));
+        }
+        let Some(punct) = PUNCT.iter().find(|p| rest.starts_with(**p)) else {
+            let c = rest.chars().next().unwrap_or_default();
+            return Err((start, format!("unexpected character {c:?}")));
+        };
+        self.bump(punct.len());
+        Ok(self.token_from(Tok::Punct(punct), start))
+    }
+
+    /// Reads a number.
+    fn number(&mut self) -> Token {
+        let start = self.pos;
+        let radix = self.peek_at(0) == Some(b'0')
+            && matches!(
+                self.peek_at(1),
+                Some(b'x' | b'X' | b'o' | b'O' | b'b' | b'B')
+            );
+        if radix {
+            self.bump(2);
+            let len = self
+                .rest()
+                .bytes()
+                .take_while(|&b| b.is_ascii_hexdigit() || b == b'_')
+                .count();
+            self.bump(len);
+            return self.token_from(Tok::Int, start);
+        }
+        let digits = |lexer: &mut Self| {
+            let len = lexer
+                .rest()
+                .bytes()
+                .take_while(|&b| b.is_ascii_digit() || b == b'_')
+                .count();
+            lexer.bump(len);
+        };
+        let mut float = false;
+        digits(self);
+        if self.peek_at(0) == Some(b'.') && self.peek_at(1) != Some(b'.') {
+            float = true;
+            self.bump(1);
+            digits(self);
+        }
+        if matches!(self.peek_at(0), Some(b'e' | b'E')) {
+            let sign = usize::from(matches!(self.peek_at(1), Some(b'+' | b'-')));
+            if self
+                .peek_at(sign.saturating_add(1))
+                .is_some_and(|b| b.is_ascii_digit())
+            {
+                float = true;
+                self.bump(sign.saturating_add(1));
+                digits(self);
+            }
+        }
+        // Multipliers such as `1.5G` and `1Ki` make integers.
+        if matches!(self.peek_at(0), Some(b'K' | b'M' | b'G' | b'T' | b'P')) {
+            float = false;
+            self.bump(1);
+            if self.peek_at(0) == Some(b'i') {
+                self.bump(1);
+            }
+        }
+        self.token_from(if float { Tok::Float } else { Tok::Int }, start)
+    }
+
+    /// Reads a string or bytes literal opened by `hashes` `#` characters,
+    /// recording the spans of its interpolations.
+    fn string(
+        &mut self,
+        hashes: usize,
+    ) -> Result<Token, ParseError> {
+        let start = self.pos;
+        self.bump(hashes);
+        let quote = self.peek_at(0).unwrap_or(b'"');
+        let triple = [quote; 3];
+        let multiline = self.rest().as_bytes().starts_with(&triple);
+        self.bump(if multiline { 3 } else { 1 });
+        let closes = |lexer: &Self, n: usize| {
+            (0..hashes).all(|i| lexer.peek_at(n.saturating_add(i)) == Some(b'#'))
+        };
+        let mut interpolations = Vec::new();
+        loop {
+            let Some(c) = self.peek_at(0) else {
+                return Err((start, "string literal not terminated".to_string()));
+            };
+            if c == b'\\' && closes(self, 1) {
+                self.bump(hashes.saturating_add(1));
+                if self.peek_at(0) == Some(b'(') {
+                    self.bump(1);
+                    interpolations.push(self.interpolation()?);
+                } else {
+                    self.bump(self.rest().chars().next().map_or(1, char::len_utf8));
+                }
+            } else if multiline && self.rest().as_bytes().starts_with(&triple) && closes(self, 3) {
+                self.bump(hashes.saturating_add(3));
+                break;
+            } else if !multiline && c == quote && closes(self, 1) {
+                self.bump(hashes.saturating_add(1));
+                break;
+            } else if !multiline && c == b'\n' {
+                return Err((start, "string literal not terminated".to_string()));
+            } else {
+                self.bump(self.rest().chars().next().map_or(1, char::len_utf8));
+            }
+        }
+        Ok(self.token_from(Tok::String(interpolations), start))
+    }
+
+    /// Reads the expression of an interpolation up to its closing `)`,
+    /// returning its span.
+    fn interpolation(&mut self) -> Result<Span, ParseError> {
+        let start = self.pos;
+        let mut depth = 0_usize;
+        loop {
+            self.skip_space();
+            if self.pos >= self.end {
+                return Err((start, "interpolation not terminated".to_string()));
+            }
+            let before = self.pos;
+            let token = self.token()?;
+            match token.tok {
+                Tok::Punct("(" | "[" | "{") => depth = depth.saturating_add(1),
+                Tok::Punct(")") if depth == 0 => {
+                    return Ok(Span { start, end: before });
+                },
+                Tok::Punct(")" | "]" | "}") => depth = depth.saturating_sub(1),
+                _ => {},
+            }
+        }
+    }
+
+    /// Reads an attribute, such as `@go(Name,type=string)`.
+    fn attribute(&mut self) -> Result<Token, ParseError> {
+        let start = self.pos;
+        self.bump(1);
+        let len = self
+            .rest()
+            .bytes()
+            .take_while(|&b| is_ident_byte(b))
+            .count();
+        self.bump(len);
+        if self.peek_at(0) != Some(b'(') {
+            return Err((self.pos, "expected '(' after attribute name".to_string()));
+        }
+        let mut depth = 0_usize;
+        let mut quoted = false;
+        while let Some(c) = self.peek_at(0) {
+            self.bump(1);
+            match c {
+                b'\\' if quoted => self.bump(1),
+                b'"' => quoted = !quoted,
+                b'(' if !quoted => depth = depth.saturating_add(1),
+                b')' if !quoted => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return Ok(self.token_from(Tok::Attr, start));
+                    }
+                },
+                b'\n' => break,
+                _ => {},
+            }
+        }
+        Err((start, "attribute not terminated".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{Tok, tokenize};
+    use crate::Span;
+
+    /// Returns the kinds and texts of the tokens of `src`.
+    fn tokens(src: &str) -> Vec<(Tok, &str)> {
+        let span = Span {
+            start: 0,
+            end: src.len(),
+        };
+        tokenize(src, span, &mut Vec::new())
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.tok, span_text(src, t.span)))
+            .collect()
+    }
+
+    /// Returns the text of `span`.
+    fn span_text(
+        src: &str,
+        span: Span,
+    ) -> &str {
+        src.get(span.start..span.end).unwrap()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokens("a: >=1.5 // x\nb?: _|_\n"), [
+            (Tok::Ident, "a"),
+            (Tok::Punct(":"), ":"),
+            (Tok::Punct(">="), ">="),
+            (Tok::Float, "1.5"),
+            (Tok::Comma, ""),
+            (Tok::Ident, "b"),
+            (Tok::Punct("?"), "?"),
+            (Tok::Punct(":"), ":"),
+            (Tok::Bottom, "_|_"),
+            (Tok::Comma, ""),
+            (Tok::Eof, ""),
+        ]);
+        assert_eq!(tokens("a &\n\t#B @go(B)"), [
+            (Tok::Ident, "a"),
+            (Tok::Punct("&"), "&"),
+            (Tok::Ident, "#B"),
+            (Tok::Attr, "@go(B)"),
+            (Tok::Comma, ""),
+            (Tok::Eof, ""),
+        ]);
+    }
+
+    #[test_case("0x1F"   => Tok::Int;   "hex")]
+    #[test_case("1_000"  => Tok::Int;   "underscores")]
+    #[test_case("1.5Gi"  => Tok::Int;   "multiplier")]
+    #[test_case(".5e-3"  => Tok::Float; "exponent")]
+    #[test_case("_#Def"  => Tok::Ident; "hidden_definition")]
+    fn test_token(src: &str) -> Tok {
+        tokens(src).swap_remove(0).0
+    }
+
+    #[test]
+    fn test_string() {
+        let src = r##"#"a"\#(x + "\(y)")"# 'b'"##;
+        let mut comments = Vec::new();
+        let span = Span {
+            start: 0,
+            end: src.len(),
+        };
+        let tokens = tokenize(src, span, &mut comments).unwrap();
+        let Some(Tok::String(interpolations)) = tokens.first().map(|t| &t.tok) else {
+            panic!("{tokens:?}");
+        };
+        let texts: Vec<&str> = interpolations.iter().map(|s| span_text(src, *s)).collect();
+        assert_eq!(texts, [r#"x + "\(y)""#]);
+        assert_eq!(tokens.get(1).map(|t| span_text(src, t.span)), Some("'b'"));
+        assert!(tokenize("\"a", Span { start: 0, end: 2 }, &mut comments).is_err());
+    }
+}
@@ -0,0 +1,540 @@
+//! Parsing CUE source into a syntax tree without evaluating it.
+//!
+//! [`parse`] reads a CUE file into a [`File`] of declarations: the package
+//! clause, imports, fields with their labels and markers, embedded
+//! expressions, `let` declarations, comprehensions and attributes.
+//! Expressions are kept as written, as trees of operators, selectors, calls
+//! and literals, and every node records the [`Span`] of source it was read
+//! from.  Comments are attached to the declarations they document and are
+//! also listed in source order on the file:
+//!
+//! ```
+//! # fn main() -> Result<(), cue_rs_ast::SyntaxError> {
+//! use cue_rs_ast::{self as ast, Constraint, DeclKind, ExprKind};
+//!
+//! let src = r#"
+//! package service
+//!
+//! // Listening port.
+//! port?: int & >0 | *8080
+//! "#;
+//! let file = ast::parse(src)?;
+//! assert_eq!(file.package().map(|p| p.name.as_str()), Some("service"));
+//! for (decl, field) in file.fields() {
+//!     assert_eq!(field.label.span().text(src), "port");
+//!     assert_eq!(field.constraint, Constraint::Optional);
+//!     assert_eq!(decl.doc_text(), "Listening port.");
+//!     assert!(matches!(field.value.kind, ExprKind::Binary { .. }));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Parsing needs neither a `cue-rs` context nor libcue, so linters and
+//! refactoring tools can inspect source cheaply.  It checks syntax only: a
+//! file that parses may still fail to compile.
+
+mod lexer;
+mod parser;
+
+/// CUE source that is not syntactically valid.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("syntax error at {line}:{column}: {message}")]
+pub struct SyntaxError {
+    /// Line of the error, starting at 1.
+    pub line: usize,
+    /// Column of the error in characters, starting at 1.
+    pub column: usize,
+    /// What is wrong.
+    pub message: String,
+}
+
+/// A byte range of the parsed source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// Offset of the first byte.
+    pub start: usize,
+    /// Offset just past the last byte.
+    pub end: usize,
+}
+
+impl Span {
+    /// Returns the text the span covers in `src`, the source it was parsed
+    /// from.
+    #[must_use]
+    pub fn text(
+        self,
+        src: &str,
+    ) -> &str {
+        src.get(self.start..self.end).unwrap_or_default()
+    }
+}
+
+/// A `//` comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// The text of the comment, without `//` and the space after it.
+    pub text: String,
+    /// Where the comment is.
+    pub span: Span,
+}
+
+/// A parsed CUE file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct File {
+    /// The declarations of the file, in source order.
+    pub decls: Vec<Decl>,
+    /// Every comment of the file, in source order.
+    pub comments: Vec<Comment>,
+}
+
+impl File {
+    /// Returns the name of the package clause, if the file has one.
+    #[must_use]
+    pub fn package(&self) -> Option<&Ident> {
+        self.decls.iter().find_map(|decl| {
+            match &decl.kind {
+                DeclKind::Package(name) => Some(name),
+                _ => None,
+            }
+        })
+    }
+
+    /// Returns the fields declared at the top level of the file, with their
+    /// declarations.
+    pub fn fields(&self) -> impl Iterator<Item = (&Decl, &Field)> {
+        fields(&self.decls)
+    }
+}
+
+/// A declaration of a file or struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decl {
+    /// What is declared.
+    pub kind: DeclKind,
+    /// The comment lines directly in front of the declaration.
+    pub doc: Vec<Comment>,
+    /// The comment after the declaration on its last line, if any.
+    pub comment: Option<Comment>,
+    /// Where the declaration is, without its comments.
+    pub span: Span,
+}
+
+impl Decl {
+    /// Returns the documentation of the declaration: its doc comment lines
+    /// joined by newlines, or else the comment after it.
+    #[must_use]
+    pub fn doc_text(&self) -> String {
+        if self.doc.is_empty() {
+            return self
+                .comment
+                .as_ref()
+                .map(|c| c.text.clone())
+                .unwrap_or_default();
+        }
+        self.doc
+            .iter()
+            .map(|c| c.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The kinds of declarations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeclKind {
+    /// The package clause, `package name`.
+    Package(Ident),
+    /// An import declaration, `import "path"` or a parenthesized list of
+    /// imports.
+    Import(Vec<ImportSpec>),
+    /// A field, `label: value`.
+    Field(Field),
+    /// A `let` declaration, `let name = value`.
+    Let {
+        /// The declared name.
+        name: Ident,
+        /// The bound expression.
+        value: Expr,
+    },
+    /// An embedded expression, such as `#Base` in a struct.
+    Embed(Expr),
+    /// A comprehension, such as `if x { a: 1 }`.
+    Comprehension(Comprehension),
+    /// `...` or `...T`, which opens a struct.
+    Ellipsis(Option<Expr>),
+    /// An attribute declared on its own, such as `@protobuf(proto3)`.
+    Attribute(Attribute),
+}
+
+/// A single import of an import declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportSpec {
+    /// The name the package is imported as, if given.
+    pub name: Option<Ident>,
+    /// The import path, unquoted.
+    pub path: String,
+    /// Where the import is.
+    pub span: Span,
+}
+
+/// An identifier, such as `name`, `#Def` or `_hidden`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ident {
+    /// The identifier.
+    pub name: String,
+    /// Where the identifier is.
+    pub span: Span,
+}
+
+/// A field declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// The alias of the field, `X` in `X=label: value`.
+    pub alias: Option<Ident>,
+    /// The label.
+    pub label: Label,
+    /// Whether the field is regular, optional or required.
+    pub constraint: Constraint,
+    /// The value.  A chained declaration such as `a: b: 1` has a struct
+    /// value with the single field `b: 1`.
+    pub value: Expr,
+    /// The attributes after the value, such as `@go(Name)`.
+    pub attributes: Vec<Attribute>,
+}
+
+/// How a field must be present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    /// A regular field, `a: x`.
+    Regular,
+    /// An optional field, `a?: x`.
+    Optional,
+    /// A required field, `a!: x`.
+    Required,
+}
+
+/// The label of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Label {
+    /// An identifier, `name: x`.
+    Ident(Ident),
+    /// A quoted label, `"x-y": x`, possibly interpolated.
+    String(Expr),
+    /// A pattern constraint, `[string]: x` or `[Name=string]: x`.
+    Pattern {
+        /// The alias of the matched label, `Name` in `[Name=string]`.
+        alias: Option<Ident>,
+        /// The pattern.
+        expr: Expr,
+        /// Where the label is, including its brackets.
+        span: Span,
+    },
+    /// A dynamic label, `(expr): x`.
+    Dynamic {
+        /// The expression evaluating to the label.
+        expr: Expr,
+        /// Where the label is, including its parentheses.
+        span: Span,
+    },
+}
+
+impl Label {
+    /// Returns where the label is.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Ident(ident) => ident.span,
+            Self::String(expr) => expr.span,
+            Self::Pattern { span, .. } | Self::Dynamic { span, .. } => *span,
+        }
+    }
+
+    /// Returns the name of an identifier label, such as `#Service`.
+    #[must_use]
+    pub fn ident(&self) -> Option<&str> {
+        match self {
+            Self::Ident(ident) => Some(&ident.name),
+            _ => None,
+        }
+    }
+}
+
+/// An attribute, such as `@go(Name,type=string)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    /// The name of the attribute, `go`.
+    pub name: String,
+    /// The text between the parentheses, `Name,type=string`.
+    pub body: String,
+    /// Where the attribute is.
+    pub span: Span,
+}
+
+/// A comprehension: clauses followed by the struct they produce.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comprehension {
+    /// The `for`, `if` and `let` clauses, in order.
+    pub clauses: Vec<Clause>,
+    /// The struct produced for each iteration.
+    pub body: Expr,
+}
+
+/// A clause of a comprehension.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Clause {
+    /// `for key, value in source` or `for value in source`.
+    For {
+        /// The key or index variable, if declared.
+        key: Option<Ident>,
+        /// The value variable.
+        value: Ident,
+        /// The iterated expression.
+        source: Expr,
+    },
+    /// `if condition`.
+    If(Expr),
+    /// `let name = value`.
+    Let {
+        /// The declared name.
+        name: Ident,
+        /// The bound expression.
+        value: Expr,
+    },
+}
+
+/// An expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr {
+    /// The kind of expression.
+    pub kind: ExprKind,
+    /// Where the expression is.
+    pub span: Span,
+}
+
+/// The kinds of expressions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprKind {
+    /// An identifier, including `_` (top).
+    Ident(String),
+    /// `_|_` (bottom).
+    Bottom,
+    /// A literal, kept as written.
+    Lit(LitKind),
+    /// A string or bytes literal with interpolations, such as `"\(a)-b"`;
+    /// the literal is the text of the span and the interpolated
+    /// expressions are listed in order.
+    Interpolation(Vec<Expr>),
+    /// A struct literal, `{ decls }`.
+    Struct(Vec<Decl>),
+    /// A list literal, `[elems]`.
+    List(Vec<Expr>),
+    /// `...` or `...T` in a list, allowing further elements.
+    Ellipsis(Option<Box<Expr>>),
+    /// A comprehension in a list, `[for x in xs { x }]`.
+    Comprehension(Box<Comprehension>),
+    /// A parenthesized expression.
+    Paren(Box<Expr>),
+    /// A unary expression, such as `-x`, `>0` or the default marker `*x`.
+    Unary {
+        /// The operator.
+        op: UnaryOp,
+        /// The operand.
+        expr: Box<Expr>,
+    },
+    /// A binary expression, such as `a & b`.
+    Binary {
+        /// The operator.
+        op: BinaryOp,
+        /// The left operand.
+        lhs: Box<Expr>,
+        /// The right operand.
+        rhs: Box<Expr>,
+    },
+    /// A selector, `x.name`.
+    Selector {
+        /// The selected expression.
+        expr: Box<Expr>,
+        /// The selected label, as written.
+        label: Ident,
+    },
+    /// An index, `x[i]`.
+    Index {
+        /// The indexed expression.
+        expr: Box<Expr>,
+        /// The index.
+        index: Box<Expr>,
+    },
+    /// A call, `f(args)`.
+    Call {
+        /// The called function.
+        func: Box<Expr>,
+        /// The arguments.
+        args: Vec<Expr>,
+    },
+}
+
+/// The kinds of literals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LitKind {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool,
+    /// An integer, such as `1`, `0x1F` or `1Ki`.
+    Int,
+    /// A floating point number, such as `1.5` or `1e3`.
+    Float,
+    /// A string, `"..."`, `"""..."""` or `#"..."#`.
+    String,
+    /// Bytes, `'...'`.
+    Bytes,
+}
+
+/// Unary operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `+`.
+    Plus,
+    /// `-`.
+    Minus,
+    /// `!`.
+    Not,
+    /// `*`, marking a default.
+    Default,
+    /// `<`.
+    Lt,
+    /// `<=`.
+    Le,
+    /// `>`.
+    Gt,
+    /// `>=`.
+    Ge,
+    /// `!=`.
+    Ne,
+    /// `=~`.
+    Match,
+    /// `!~`.
+    NotMatch,
+}
+
+/// Binary operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    /// `|`, disjunction.
+    Or,
+    /// `&`, unification.
+    And,
+    /// `||`.
+    LogicalOr,
+    /// `&&`.
+    LogicalAnd,
+    /// `==`.
+    Eq,
+    /// `!=`.
+    Ne,
+    /// `<`.
+    Lt,
+    /// `<=`.
+    Le,
+    /// `>`.
+    Gt,
+    /// `>=`.
+    Ge,
+    /// `=~`.
+    Match,
+    /// `!~`.
+    NotMatch,
+    /// `+`.
+    Add,
+    /// `-`.
+    Sub,
+    /// `*`.
+    Mul,
+    /// `/`.
+    Div,
+}
+
+/// Parses the CUE file `src`.
+///
+/// # Errors
+///
+/// Returns a [`SyntaxError`] if `src` is not syntactically valid CUE.
+pub fn parse(src: &str) -> Result<File, SyntaxError> {
+    parser::parse_file(src).map_err(|error| syntax_error(src, error))
+}
+
+/// Parses the CUE expression `src`, such as `int & >0 | *1`.
+///
+/// # Errors
+///
+/// Returns a [`SyntaxError`] if `src` is not a single syntactically valid
+/// expression.
+pub fn parse_expr(src: &str) -> Result<Expr, SyntaxError> {
+    parser::parse_expr(src).map_err(|error| syntax_error(src, error))
+}
+
+/// Returns the fields of `decls`, with their declarations.
+fn fields(decls: &[Decl]) -> impl Iterator<Item = (&Decl, &Field)> {
+    decls.iter().filter_map(|decl| {
+        match &decl.kind {
+            DeclKind::Field(field) => Some((decl, field)),
+            _ => None,
+        }
+    })
+}
+
+/// Builds the [`SyntaxError`] of a syntax error at `offset` of `src`.
+fn syntax_error(
+    src: &str,
+    (offset, message): lexer::ParseError,
+) -> SyntaxError {
+    let before = src.get(..offset).unwrap_or(src);
+    let line_start = before.rfind('\n').map_or(0, |i| i.saturating_add(1));
+    SyntaxError {
+        line: before.matches('\n').count().saturating_add(1),
+        column: before
+            .get(line_start..)
+            .unwrap_or_default()
+            .chars()
+            .count()
+            .saturating_add(1),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{BinaryOp, ExprKind, SyntaxError, parse, parse_expr};
+
+    #[test_case("a: {"        => (1, 5);  "unclosed_struct")]
+    #[test_case("a: 1\nb c"   => (2, 3);  "missing_comma")]
+    #[test_case("a: \"x\n"    => (1, 4);  "unterminated_string")]
+    #[test_case("a: 1 ^ 2"    => (1, 6);  "unexpected_character")]
+    fn test_syntax_error(src: &str) -> (usize, usize) {
+        match parse(src) {
+            Err(SyntaxError { line, column, .. }) => (line, column),
+            other => panic!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_expr() {
+        let expr = parse_expr("int & >0 | *1").unwrap();
+        let ExprKind::Binary {
+            op: BinaryOp::Or,
+            lhs,
+            ..
+        } = expr.kind
+        else {
+            panic!("{expr:?}");
+        };
+        assert!(matches!(lhs.kind, ExprKind::Binary {
+            op: BinaryOp::And,
+            ..
+        }));
+        assert!(parse_expr("a: 1").is_err());
+    }
+}
@@ -0,0 +1,1030 @@
+//! A recursive descent parser over the tokens of [`lexer`](super::lexer).
+//!
+//! Binary operators are parsed by precedence climbing, from `|` binding
+//! loosest up to `*` and `/`.  Fields are told apart from embedded
+//! expressions by reading a label and backtracking if no `:` follows it.
+
+use super::{
+    Attribute, BinaryOp, Clause, Comment, Comprehension, Constraint, Decl, DeclKind, Expr,
+    ExprKind, Field, File, Ident, ImportSpec, Label, LitKind, Span, UnaryOp,
+    lexer::{ParseError, Tok, Token, tokenize},
+};
+
+/// Maximum nesting depth of expressions and declarations, keeping deeply
+/// nested input from overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+/// The token returned past the end of the tokens.
+static EOF: Token = Token {
+    tok: Tok::Eof,
+    span: Span { start: 0, end: 0 },
+};
+
+/// Parses the file `src`.
+pub(super) fn parse_file(src: &str) -> Result<File, ParseError> {
+    let mut comments = Vec::new();
+    let span = Span {
+        start: 0,
+        end: src.len(),
+    };
+    let tokens = tokenize(src, span, &mut comments)?;
+    let decls = Parser::new(src, tokens, &comments, 0).decls(true)?;
+    Ok(File { decls, comments })
+}
+
+/// Parses the expression `src`.
+pub(super) fn parse_expr(src: &str) -> Result<Expr, ParseError> {
+    let span = Span {
+        start: 0,
+        end: src.len(),
+    };
+    Parser::sub_expr(src, span, 0)
+}
+
+/// Returns the operator and precedence of the binary operator `punct`.
+fn binary_op(punct: &str) -> Option<(BinaryOp, u8)> {
+    Some(match punct {
+        "|" => (BinaryOp::Or, 1),
+        "&" => (BinaryOp::And, 2),
+        "||" => (BinaryOp::LogicalOr, 3),
+        "&&" => (BinaryOp::LogicalAnd, 4),
+        "==" => (BinaryOp::Eq, 5),
+        "!=" => (BinaryOp::Ne, 5),
+        "<" => (BinaryOp::Lt, 5),
+        "<=" => (BinaryOp::Le, 5),
+        ">" => (BinaryOp::Gt, 5),
+        ">=" => (BinaryOp::Ge, 5),
+        "=~" => (BinaryOp::Match, 5),
+        "!~" => (BinaryOp::NotMatch, 5),
+        "+" => (BinaryOp::Add, 6),
+        "-" => (BinaryOp::Sub, 6),
+        "*" => (BinaryOp::Mul, 7),
+        "/" => (BinaryOp::Div, 7),
+        _ => return None,
+    })
+}
+
+/// Returns the unary operator `punct`.
+fn unary_op(punct: &str) -> Option<UnaryOp> {
+    Some(match punct {
+        "+" => UnaryOp::Plus,
+        "-" => UnaryOp::Minus,
+        "!" => UnaryOp::Not,
+        "*" => UnaryOp::Default,
+        "<" => UnaryOp::Lt,
+        "<=" => UnaryOp::Le,
+        ">" => UnaryOp::Gt,
+        ">=" => UnaryOp::Ge,
+        "!=" => UnaryOp::Ne,
+        "=~" => UnaryOp::Match,
+        "!~" => UnaryOp::NotMatch,
+        _ => return None,
+    })
+}
+
+/// Returns the comment lines directly in front of the declaration at
+/// `start`, after the token ending at `prev_end`: comments alone on their
+/// lines, with no blank line between them and the declaration.
+fn doc_comments(
+    src: &str,
+    comments: &[Comment],
+    prev_end: usize,
+    start: usize,
+) -> Vec<Comment> {
+    let before = comments.partition_point(|c| c.span.start < start);
+    let mut next = start;
+    let mut group = Vec::new();
+    for comment in comments.get(..before).unwrap_or_default().iter().rev() {
+        let gap = src.get(comment.span.end..next).unwrap_or_default();
+        let line = src.get(..comment.span.start).unwrap_or_default();
+        let alone = line
+            .rsplit('\n')
+            .next()
+            .is_none_or(|indent| indent.trim().is_empty());
+        if comment.span.start < prev_end
+            || !alone
+            || !gap.trim().is_empty()
+            || gap.matches('\n').count() > 1
+        {
+            break;
+        }
+        group.push(comment.clone());
+        next = comment.span.start;
+    }
+    group.reverse();
+    group
+}
+
+/// Returns the comment on the same line after the declaration ending at
+/// `end`.
+fn trailing_comment(
+    src: &str,
+    comments: &[Comment],
+    end: usize,
+) -> Option<Comment> {
+    let comment = comments.get(comments.partition_point(|c| c.span.start < end))?;
+    let gap = src.get(end..comment.span.start).unwrap_or_default();
+    (!gap.contains('\n')).then(|| comment.clone())
+}
+
+/// The parser state.
+struct Parser<'a> {
+    /// The whole source.
+    src: &'a str,
+    /// The tokens being parsed, ending with [`Tok::Eof`].
+    tokens: Vec<Token>,
+    /// Index of the current token.
+    pos: usize,
+    /// The comments of the file, in source order.
+    comments: &'a [Comment],
+    /// Offset just past the last token read.
+    last_end: usize,
+    /// Current nesting depth.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a parser over `tokens` of `src`, nested `depth` deep.
+    fn new(
+        src: &'a str,
+        tokens: Vec<Token>,
+        comments: &'a [Comment],
+        depth: usize,
+    ) -> Self {
+        Self {
+            src,
+            tokens,
+            pos: 0,
+            comments,
+            last_end: 0,
+            depth,
+        }
+    }
+
+    /// Parses `src[span]` as a single expression, such as an
+    /// interpolation, nested `depth` deep.
+    fn sub_expr(
+        src: &'a str,
+        span: Span,
+        depth: usize,
+    ) -> Result<Expr, ParseError> {
+        let tokens = tokenize(src, span, &mut Vec::new())?;
+        let mut parser = Parser::new(src, tokens, &[], depth);
+        let expr = parser.expr()?;
+        if parser.peek().tok == Tok::Comma && parser.peek().span.start == parser.peek().span.end {
+            parser.bump();
+        }
+        if parser.peek().tok != Tok::Eof {
+            return parser.error(format!(
+                "expected end of expression, found {}",
+                parser.found()
+            ));
+        }
+        Ok(expr)
+    }
+
+    /// Returns the current token.
+    fn peek(&self) -> &Token {
+        self.peek_nth(0)
+    }
+
+    /// Returns the token `n` tokens after the current one.
+    fn peek_nth(
+        &self,
+        n: usize,
+    ) -> &Token {
+        self.tokens
+            .get(self.pos.saturating_add(n))
+            .or(self.tokens.last())
+            .unwrap_or(&EOF)
+    }
+
+    /// Returns the source of `token`.
+    fn text(
+        &self,
+        token: &Token,
+    ) -> &'a str {
+        token.span.text(self.src)
+    }
+
+    /// Returns `true` if the current token is the punctuation `punct`.
+    fn at(
+        &self,
+        punct: &str,
+    ) -> bool {
+        matches!(self.peek().tok, Tok::Punct(p) if p == punct)
+    }
+
+    /// Returns `true` if the current token is the identifier `word`.
+    fn at_word(
+        &self,
+        word: &str,
+    ) -> bool {
+        self.peek().tok == Tok::Ident && self.text(self.peek()) == word
+    }
+
+    /// Returns `true` if the current token ends a declaration.
+    fn at_decl_end(&self) -> bool {
+        matches!(self.peek().tok, Tok::Comma | Tok::Eof) || self.at("}")
+    }
+
+    /// Reads the current token.
+    fn bump(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token.tok != Tok::Eof {
+            self.pos = self.pos.saturating_add(1);
+        }
+        if token.span.start < token.span.end {
+            self.last_end = token.span.end;
+        }
+        token
+    }
+
+    /// Describes the current token for an error message.
+    fn found(&self) -> String {
+        let token = self.peek();
+        match token.tok {
+            Tok::Eof => "end of input".to_string(),
+            Tok::Comma if token.span.start == token.span.end => "newline".to_string(),
+            _ => format!("'{}'", self.text(token)),
+        }
+    }
+
+    /// Returns a syntax error at the current token.
+    fn error<T>(
+        &self,
+        message: String,
+    ) -> Result<T, ParseError> {
+        Err((self.peek().span.start, message))
+    }
+
+    /// Reads the punctuation `punct`.
+    fn expect(
+        &mut self,
+        punct: &str,
+    ) -> Result<Span, ParseError> {
+        if !self.at(punct) {
+            return self.error(format!("expected '{punct}', found {}", self.found()));
+        }
+        Ok(self.bump().span)
+    }
+
+    /// Reads an identifier.
+    fn ident(&mut self) -> Result<Ident, ParseError> {
+        if self.peek().tok != Tok::Ident {
+            return self.error(format!("expected identifier, found {}", self.found()));
+        }
+        let token = self.bump();
+        Ok(Ident {
+            name: self.text(&token).to_string(),
+            span: token.span,
+        })
+    }
+
+    /// Runs `f` one level deeper, failing if the input nests too deep.
+    fn nested<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.depth >= MAX_DEPTH {
+            return self.error(format!("nesting deeper than {MAX_DEPTH} levels"));
+        }
+        self.depth = self.depth.saturating_add(1);
+        let result = f(self);
+        self.depth = self.depth.saturating_sub(1);
+        result
+    }
+
+    /// Reads declarations up to the end of the file or, if `file` is
+    /// `false`, the `}` closing the struct.
+    fn decls(
+        &mut self,
+        file: bool,
+    ) -> Result<Vec<Decl>, ParseError> {
+        let mut decls = Vec::new();
+        loop {
+            if self.peek().tok == Tok::Eof || (!file && self.at("}")) {
+                return Ok(decls);
+            }
+            decls.push(self.decl(file)?);
+            match self.peek().tok {
+                Tok::Comma => {
+                    self.bump();
+                },
+                Tok::Eof => {},
+                Tok::Punct("}") if !file => {},
+                _ => return self.error(format!("expected ',' or newline, found {}", self.found())),
+            }
+        }
+    }
+
+    /// Reads a declaration with its comments.
+    fn decl(
+        &mut self,
+        file: bool,
+    ) -> Result<Decl, ParseError> {
+        let prev_end = self.last_end;
+        let start = self.peek().span.start;
+        let kind = self.decl_kind(file)?;
+        let span = Span {
+            start,
+            end: self.last_end,
+        };
+        Ok(Decl {
+            kind,
+            doc: doc_comments(self.src, self.comments, prev_end, start),
+            comment: trailing_comment(self.src, self.comments, span.end),
+            span,
+        })
+    }
+
+    /// Reads a declaration.
+    fn decl_kind(
+        &mut self,
+        file: bool,
+    ) -> Result<DeclKind, ParseError> {
+        if self.at("...") {
+            self.bump();
+            if self.at_decl_end() {
+                return Ok(DeclKind::Ellipsis(None));
+            }
+            return Ok(DeclKind::Ellipsis(Some(self.expr()?)));
+        }
+        if self.peek().tok == Tok::Attr {
+            let token = self.bump();
+            return Ok(DeclKind::Attribute(self.attribute(&token)));
+        }
+        // Keywords are labels when followed by a marker, `:` or `=`.
+        let keyword = self.peek().tok == Tok::Ident
+            && !matches!(self.peek_nth(1).tok, Tok::Punct(":" | "?" | "!" | "="));
+        if keyword {
+            match self.text(self.peek()) {
+                "package" if file => {
+                    self.bump();
+                    return Ok(DeclKind::Package(self.ident()?));
+                },
+                "import" if file => {
+                    self.bump();
+                    return Ok(DeclKind::Import(self.imports()?));
+                },
+                "let" => {
+                    self.bump();
+                    let name = self.ident()?;
+                    self.expect("=")?;
+                    let value = self.expr()?;
+                    return Ok(DeclKind::Let { name, value });
+                },
+                "for" | "if" => return Ok(DeclKind::Comprehension(self.comprehension()?)),
+                _ => {},
+            }
+        }
+        if let Some(field) = self.field()? {
+            return Ok(DeclKind::Field(field));
+        }
+        Ok(DeclKind::Embed(self.expr()?))
+    }
+
+    /// Reads the imports after `import`.
+    fn imports(&mut self) -> Result<Vec<ImportSpec>, ParseError> {
+        if !self.at("(") {
+            return Ok(vec![self.import_spec()?]);
+        }
+        self.bump();
+        let mut specs = Vec::new();
+        while !self.at(")") {
+            specs.push(self.import_spec()?);
+            if self.peek().tok == Tok::Comma {
+                self.bump();
+            } else if !self.at(")") {
+                return self.error(format!("expected ',' or ')', found {}", self.found()));
+            }
+        }
+        self.bump();
+        Ok(specs)
+    }
+
+    /// Reads a single import, `"path"` or `name "path"`.
+    fn import_spec(&mut self) -> Result<ImportSpec, ParseError> {
+        let start = self.peek().span.start;
+        let name = if self.peek().tok == Tok::Ident {
+            Some(self.ident()?)
+        } else {
+            None
+        };
+        if !matches!(self.peek().tok, Tok::String(_)) {
+            return self.error(format!("expected import path, found {}", self.found()));
+        }
+        let token = self.bump();
+        Ok(ImportSpec {
+            name,
+            path: self.text(&token).trim_matches('"').to_string(),
+            span: Span {
+                start,
+                end: token.span.end,
+            },
+        })
+    }
+
+    /// Reads a field, or returns `None` and leaves the cursor unchanged if
+    /// no field starts at it.
+    fn field(&mut self) -> Result<Option<Field>, ParseError> {
+        let (pos, last_end) = (self.pos, self.last_end);
+        let field = self.try_field()?;
+        if field.is_none() {
+            self.pos = pos;
+            self.last_end = last_end;
+        }
+        Ok(field)
+    }
+
+    /// Reads a field, see [`Parser::field`].
+    fn try_field(&mut self) -> Result<Option<Field>, ParseError> {
+        let alias = if self.peek().tok == Tok::Ident && self.peek_nth(1).tok == Tok::Punct("=") {
+            let alias = self.ident()?;
+            self.bump();
+            Some(alias)
+        } else {
+            None
+        };
+        let Some(label) = self.label()? else {
+            return Ok(None);
+        };
+        let constraint = if self.at("?") {
+            self.bump();
+            Constraint::Optional
+        } else if self.at("!") {
+            self.bump();
+            Constraint::Required
+        } else {
+            Constraint::Regular
+        };
+        if !self.at(":") {
+            return Ok(None);
+        }
+        self.bump();
+        let value = self.nested(Self::field_value)?;
+        let mut attributes = Vec::new();
+        while self.peek().tok == Tok::Attr {
+            let token = self.bump();
+            attributes.push(self.attribute(&token));
+        }
+        Ok(Some(Field {
+            alias,
+            label,
+            constraint,
+            value,
+            attributes,
+        }))
+    }
+
+    /// Reads the label of a field, or returns `None` if there is none.
+    fn label(&mut self) -> Result<Option<Label>, ParseError> {
+        let start = self.peek().span.start;
+        match self.peek().tok {
+            Tok::Ident => Ok(Some(Label::Ident(self.ident()?))),
+            Tok::String(_) => Ok(Some(Label::String(self.operand()?))),
+            Tok::Punct(open @ ("[" | "(")) => {
+                self.bump();
+                let alias = if open == "["
+                    && self.peek().tok == Tok::Ident
+                    && self.peek_nth(1).tok == Tok::Punct("=")
+                {
+                    let alias = self.ident()?;
+                    self.bump();
+                    Some(alias)
+                } else {
+                    None
+                };
+                // A list or parenthesized embedding fails to parse as a
+                // label; it is parsed again as an expression.
+                let Ok(expr) = self.expr() else {
+                    return Ok(None);
+                };
+                if !self.at(if open == "[" { "]" } else { ")" }) {
+                    return Ok(None);
+                }
+                let span = Span {
+                    start,
+                    end: self.bump().span.end,
+                };
+                Ok(Some(if open == "[" {
+                    Label::Pattern { alias, expr, span }
+                } else {
+                    Label::Dynamic { expr, span }
+                }))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Reads the value of a field: an expression, or a chained field as in
+    /// `a: b: 1`.
+    fn field_value(&mut self) -> Result<Expr, ParseError> {
+        let start = self.peek().span.start;
+        let Some(field) = self.field()? else {
+            return self.expr();
+        };
+        let span = Span {
+            start,
+            end: self.last_end,
+        };
+        Ok(Expr {
+            kind: ExprKind::Struct(vec![Decl {
+                kind: DeclKind::Field(field),
+                doc: Vec::new(),
+                comment: None,
+                span,
+            }]),
+            span,
+        })
+    }
+
+    /// Builds the attribute of the token `token`.
+    fn attribute(
+        &self,
+        token: &Token,
+    ) -> Attribute {
+        let text = self.text(token).trim_start_matches('@');
+        let (name, body) = text.split_once('(').unwrap_or((text, ""));
+        Attribute {
+            name: name.to_string(),
+            body: body.strip_suffix(')').unwrap_or(body).to_string(),
+            span: token.span,
+        }
+    }
+
+    /// Reads a comprehension: its clauses and its struct.
+    fn comprehension(&mut self) -> Result<Comprehension, ParseError> {
+        let mut clauses = Vec::new();
+        loop {
+            if self.at_word("for") {
+                self.bump();
+                let first = self.ident()?;
+                let (key, value) = if self.peek().tok == Tok::Comma {
+                    self.bump();
+                    (Some(first), self.ident()?)
+                } else {
+                    (None, first)
+                };
+                if !self.at_word("in") {
+                    return self.error(format!("expected 'in', found {}", self.found()));
+                }
+                self.bump();
+                let source = self.expr()?;
+                clauses.push(Clause::For { key, value, source });
+            } else if self.at_word("if") {
+                self.bump();
+                clauses.push(Clause::If(self.expr()?));
+            } else if self.at_word("let") && !clauses.is_empty() {
+                self.bump();
+                let name = self.ident()?;
+                self.expect("=")?;
+                let value = self.expr()?;
+                clauses.push(Clause::Let { name, value });
+            } else {
+                break;
+            }
+            // Clauses may be split over lines.
+            let next = self.peek_nth(1);
+            let continued = matches!(next.tok, Tok::Punct("{"))
+                || (next.tok == Tok::Ident && matches!(self.text(next), "for" | "if" | "let"));
+            if self.peek().tok == Tok::Comma && continued {
+                self.bump();
+            }
+        }
+        if clauses.is_empty() || !self.at("{") {
+            return self.error(format!("expected '{{', found {}", self.found()));
+        }
+        let body = self.operand()?;
+        Ok(Comprehension { clauses, body })
+    }
+
+    /// Reads an expression.
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        self.binary(1)
+    }
+
+    /// Reads a binary expression of operators binding at least as tightly
+    /// as `min`.
+    fn binary(
+        &mut self,
+        min: u8,
+    ) -> Result<Expr, ParseError> {
+        let mut lhs = self.unary()?;
+        loop {
+            let Tok::Punct(punct) = self.peek().tok else {
+                return Ok(lhs);
+            };
+            let Some((op, precedence)) = binary_op(punct).filter(|(_, p)| *p >= min) else {
+                return Ok(lhs);
+            };
+            self.bump();
+            let rhs = self.binary(precedence.saturating_add(1))?;
+            let span = Span {
+                start: lhs.span.start,
+                end: rhs.span.end,
+            };
+            lhs = Expr {
+                kind: ExprKind::Binary {
+                    op,
+                    lhs: Box::new(lhs),
+                    rhs: Box::new(rhs),
+                },
+                span,
+            };
+        }
+    }
+
+    /// Reads a unary expression.
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        self.nested(|p| {
+            let Some(op) = (match p.peek().tok {
+                Tok::Punct(punct) => unary_op(punct),
+                _ => None,
+            }) else {
+                return p.postfix();
+            };
+            let start = p.bump().span.start;
+            let expr = p.unary()?;
+            Ok(Expr {
+                span: Span {
+                    start,
+                    end: expr.span.end,
+                },
+                kind: ExprKind::Unary {
+                    op,
+                    expr: Box::new(expr),
+                },
+            })
+        })
+    }
+
+    /// Reads an operand followed by selectors, indices and calls.
+    fn postfix(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.operand()?;
+        loop {
+            let start = expr.span.start;
+            let kind = if self.at(".") {
+                self.bump();
+                if !matches!(self.peek().tok, Tok::Ident | Tok::String(_)) {
+                    return self.error(format!("expected selector, found {}", self.found()));
+                }
+                let token = self.bump();
+                ExprKind::Selector {
+                    expr: Box::new(expr),
+                    label: Ident {
+                        name: self.text(&token).to_string(),
+                        span: token.span,
+                    },
+                }
+            } else if self.at("[") {
+                self.bump();
+                let index = self.expr()?;
+                self.expect("]")?;
+                ExprKind::Index {
+                    expr: Box::new(expr),
+                    index: Box::new(index),
+                }
+            } else if self.at("(") {
+                self.bump();
+                let args = self.elements(")")?;
+                ExprKind::Call {
+                    func: Box::new(expr),
+                    args,
+                }
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr {
+                kind,
+                span: Span {
+                    start,
+                    end: self.last_end,
+                },
+            };
+        }
+    }
+
+    /// Reads the elements of a list or the arguments of a call up to the
+    /// closing `close`.
+    fn elements(
+        &mut self,
+        close: &str,
+    ) -> Result<Vec<Expr>, ParseError> {
+        let mut elems = Vec::new();
+        while !self.at(close) {
+            let start = self.peek().span.start;
+            let kind = if close == "]" && self.at("...") {
+                self.bump();
+                if self.at("]") || self.peek().tok == Tok::Comma {
+                    ExprKind::Ellipsis(None)
+                } else {
+                    ExprKind::Ellipsis(Some(Box::new(self.expr()?)))
+                }
+            } else if close == "]" && (self.at_word("for") || self.at_word("if")) {
+                ExprKind::Comprehension(Box::new(self.comprehension()?))
+            } else {
+                elems.push(self.expr()?);
+                self.element_end(close)?;
+                continue;
+            };
+            elems.push(Expr {
+                kind,
+                span: Span {
+                    start,
+                    end: self.last_end,
+                },
+            });
+            self.element_end(close)?;
+        }
+        self.bump();
+        Ok(elems)
+    }
+
+    /// Reads the comma after an element, unless `close` follows it.
+    fn element_end(
+        &mut self,
+        close: &str,
+    ) -> Result<(), ParseError> {
+        if self.peek().tok == Tok::Comma {
+            self.bump();
+            return Ok(());
+        }
+        if self.at(close) {
+            return Ok(());
+        }
+        self.error(format!("expected ',' or '{close}', found {}", self.found()))
+    }
+
+    /// Reads an identifier, literal, or bracketed expression.
+    fn operand(&mut self) -> Result<Expr, ParseError> {
+        let start = self.peek().span.start;
+        let kind = match self.peek().tok.clone() {
+            Tok::Ident => {
+                let token = self.bump();
+                match self.text(&token) {
+                    "true" | "false" => ExprKind::Lit(LitKind::Bool),
+                    "null" => ExprKind::Lit(LitKind::Null),
+                    name => ExprKind::Ident(name.to_string()),
+                }
+            },
+            Tok::Int => {
+                self.bump();
+                ExprKind::Lit(LitKind::Int)
+            },
+            Tok::Float => {
+                self.bump();
+                ExprKind::Lit(LitKind::Float)
+            },
+            Tok::Bottom => {
+                self.bump();
+                ExprKind::Bottom
+            },
+            Tok::String(interpolations) => {
+                let token = self.bump();
+                if interpolations.is_empty() {
+                    let bytes = self.text(&token).trim_start_matches('#').starts_with('\'');
+                    ExprKind::Lit(if bytes {
+                        LitKind::Bytes
+                    } else {
+                        LitKind::String
+                    })
+                } else {
+                    let depth = self.depth.saturating_add(1);
+                    let parts = interpolations
+                        .into_iter()
+                        .map(|span| Self::sub_expr(self.src, span, depth))
+                        .collect::<Result<_, _>>()?;
+                    ExprKind::Interpolation(parts)
+                }
+            },
+            Tok::Punct("(") => {
+                self.bump();
+                let expr = self.expr()?;
+                self.expect(")")?;
+                ExprKind::Paren(Box::new(expr))
+            },
+            Tok::Punct("{") => {
+                self.bump();
+                let decls = self.decls(false)?;
+                self.expect("}")?;
+                ExprKind::Struct(decls)
+            },
+            Tok::Punct("[") => {
+                self.bump();
+                ExprKind::List(self.elements("]")?)
+            },
+            _ => return self.error(format!("expected expression, found {}", self.found())),
+        };
+        Ok(Expr {
+            kind,
+            span: Span {
+                start,
+                end: self.last_end,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_file;
+    use crate::{
+        Clause, Constraint, DeclKind, ExprKind, File, Label, LitKind, UnaryOp, parse, parse_expr,
+    };
+
+    const SRC: &str = r##"
+// Package config holds the service configuration.
+package config
+
+import (
+    "strings"
+    l "list"
+)
+
+// The service.
+#Service: {
+    // Name of the service.
+    name!: string & strings.MinRunes(1)
+    port?: int & >0 & <65536 | *8080 // Listening port.
+    tags: [...string] @go(Tags)
+
+    let base = "x"
+    if base == "x" { extra: 1 }
+    for k, v in {a: 1} { "\(k)": v }
+
+    env: [Name=string]: string
+    "x-y": #"a"b"#
+    X=(base): a.b[0] | l.Sum([1, 2,])
+    ...
+}
+
+#Fleet: close({ services: [...{ name: string }] })
+"##;
+
+    /// Returns the source of the fields of `decls` with their docs.
+    fn fields(file: &File) -> Vec<(&str, Constraint, &str, String)> {
+        let DeclKind::Field(service) = &file.decls[2].kind else {
+            panic!("{file:?}");
+        };
+        let ExprKind::Struct(decls) = &service.value.kind else {
+            panic!("{service:?}");
+        };
+        decls
+            .iter()
+            .filter_map(|decl| {
+                let DeclKind::Field(f) = &decl.kind else {
+                    return None;
+                };
+                Some((
+                    f.label.span().text(SRC),
+                    f.constraint,
+                    f.value.span.text(SRC),
+                    decl.doc_text(),
+                ))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let file = parse_file(SRC).unwrap();
+        assert_eq!(file.package().unwrap().name, "config");
+        assert_eq!(
+            file.decls.first().unwrap().doc_text(),
+            "Package config holds the service configuration."
+        );
+        let DeclKind::Import(imports) = &file.decls[1].kind else {
+            panic!("{file:?}");
+        };
+        let imports: Vec<_> = imports
+            .iter()
+            .map(|i| (i.name.as_ref().map(|n| n.name.as_str()), i.path.as_str()))
+            .collect();
+        assert_eq!(imports, [(None, "strings"), (Some("l"), "list")]);
+        assert_eq!(file.decls[2].doc_text(), "The service.");
+        assert_eq!(fields(&file), [
+            (
+                "name",
+                Constraint::Required,
+                "string & strings.MinRunes(1)",
+                "Name of the service.".to_string()
+            ),
+            (
+                "port",
+                Constraint::Optional,
+                "int & >0 & <65536 | *8080",
+                "Listening port.".to_string()
+            ),
+            ("tags", Constraint::Regular, "[...string]", String::new()),
+            (
+                "env",
+                Constraint::Regular,
+                "[Name=string]: string",
+                String::new()
+            ),
+            ("\"x-y\"", Constraint::Regular, "#\"a\"b\"#", String::new()),
+            (
+                "(base)",
+                Constraint::Regular,
+                "a.b[0] | l.Sum([1, 2,])",
+                String::new()
+            ),
+        ]);
+        assert_eq!(file.comments.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_decls() {
+        let file = parse(SRC).unwrap();
+        let DeclKind::Field(service) = &file.decls[2].kind else {
+            panic!();
+        };
+        let ExprKind::Struct(decls) = &service.value.kind else {
+            panic!();
+        };
+        let kinds: Vec<&str> = decls
+            .iter()
+            .map(|d| {
+                match &d.kind {
+                    DeclKind::Field(_) => "field",
+                    DeclKind::Let { .. } => "let",
+                    DeclKind::Comprehension(_) => "comprehension",
+                    DeclKind::Ellipsis(_) => "ellipsis",
+                    _ => "other",
+                }
+            })
+            .collect();
+        assert_eq!(kinds, [
+            "field",
+            "field",
+            "field",
+            "let",
+            "comprehension",
+            "comprehension",
+            "field",
+            "field",
+            "field",
+            "ellipsis"
+        ]);
+        let Some(DeclKind::Comprehension(comprehension)) = decls.get(5).map(|d| &d.kind) else {
+            panic!();
+        };
+        assert!(matches!(comprehension.clauses.as_slice(), [Clause::For {
+            key: Some(_),
+            ..
+        }]));
+        let DeclKind::Field(env) = &decls[6].kind else {
+            panic!();
+        };
+        let ExprKind::Struct(env) = &env.value.kind else {
+            panic!();
+        };
+        let Some(DeclKind::Field(pattern)) = env.first().map(|d| &d.kind) else {
+            panic!();
+        };
+        assert!(
+            matches!(&pattern.label, Label::Pattern { alias: Some(a), .. } if a.name == "Name")
+        );
+        let DeclKind::Field(tags) = &decls[2].kind else {
+            panic!();
+        };
+        assert_eq!(
+            tags.attributes
+                .first()
+                .map(|a| (a.name.as_str(), a.body.as_str())),
+            Some(("go", "Tags"))
+        );
+    }
+
+    #[test]
+    fn test_parse_keyword_labels() {
+        let file = parse("if: 1\nfor?: 2\nlet: 3\npackage: a.b\n").unwrap();
+        let labels: Vec<_> = file
+            .fields()
+            .map(|(_, f)| f.label.ident().unwrap())
+            .collect();
+        assert_eq!(labels, ["if", "for", "let", "package"]);
+    }
+
+    #[test]
+    fn test_parse_interpolation() {
+        let expr = parse_expr(r#""a\(b + "\(c)")""#).unwrap();
+        let ExprKind::Interpolation(parts) = expr.kind else {
+            panic!("{expr:?}");
+        };
+        assert!(matches!(parts.as_slice(), [p] if matches!(p.kind, ExprKind::Binary { .. })));
+        let default = parse_expr("*'x'").unwrap();
+        assert!(
+            matches!(default.kind, ExprKind::Unary { op: UnaryOp::Default, expr } if expr.kind == ExprKind::Lit(LitKind::Bytes))
+        );
+    }
+
+    #[test]
+    fn test_parse_depth() {
+        let deep = format!("a: {}{}", "[".repeat(100), "]".repeat(100));
+        assert!(parse(&deep).is_ok());
+        let too_deep = format!("a: {}{}", "[".repeat(200), "]".repeat(200));
+        assert!(parse(&too_deep).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+#![allow(
+    missing_docs,
+    clippy::missing_docs_in_private_items,
+    clippy::unwrap_used
+)]
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use cue_rs::{Ctx, Value};
+
+#[derive(serde::Serialize)]
+struct Server {
+    host: &'static str,
+    ports: Vec<u16>,
+    tls: bool,
+}
+
+// ── encode vs JSON round trip ─────────────────────────────────────────────────
+
+fn bench_encode(c: &mut Criterion) {
+    let ctx = Ctx::new().unwrap();
+    let server = Server {
+        host: "db.internal",
+        ports: vec![5432, 5433],
+        tls: true,
+    };
+
+    let mut g = c.benchmark_group("encode");
+
+    g.bench_function("int/encode", |b| {
+        b.iter(|| Value::encode(&ctx, black_box(&42i64)));
+    });
+
+    g.bench_function("int/json", |b| {
+        b.iter(|| Value::compile_bytes(&ctx, &serde_json::to_vec(black_box(&42i64)).unwrap()));
+    });
+
+    g.bench_function("string/encode", |b| {
+        b.iter(|| Value::encode(&ctx, black_box("hello world")));
+    });
+
+    g.bench_function("string/json", |b| {
+        b.iter(|| {
+            Value::compile_bytes(&ctx, &serde_json::to_vec(black_box("hello world")).unwrap())
+        });
+    });
+
+    // Composite values take the JSON path, so each pair below should match.
+    g.bench_function("struct/encode", |b| {
+        b.iter(|| Value::encode(&ctx, black_box(&server)));
+    });
+
+    g.bench_function("struct/json", |b| {
+        b.iter(|| Value::compile_bytes(&ctx, &serde_json::to_vec(black_box(&server)).unwrap()));
+    });
+
+    g.bench_function("list/encode", |b| {
+        b.iter(|| Value::encode(&ctx, black_box(&server.ports)));
+    });
+
+    g.bench_function("list/json", |b| {
+        b.iter(|| {
+            Value::compile_bytes(&ctx, &serde_json::to_vec(black_box(&server.ports)).unwrap())
+        });
+    });
+
+    g.finish();
+}
+
+criterion_group!(benches, bench_encode);
+criterion_main!(benches);
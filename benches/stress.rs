@@ -0,0 +1,55 @@
+#![allow(
+    missing_docs,
+    clippy::missing_docs_in_private_items,
+    clippy::unwrap_used
+)]
+
+use criterion::{BatchSize, Criterion, black_box, criterion_group, criterion_main};
+use cue_rs::{Ctx, Value, stress};
+
+// ── validate generated schemas ────────────────────────────────────────────────
+
+fn bench_validate(c: &mut Criterion) {
+    let ctx = Ctx::new().unwrap();
+
+    let mut g = c.benchmark_group("stress_validate");
+
+    for complexity in [10, 100, 1000] {
+        let schema = stress::generate_schema(0x5EED, complexity);
+        let definition = Value::compile_string(&ctx, schema.source())
+            .unwrap()
+            .lookup(stress::DEFINITION)
+            .unwrap();
+        let valid = schema.valid(1);
+        let near_miss = schema.near_miss(1);
+
+        g.bench_function(format!("valid/{complexity}"), |b| {
+            b.iter_batched(
+                || Value::compile_string(&ctx, &valid).unwrap(),
+                |data| Value::unify(&definition, &data).is_valid(),
+                BatchSize::SmallInput,
+            );
+        });
+
+        g.bench_function(format!("near_miss/{complexity}"), |b| {
+            b.iter_batched(
+                || Value::compile_string(&ctx, &near_miss).unwrap(),
+                |data| Value::unify(&definition, &data).is_valid(),
+                BatchSize::SmallInput,
+            );
+        });
+    }
+
+    g.finish();
+}
+
+// ── generate ──────────────────────────────────────────────────────────────────
+
+fn bench_generate(c: &mut Criterion) {
+    c.bench_function("stress_generate/100", |b| {
+        b.iter(|| stress::generate_schema(black_box(0x5EED), 100));
+    });
+}
+
+criterion_group!(benches, bench_validate, bench_generate);
+criterion_main!(benches);
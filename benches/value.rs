@@ -5,7 +5,7 @@
 )]
 
 use criterion::{BatchSize, Criterion, black_box, criterion_group, criterion_main};
-use cue_rs::{Ctx, Value};
+use cue_rs::{Ctx, Path, Value};
 
 // ── compile_string ────────────────────────────────────────────────────────────
 
@@ -118,11 +118,32 @@ fn bench_unify(c: &mut Criterion) {
     g.finish();
 }
 
+// ── lookup ────────────────────────────────────────────────────────────────────
+
+fn bench_lookup(c: &mut Criterion) {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(&ctx, "{ a: { b: 1, c: 2, d: 3, e: 4 } }").unwrap();
+    let paths = ["a.b", "a.c", "a.d", "a.e"].map(|p| Path::new(p).unwrap());
+
+    let mut g = c.benchmark_group("lookup");
+
+    g.bench_function("lookup_str", |b| {
+        b.iter(|| ["a.b", "a.c", "a.d", "a.e"].map(|p| v.lookup(black_box(p))));
+    });
+
+    g.bench_function("lookup_path", |b| {
+        b.iter(|| paths.each_ref().map(|p| v.lookup_path(black_box(p))));
+    });
+
+    g.finish();
+}
+
 criterion_group!(
     benches,
     bench_compile_string,
     bench_compile_bytes,
     bench_is_valid,
     bench_unify,
+    bench_lookup,
 );
 criterion_main!(benches);
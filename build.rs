@@ -2,16 +2,43 @@
 
 #![allow(clippy::unwrap_used, clippy::expect_used)]
 
-use std::{env, path::PathBuf, process::Command};
+use std::{env, fs, path::PathBuf, process::Command};
+
+/// Returns the version required for `module` in the given `go.mod` contents.
+fn module_version<'a>(
+    go_mod: &'a str,
+    module: &str,
+) -> Option<&'a str> {
+    go_mod
+        .lines()
+        .map(|line| line.trim().trim_start_matches("require").split_whitespace())
+        .find_map(|mut words| {
+            (words.next() == Some(module))
+                .then(|| words.next())
+                .flatten()
+        })
+}
 
 fn main() {
     // Rebuild whenever the module manifest or lockfile changes (i.e. a version
     // bump of github.com/cue-lang/libcue).
-    println!("cargo:rerun-if-changed=go-cue/go.mod");
-    println!("cargo:rerun-if-changed=go-cue/go.sum");
+    println!("cargo:rerun-if-changed=libcue/go.mod");
+    println!("cargo:rerun-if-changed=libcue/go.sum");
 
     let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
     let go_dir = manifest_dir.join("libcue");
+
+    // Bake the module versions into the crate, see `cue_rs::version()`.
+    let go_mod = fs::read_to_string(go_dir.join("go.mod")).expect("failed to read libcue/go.mod");
+    println!(
+        "cargo:rustc-env=CUE_RS_CUE_VERSION={}",
+        module_version(&go_mod, "cuelang.org/go").expect("cuelang.org/go not found in go.mod")
+    );
+    println!(
+        "cargo:rustc-env=CUE_RS_LIBCUE_VERSION={}",
+        module_version(&go_mod, "github.com/cue-lang/libcue")
+            .expect("github.com/cue-lang/libcue not found in go.mod")
+    );
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
     let lib_out = out_dir.join("libcue.a");
 
@@ -0,0 +1,509 @@
+//! Build-script helpers bundling CUE schemas for
+//! [`cue-rs`](https://docs.rs/cue-rs).
+//!
+//! [`bundle`] checks every `.cue` file of a directory while the crate builds,
+//! then writes them to `OUT_DIR` as a single CUE source together with a Rust
+//! module of constants, so a broken schema fails the build and the schemas
+//! ship inside the binary:
+//!
+//! ```no_run
+//! // build.rs
+//! fn main() -> Result<(), cue_rs_build::Error> {
+//!     cue_rs_build::bundle("schemas/")
+//! }
+//! ```
+//!
+//! ```ignore
+//! // src/schemas.rs
+//! include!(concat!(env!("OUT_DIR"), "/schemas.rs"));
+//!
+//! // elsewhere
+//! let ctx = cue_rs::Ctx::new()?;
+//! let deployment = cue_rs::Value::compile_string(&ctx, schemas::K8S_DEPLOYMENT)?;
+//! let all = cue_rs::Value::compile_string(&ctx, schemas::BUNDLE)?;
+//! ```
+//!
+//! For a directory `schemas/` the module, `schemas.rs`, holds:
+//!
+//! - `BUNDLE` — every schema concatenated into one CUE source, `schemas.cue`, with the
+//!   `package` clauses dropped and the imports of all files merged at the top;
+//! - a constant per file with its source, named after its path in the directory:
+//!   `k8s/deployment.cue` becomes `K8S_DEPLOYMENT`.
+//!
+//! Files and directories whose names start with `_` or `.`, and the
+//! `cue.mod` directory, are skipped, as CUE does.  Each schema is parsed with
+//! [`cue_rs_ast::parse`]; CUE's formatter is not reachable through libcue,
+//! so formatting is limited to normalizing line endings, trailing whitespace
+//! and the final newline.
+//!
+//! # Features
+//!
+//! - `compile` — also compiles and validates every schema, and the bundle, with the
+//!   evaluator vendored by `cue-rs`.
+
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+/// An error bundling schemas.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// A schema or the output could not be read or written.
+    #[error("{}: {source}", path.display())]
+    Io {
+        /// The file or directory.
+        path: PathBuf,
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+    /// A schema does not parse.
+    #[error("{}: {source}", path.display())]
+    Syntax {
+        /// The schema.
+        path: PathBuf,
+        /// The error parsing it.
+        source: cue_rs_ast::SyntaxError,
+    },
+    /// A schema, or the bundle, is not valid.
+    #[cfg(feature = "compile")]
+    #[error("{}: {source}", path.display())]
+    Cue {
+        /// The schema, or the bundle written to `OUT_DIR`.
+        path: PathBuf,
+        /// The error evaluating it.
+        source: cue_rs::error::Error,
+    },
+    /// Two schemas map to the same constant name.
+    #[error("{first} and {second} are both bundled as `{name}`")]
+    DuplicateName {
+        /// The constant name.
+        name: String,
+        /// The first schema.
+        first: String,
+        /// The second schema.
+        second: String,
+    },
+    /// `OUT_DIR` is not set, as outside of a build script.
+    #[error("OUT_DIR is not set; call the bundler from a build script")]
+    OutDir,
+}
+
+/// Bundles the schemas of `dir` into `OUT_DIR`, see the
+/// [crate documentation](crate).
+///
+/// # Errors
+///
+/// See [`Bundle::write`].
+pub fn bundle(dir: impl AsRef<Path>) -> Result<(), Error> {
+    Bundle::new(dir).write()
+}
+
+/// Options for bundling a directory of schemas.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    /// The schema directory.
+    dir: PathBuf,
+    /// The output directory, `OUT_DIR` by default.
+    out_dir: Option<PathBuf>,
+    /// The name of the output files, the directory name by default.
+    name: Option<String>,
+}
+
+impl Bundle {
+    /// Bundles the schemas of `dir`.
+    #[must_use]
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            out_dir: None,
+            name: None,
+        }
+    }
+
+    /// Writes the output to `out_dir` instead of `OUT_DIR`.
+    #[must_use]
+    pub fn with_out_dir(
+        mut self,
+        out_dir: impl AsRef<Path>,
+    ) -> Self {
+        self.out_dir = Some(out_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Names the output `<name>.cue` and `<name>.rs` instead of after the
+    /// schema directory.
+    #[must_use]
+    pub fn with_name(
+        mut self,
+        name: impl Into<String>,
+    ) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Checks and writes the bundle, and tells cargo to rerun the build
+    /// script when the schema directory changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the directory cannot be read or the output
+    /// written, [`Error::Syntax`] if a schema does not parse, `Error::Cue`
+    /// with the `compile` feature if it is not valid,
+    /// [`Error::DuplicateName`] if two schemas map to the same constant, and
+    /// [`Error::OutDir`] without an output directory.
+    pub fn write(&self) -> Result<(), Error> {
+        let out_dir = self
+            .out_dir
+            .clone()
+            .or_else(|| std::env::var_os("OUT_DIR").map(PathBuf::from))
+            .ok_or(Error::OutDir)?;
+        let name = self.name.clone().unwrap_or_else(|| {
+            self.dir.file_name().map_or_else(
+                || "schemas".to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            )
+        });
+
+        let mut files = Vec::new();
+        schema_files(&self.dir, &mut files)?;
+        files.sort();
+        let mut schemas: Vec<Schema> = Vec::with_capacity(files.len());
+        for path in files {
+            let schema = Schema::read(&self.dir, &path)?;
+            if let Some(other) = schemas
+                .iter()
+                .find(|other| other.constant == schema.constant)
+            {
+                return Err(Error::DuplicateName {
+                    name: schema.constant,
+                    first: other.name.clone(),
+                    second: schema.name,
+                });
+            }
+            schemas.push(schema);
+        }
+
+        let cue_path = out_dir.join(format!("{name}.cue"));
+        let source = concat(&schemas);
+        #[cfg(feature = "compile")]
+        compile(&source).map_err(|source| {
+            Error::Cue {
+                path: cue_path.clone(),
+                source,
+            }
+        })?;
+        write(&cue_path, &source)?;
+        write(
+            &out_dir.join(format!("{name}.rs")),
+            &module(&self.dir, &cue_path, &schemas),
+        )?;
+        println!("cargo:rerun-if-changed={}", self.dir.display());
+        Ok(())
+    }
+}
+
+/// A schema read from the directory being bundled.
+#[derive(Debug)]
+struct Schema {
+    /// Path relative to the directory, with `/` separators.
+    name: String,
+    /// Name of the constant holding the schema.
+    constant: String,
+    /// The formatted source.
+    source: String,
+    /// The parsed source.
+    file: cue_rs_ast::File,
+}
+
+impl Schema {
+    /// Reads, formats and checks the schema at `path` under `dir`.
+    fn read(
+        dir: &Path,
+        path: &Path,
+    ) -> Result<Self, Error> {
+        let source = std::fs::read_to_string(path).map_err(|source| {
+            Error::Io {
+                path: path.to_path_buf(),
+                source,
+            }
+        })?;
+        let source = format(&source);
+        let file = cue_rs_ast::parse(&source).map_err(|source| {
+            Error::Syntax {
+                path: path.to_path_buf(),
+                source,
+            }
+        })?;
+        #[cfg(feature = "compile")]
+        compile(&source).map_err(|source| {
+            Error::Cue {
+                path: path.to_path_buf(),
+                source,
+            }
+        })?;
+        let name = path
+            .strip_prefix(dir)
+            .unwrap_or(path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        Ok(Self {
+            constant: constant(&name),
+            name,
+            source,
+            file,
+        })
+    }
+}
+
+/// Collects the `.cue` files under `dir`, skipping hidden files and
+/// directories and `cue.mod`.
+fn schema_files(
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), Error> {
+    let io = |source| {
+        Error::Io {
+            path: dir.to_path_buf(),
+            source,
+        }
+    };
+    for entry in std::fs::read_dir(dir).map_err(io)? {
+        let path = entry.map_err(io)?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with(['_', '.']) || name == "cue.mod" {
+            continue;
+        }
+        if path.is_dir() {
+            schema_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "cue") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Normalizes the whitespace of `source`: `\n` line endings, no trailing
+/// whitespace and a single final newline.
+fn format(source: &str) -> String {
+    let mut out = source
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_string();
+    out.push('\n');
+    out
+}
+
+/// Returns the constant name of the schema `name`: its path without the
+/// `.cue` extension, in upper snake case.
+fn constant(name: &str) -> String {
+    let mut constant = name
+        .strip_suffix(".cue")
+        .unwrap_or(name)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    if !constant.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        constant.insert(0, '_');
+    }
+    constant
+}
+
+/// Concatenates `schemas` into one CUE source, dropping their `package`
+/// clauses and merging their imports at the top.
+fn concat(schemas: &[Schema]) -> String {
+    use cue_rs_ast::DeclKind;
+
+    let mut imports = Vec::new();
+    let mut bodies = String::new();
+    for schema in schemas {
+        let src = schema.source.as_str();
+        let mut body = String::new();
+        let mut pos = 0;
+        for decl in &schema.file.decls {
+            match &decl.kind {
+                DeclKind::Package(_) => {},
+                DeclKind::Import(specs) => {
+                    for spec in specs {
+                        let spec = spec.span.text(src);
+                        if !imports.contains(&spec) {
+                            imports.push(spec);
+                        }
+                    }
+                },
+                _ => continue,
+            }
+            body.push_str(src.get(pos..decl.span.start).unwrap_or_default());
+            pos = decl.span.end;
+        }
+        body.push_str(src.get(pos..).unwrap_or_default());
+        let _ = write!(bodies, "\n// {}\n\n{}", schema.name, body.trim_start());
+    }
+
+    let mut out = String::new();
+    if !imports.is_empty() {
+        out.push_str("import (\n");
+        for spec in imports {
+            let _ = writeln!(out, "\t{spec}");
+        }
+        out.push_str(")\n");
+    }
+    out.push_str(&bodies);
+    out.trim_start().to_string()
+}
+
+/// Returns the Rust module of the bundle of `dir` written to `cue_path`.
+fn module(
+    dir: &Path,
+    cue_path: &Path,
+    schemas: &[Schema],
+) -> String {
+    let mut out = format!("// @generated by cue-rs-build from `{}`.\n", dir.display());
+    let _ = write!(
+        out,
+        "\n/// All schemas, concatenated into one CUE source.\npub const BUNDLE: &str = include_str!({:?});\n",
+        cue_path.display().to_string(),
+    );
+    for schema in schemas {
+        let _ = write!(
+            out,
+            "\n/// The schema `{}`.\npub const {}: &str = {:?};\n",
+            schema.name, schema.constant, schema.source,
+        );
+    }
+    out
+}
+
+/// Compiles and validates `source` with libcue.
+#[cfg(feature = "compile")]
+fn compile(source: &str) -> Result<(), cue_rs::error::Error> {
+    let ctx = cue_rs::Ctx::new()?;
+    cue_rs::Value::compile_string(&ctx, source)?.is_valid()
+}
+
+/// Writes `contents` to `path`, leaving the file untouched if it already
+/// holds them so cargo does not rebuild the crate needlessly.
+fn write(
+    path: &Path,
+    contents: &str,
+) -> Result<(), Error> {
+    if std::fs::read_to_string(path).is_ok_and(|old| old == contents) {
+        return Ok(());
+    }
+    std::fs::write(path, contents).map_err(|source| {
+        Error::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use cue_rs_ast::SyntaxError;
+    use test_case::test_case;
+
+    use super::{Bundle, Error};
+
+    /// Writes `files` into a fresh directory named after `test`.
+    fn schemas(
+        test: &str,
+        files: &[(&str, &str)],
+    ) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("cue-rs-build-{test}-{}", std::process::id()));
+        for (name, src) in files {
+            let path = dir.join("schemas").join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, src).unwrap();
+        }
+        dir
+    }
+
+    #[test_case("service.cue" => "SERVICE" ; "file")]
+    #[test_case("k8s/deployment.cue" => "K8S_DEPLOYMENT" ; "nested")]
+    #[test_case("api-v1.cue" => "API_V1" ; "dash")]
+    #[test_case("1st.cue" => "_1ST" ; "digit")]
+    fn test_constant(name: &str) -> String {
+        super::constant(name)
+    }
+
+    #[test]
+    fn test_bundle() {
+        let dir = schemas("bundle", &[
+            (
+                "service.cue",
+                "package schemas\r\n\r\nimport \"strings\"\r\n\r\n#Service: {\r\n\tname: strings.MinRunes(1)  \r\n}\r\n\r\n",
+            ),
+            (
+                "k8s/deployment.cue",
+                "package k8s\n\nimport (\n\t\"strings\"\n\t\"list\"\n)\n\n// A deployment.\n#Deployment: {\n\tname: strings.MinRunes(1)\n\tports: list.MinItems(1)\n}\n",
+            ),
+            ("_draft.cue", "#Draft: {"),
+            ("cue.mod/module.cue", "module: \"example.com\""),
+        ]);
+        Bundle::new(dir.join("schemas"))
+            .with_out_dir(&dir)
+            .write()
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("schemas.cue")).unwrap(),
+            "import (\n\t\"strings\"\n\t\"list\"\n)\n\n// k8s/deployment.cue\n\n// A deployment.\n#Deployment: {\n\tname: strings.MinRunes(1)\n\tports: list.MinItems(1)\n}\n\n// service.cue\n\n#Service: {\n\tname: strings.MinRunes(1)\n}\n",
+        );
+        let module = std::fs::read_to_string(dir.join("schemas.rs")).unwrap();
+        assert!(module.contains("pub const BUNDLE: &str = include_str!("));
+        assert!(module.contains(
+            "pub const SERVICE: &str = \"package schemas\\n\\nimport \\\"strings\\\"\\n\\n#Service: {\\n\\tname: strings.MinRunes(1)\\n}\\n\";"
+        ));
+        assert!(module.contains("pub const K8S_DEPLOYMENT: &str"));
+        assert!(!module.contains("DRAFT"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_bundle_errors() {
+        let dir = schemas("errors", &[(
+            "service.cue",
+            "#Service: {\n\tname: string\n",
+        )]);
+        let err = Bundle::new(dir.join("schemas"))
+            .with_out_dir(&dir)
+            .write()
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::Syntax { path, source: SyntaxError { line: 3, .. } } if path.ends_with("service.cue")),
+            "{err}"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let dir = schemas("duplicate", &[("a-b.cue", "a: 1"), ("a_b.cue", "b: 1")]);
+        let err = Bundle::new(dir.join("schemas"))
+            .with_out_dir(&dir)
+            .write()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a-b.cue and a_b.cue are both bundled as `A_B`"
+        );
+
+        let err = Bundle::new(Path::new("missing"))
+            .with_out_dir(&dir)
+            .write()
+            .unwrap_err();
+        assert!(matches!(err, Error::Io { .. }), "{err}");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
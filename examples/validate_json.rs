@@ -2,22 +2,19 @@
 //!
 //! The approach:
 //! 1. Compile the CUE schema string into a [`cue_rs::Value`].
-//! 2. Serialize the JSON data to bytes and compile it into a second [`cue_rs::Value`].
-//! 3. Unify the schema and the data — in CUE, unification is the `&` operator.
-//! 4. Call [`cue_rs::Value::is_valid`] on the result; a bottom value (`_|_`) means the
-//!    data does not conform to the schema.
+//! 2. Serialize the JSON data to bytes and pass them to [`cue_rs::Value::validate_json`],
+//!    which compiles them, unifies them with the schema — in CUE, unification is the `&`
+//!    operator — and checks that the result is valid and concrete.
 
 use cue_rs::{Ctx, Value, error::Error};
 
-/// Validates `data` against `schema` by unifying them and checking [`Value::is_valid`].
+/// Validates `data` against `schema` with [`Value::validate_json`].
 fn validate(
     ctx: &Ctx,
     schema: &Value,
     data: &serde_json::Value,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let json_bytes = serde_json::to_vec(data)?;
-    let data_val = Value::compile_bytes(ctx, &json_bytes)?;
-    Value::unify(schema, &data_val).is_valid()?;
+    schema.validate_json(ctx, &serde_json::to_vec(data)?)?;
     Ok(())
 }
 
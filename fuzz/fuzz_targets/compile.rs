@@ -0,0 +1,18 @@
+//! Compiles arbitrary bytes and exports and decodes the result.
+
+#![no_main]
+
+use cue_rs::{Ctx, Value};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(ctx) = Ctx::new() else {
+        return;
+    };
+    if let Ok(value) = Value::compile_bytes(&ctx, data) {
+        drop(value.is_valid());
+        drop(value.to_json_vec());
+        drop(value.to_string());
+        drop(value.default_value());
+    }
+});
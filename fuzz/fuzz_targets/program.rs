@@ -0,0 +1,12 @@
+//! Runs arbitrary sequences of operations over a table of values.
+
+#![no_main]
+
+use cue_rs::{Ctx, fuzz::Program};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|program: Program| {
+    if let Ok(ctx) = Ctx::new() {
+        program.run(&ctx);
+    }
+});
@@ -0,0 +1,23 @@
+//! Unifies two arbitrary sources and exports the result.
+
+#![no_main]
+
+use cue_rs::{Ctx, Value};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (&str, &str)| {
+    let Ok(ctx) = Ctx::new() else {
+        return;
+    };
+    let (Ok(a), Ok(b)) = (
+        Value::compile_string(&ctx, input.0),
+        Value::compile_string(&ctx, input.1),
+    ) else {
+        return;
+    };
+    let unified = Value::unify(&a, &b);
+    drop(a);
+    drop(unified.is_valid());
+    drop(unified.to_json_vec());
+    drop(Value::unify(&unified, &b).to_json_vec());
+});
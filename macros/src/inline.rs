@@ -0,0 +1,225 @@
+//! Rebuilding CUE source from the tokens of a [`cue!`](crate::cue) call.
+//!
+//! Tokens are laid out as they were written: a token on a later line than
+//! the previous one starts a new line, which keeps CUE's newline-based comma
+//! elision working, and tokens separated by whitespace in the Rust source
+//! are separated by a space.  Rust string literals are decoded and quoted
+//! again as CUE strings.
+
+use std::fmt::Write as _;
+
+use proc_macro::{Delimiter, Span, TokenStream, TokenTree};
+
+/// CUE source rebuilt from tokens, with the position of every token in it.
+#[derive(Default)]
+pub(crate) struct Source {
+    /// The CUE source.
+    pub(crate) text: String,
+    /// Line, column (both starting at 1, the column in characters) and span
+    /// of every token written to `text`, in order.
+    tokens: Vec<(usize, usize, Span)>,
+    /// Line of `text` being written, starting at 1.
+    line: usize,
+    /// Characters written to the current line of `text`.
+    column: usize,
+    /// Span of the last token written, if any.
+    last: Option<Span>,
+}
+
+impl Source {
+    /// Rebuilds the CUE source of `input`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the span of the first literal that cannot be written as CUE,
+    /// with the reason.
+    pub(crate) fn new(input: TokenStream) -> Result<Self, (Span, String)> {
+        let mut source = Self {
+            line: 1,
+            ..Self::default()
+        };
+        source.stream(input)?;
+        Ok(source)
+    }
+
+    /// Returns the span of the token at `line` and `column` of the source,
+    /// the last one starting at or before it.
+    pub(crate) fn span_at(
+        &self,
+        line: usize,
+        column: usize,
+    ) -> Span {
+        self.tokens
+            .iter()
+            .rev()
+            .find(|(l, c, _)| (*l, *c) <= (line, column))
+            .or(self.tokens.first())
+            .map_or_else(Span::call_site, |(_, _, span)| *span)
+    }
+
+    /// Writes the tokens of `stream`.
+    fn stream(
+        &mut self,
+        stream: TokenStream,
+    ) -> Result<(), (Span, String)> {
+        for token in stream {
+            match token {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    self.write(open, group.span_open());
+                    self.stream(group.stream())?;
+                    self.write(close, group.span_close());
+                },
+                TokenTree::Ident(ident) => {
+                    let name = ident.to_string();
+                    self.write(name.strip_prefix("r#").unwrap_or(&name), ident.span());
+                },
+                TokenTree::Punct(punct) => self.write(&punct.as_char().to_string(), punct.span()),
+                TokenTree::Literal(lit) => {
+                    let text = lit.to_string();
+                    match literal(&text) {
+                        Some(cue) => self.write(&cue, lit.span()),
+                        None => return Err((lit.span(), format!("unsupported literal `{text}`"))),
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes `text`, read from `span`, after the whitespace separating it
+    /// from the previous token.
+    fn write(
+        &mut self,
+        text: &str,
+        span: Span,
+    ) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(last) = self.last {
+            let (end, start) = (last.end(), span.start());
+            let lines = start.line().saturating_sub(end.line());
+            if lines > 0 {
+                self.text.push_str(&"\n".repeat(lines));
+                self.line = self.line.saturating_add(lines);
+                self.column = 0;
+            } else if start.column() != end.column() {
+                self.text.push(' ');
+                self.column = self.column.saturating_add(1);
+            }
+        }
+        self.tokens
+            .push((self.line, self.column.saturating_add(1), span));
+        self.text.push_str(text);
+        self.column = self.column.saturating_add(text.chars().count());
+        self.last = Some(span);
+    }
+}
+
+/// Returns the CUE form of the Rust literal `text`: numbers as written, and
+/// string and raw string literals quoted as CUE strings.
+fn literal(text: &str) -> Option<String> {
+    if text.starts_with(|c: char| c.is_ascii_digit()) {
+        return Some(text.to_string());
+    }
+    let value = if let Some(raw) = text.strip_prefix('r') {
+        let hashes = raw.len().saturating_sub(raw.trim_start_matches('#').len());
+        let delim = "#".repeat(hashes);
+        raw.strip_prefix(&delim)?
+            .strip_prefix('"')?
+            .strip_suffix(&delim)?
+            .strip_suffix('"')?
+            .to_string()
+    } else {
+        unescape(text.strip_prefix('"')?.strip_suffix('"')?)?
+    };
+    Some(quote(&value))
+}
+
+/// Decodes the escapes of the body of a Rust string literal.
+fn unescape(body: &str) -> Option<String> {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            '0' => out.push('\0'),
+            c @ ('\\' | '\'' | '"') => out.push(c),
+            'x' => {
+                let hex = [chars.next()?, chars.next()?].iter().collect::<String>();
+                out.push(char::from(
+                    u8::from_str_radix(&hex, 16).ok().filter(u8::is_ascii)?,
+                ));
+            },
+            'u' => {
+                if chars.next()? != '{' {
+                    return None;
+                }
+                let hex = chars
+                    .by_ref()
+                    .take_while(|c| *c != '}')
+                    .filter(|c| *c != '_')
+                    .collect::<String>();
+                out.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            },
+            '\n' => while chars.next_if(|c| c.is_whitespace()).is_some() {},
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Quotes `value` as a CUE string literal.
+fn quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len().saturating_add(2));
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    #[test_case("42" => Some("42".to_string()) ; "integer")]
+    #[test_case("1.5e3" => Some("1.5e3".to_string()) ; "float")]
+    #[test_case(r#""a\"b""# => Some(r#""a\"b""#.to_string()) ; "quote")]
+    #[test_case(r#""^\\d+$""# => Some(r#""^\\d+$""#.to_string()) ; "backslash")]
+    #[test_case(r#""tab\tnew\nline""# => Some(r#""tab\tnew\nline""#.to_string()) ; "control")]
+    #[test_case(r#""\x41\u{1F600}""# => Some("\"A\u{1F600}\"".to_string()) ; "unicode")]
+    #[test_case(r#""a\
+                 b""# => Some(r#""ab""#.to_string()) ; "continuation")]
+    #[test_case(r#""\x07""# => Some(r#""\u0007""#.to_string()) ; "bell")]
+    #[test_case(r##"r#"^\d+"$"#"## => Some(r#""^\\d+\"$""#.to_string()) ; "raw")]
+    #[test_case(r#""\(x)""# => None ; "interpolation")]
+    #[test_case(r#"b"bytes""# => None ; "byte_string")]
+    #[test_case("'c'" => None ; "character")]
+    fn test_literal(text: &str) -> Option<String> {
+        super::literal(text)
+    }
+}
@@ -0,0 +1,261 @@
+//! Compile-time checks of CUE sources for [`cue-rs`](https://docs.rs/cue-rs).
+//!
+//! [`embed_cue!`] embeds a `.cue` file into the binary as a `&'static str`,
+//! like [`include_str!`], after parsing it with
+//! [`cue_rs_ast::parse`].  A schema with a syntax error fails the build,
+//! pointing at the macro call, instead of failing at runtime when it is
+//! first compiled:
+//!
+//! ```
+//! use cue_rs_macros::embed_cue;
+//!
+//! static SCHEMA: &str = embed_cue!("tests/fixtures/service.cue");
+//!
+//! assert!(SCHEMA.contains("#Service"));
+//! ```
+//!
+//! ```compile_fail
+//! // error: tests/fixtures/invalid.cue: syntax error at 3:1: ...
+//! static SCHEMA: &str = cue_rs_macros::embed_cue!("tests/fixtures/invalid.cue");
+//! ```
+//!
+//! Paths are resolved against the directory of the `Cargo.toml` of the crate
+//! calling the macro, as `CARGO_MANIFEST_DIR`.  The file is embedded with
+//! [`include_str!`], so cargo rebuilds the crate whenever it changes.
+//!
+//! [`cue!`] checks CUE written inline in Rust source the same way, and
+//! expands to it as a `&'static str`, ready for `cue-rs`'s
+//! `Value::compile_string`:
+//!
+//! ```
+//! use cue_rs_macros::cue;
+//!
+//! const SCHEMA: &str = cue! {
+//!     #Service: {
+//!         name:      string & =~"^[a-z][a-z0-9-]*$"
+//!         replicas?: int & >0 | *1
+//!         port:      int & >0 & <65536
+//!     }
+//! };
+//!
+//! assert!(SCHEMA.starts_with("#Service: {\n"));
+//! ```
+//!
+//! The CUE is read as Rust tokens, laid out on the lines they were written
+//! on, and a syntax error points at the offending token.  String literals
+//! are Rust literals, so quotes and backslashes are escaped the Rust way, or
+//! not at all in raw strings (`r"^\d+$"`), and are quoted again for CUE.
+//! Rust tokens cannot express CUE comments, interpolations, multi-line and
+//! raw CUE strings, or bytes; use [`embed_cue!`] for such sources.
+//!
+//! ```compile_fail
+//! // error: expected ',' or newline, found 'port'
+//! const SCHEMA: &str = cue_rs_macros::cue! { #Service: { name: string port: int } };
+//! ```
+//!
+//! # Features
+//!
+//! - `compile` — also compiles and validates the CUE with the evaluator vendored by
+//!   `cue-rs`, so unresolved references and conflicting constraints fail the build as
+//!   well.  Imports of other packages cannot be resolved from a single source and are
+//!   reported as errors.
+
+mod inline;
+
+use std::path::{Path, PathBuf};
+
+use cue_rs_ast::SyntaxError;
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Embeds the CUE file at the given path as a `&'static str`, failing the
+/// build if it does not parse.
+///
+/// See the [crate documentation](crate) for how the path is resolved.
+#[proc_macro]
+pub fn embed_cue(input: TokenStream) -> TokenStream {
+    let (lit, span) = match path_literal(input) {
+        Ok(lit) => lit,
+        Err((span, message)) => return invoke("compile_error", &message, span),
+    };
+    let path = Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default()).join(&lit);
+    let checked = std::fs::read_to_string(&path)
+        .map_err(|error| error.to_string())
+        .and_then(|src| check(&src).map_err(|error| error.to_string()));
+    match checked {
+        Ok(()) => invoke("include_str", &path.to_string_lossy(), span),
+        Err(message) => {
+            invoke(
+                "compile_error",
+                &format!("{}: {message}", lit.display()),
+                span,
+            )
+        },
+    }
+}
+
+/// Writes CUE inline as a `&'static str` of CUE source, failing the build if
+/// it does not parse.
+///
+/// See the [crate documentation](crate) for what can be written.
+#[proc_macro]
+pub fn cue(input: TokenStream) -> TokenStream {
+    let source = match inline::Source::new(input) {
+        Ok(source) => source,
+        Err((span, message)) => return invoke("compile_error", &message, span),
+    };
+    match check(&source.text) {
+        Ok(()) => TokenTree::Literal(Literal::string(&source.text)).into(),
+        Err(CheckError::Syntax(SyntaxError {
+            line,
+            column,
+            message,
+        })) => invoke("compile_error", &message, source.span_at(line, column)),
+        #[cfg(feature = "compile")]
+        Err(error) => invoke("compile_error", &error.to_string(), Span::call_site()),
+    }
+}
+
+/// Reads the single string literal `input` of [`embed_cue!`], with its span.
+fn path_literal(input: TokenStream) -> Result<(PathBuf, Span), (Span, String)> {
+    let mut tokens = input.into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(lit)), None) => {
+            unquote(&lit.to_string())
+                .map(|path| (PathBuf::from(path), lit.span()))
+                .ok_or_else(|| (lit.span(), "expected a string literal path".to_string()))
+        },
+        (Some(token), _) => {
+            Err((
+                token.span(),
+                "expected a single string literal path".to_string(),
+            ))
+        },
+        (None, _) => {
+            Err((
+                Span::call_site(),
+                "expected a string literal path".to_string(),
+            ))
+        },
+    }
+}
+
+/// Returns the value of the string literal `lit` as written in source, either
+/// a plain literal without escapes or a raw one.
+fn unquote(lit: &str) -> Option<&str> {
+    if let Some(raw) = lit.strip_prefix('r') {
+        let hashes = raw.len().saturating_sub(raw.trim_start_matches('#').len());
+        let delim = "#".repeat(hashes);
+        return raw
+            .strip_prefix(&delim)?
+            .strip_prefix('"')?
+            .strip_suffix(&delim)?
+            .strip_suffix('"');
+    }
+    lit.strip_prefix('"')?
+        .strip_suffix('"')
+        .filter(|value| !value.contains('\\'))
+}
+
+/// Why [`check`] rejected a CUE source.
+#[derive(Debug)]
+enum CheckError {
+    /// The source does not parse.
+    Syntax(SyntaxError),
+    /// The source does not compile or is not valid.
+    #[cfg(feature = "compile")]
+    Cue(cue_rs::error::Error),
+}
+
+impl std::fmt::Display for CheckError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            Self::Syntax(error) => error.fmt(f),
+            #[cfg(feature = "compile")]
+            Self::Cue(error) => error.fmt(f),
+        }
+    }
+}
+
+impl From<SyntaxError> for CheckError {
+    fn from(error: SyntaxError) -> Self {
+        Self::Syntax(error)
+    }
+}
+
+#[cfg(feature = "compile")]
+impl From<cue_rs::error::Error> for CheckError {
+    fn from(error: cue_rs::error::Error) -> Self {
+        Self::Cue(error)
+    }
+}
+
+/// Parses `src`, and with the `compile` feature compiles and validates it.
+fn check(src: &str) -> Result<(), CheckError> {
+    cue_rs_ast::parse(src)?;
+    #[cfg(feature = "compile")]
+    {
+        let ctx = cue_rs::Ctx::new()?;
+        cue_rs::Value::compile_string(&ctx, src)?.is_valid()?;
+    }
+    Ok(())
+}
+
+/// Builds the call `name!("arg")` of a built-in macro, spanned at `span`.
+fn invoke(
+    name: &str,
+    arg: &str,
+    span: Span,
+) -> TokenStream {
+    let mut arg = Literal::string(arg);
+    arg.set_span(span);
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    let mut args = Group::new(Delimiter::Parenthesis, TokenTree::Literal(arg).into());
+    args.set_span(span);
+    [
+        TokenTree::Ident(Ident::new(name, span)),
+        TokenTree::Punct(bang),
+        TokenTree::Group(args),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use cue_rs_ast::SyntaxError;
+    use test_case::test_case;
+
+    use super::CheckError;
+
+    #[test_case(r#""schema.cue""# => Some("schema.cue") ; "plain")]
+    #[test_case(r#"r"dir\schema.cue""# => Some(r"dir\schema.cue") ; "raw")]
+    #[test_case(r##"r#"a"b.cue"#"## => Some(r#"a"b.cue"#) ; "raw_hashes")]
+    #[test_case(r#""dir\\schema.cue""# => None ; "escaped")]
+    #[test_case("b\"schema.cue\"" => None ; "byte_string")]
+    #[test_case("1" => None ; "integer")]
+    fn test_unquote(lit: &str) -> Option<&str> {
+        super::unquote(lit)
+    }
+
+    #[test_case("service.cue" => Ok(()) ; "valid")]
+    #[test_case("invalid.cue" => Err((3, 1)) ; "unclosed_struct")]
+    fn test_check(name: &str) -> Result<(), (usize, usize)> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        let src = std::fs::read_to_string(path).unwrap();
+        super::check(&src).map_err(|error| {
+            match error {
+                CheckError::Syntax(SyntaxError { line, column, .. }) => (line, column),
+                #[cfg(feature = "compile")]
+                error => panic!("{error}"),
+            }
+        })
+    }
+}
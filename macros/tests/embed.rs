@@ -0,0 +1,34 @@
+//! Embedding CUE with `embed_cue!` and `cue!`.
+
+use cue_rs_macros::{cue, embed_cue};
+
+/// The schema embedded at compile time.
+static SCHEMA: &str = embed_cue!("tests/fixtures/service.cue");
+
+#[test]
+fn embed_cue_test() {
+    assert_eq!(SCHEMA, include_str!("fixtures/service.cue"));
+}
+
+#[test]
+fn cue_test() {
+    const SCHEMA: &str = cue! {
+        #Service: {
+            name:      string & =~r"^[a-z][\w-]*$"
+            "log-level"?: "debug" | *"info"
+            type: "web" | "worker"
+            ports: [...int & >=1024]
+        }
+        svc: #Service & {name: "api", type: "web", ports: [8080]}
+    };
+    assert_eq!(
+        SCHEMA,
+        r#"#Service: {
+name: string & =~"^[a-z][\\w-]*$"
+"log-level"?: "debug" | *"info"
+type: "web" | "worker"
+ports: [...int & >=1024]
+}
+svc: #Service & {name: "api", type: "web", ports: [8080]}"#
+    );
+}
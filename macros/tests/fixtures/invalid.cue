@@ -0,0 +1,2 @@
+#Service: {
+	name: string
@@ -0,0 +1,8 @@
+package service
+
+// A deployed service.
+#Service: {
+	name:      string & =~"^[a-z][a-z0-9-]*$"
+	replicas?: int & >0 | *1
+	port:      int & >0 & <65536
+}
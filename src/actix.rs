@@ -0,0 +1,289 @@
+//! An [`actix-web`](actix_web) extractor validating request bodies against a
+//! CUE schema.
+//!
+//! [`CueValidated<T>`] reads a JSON body, validates it against the schema of
+//! the [`Validator`] registered as app data and deserializes the unified
+//! value, so defaults declared in the schema are filled in:
+//!
+//! ```no_run
+//! use actix_web::{App, HttpServer, post};
+//! use cue_rs::{actix::CueValidated, validator::Validator};
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Order {
+//!     item: String,
+//!     quantity: u32,
+//! }
+//!
+//! #[post("/orders")]
+//! async fn create(order: CueValidated<Order>) -> String {
+//!     format!("{} x {}", order.quantity, order.item)
+//! }
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! HttpServer::new(|| {
+//!     App::new()
+//!         .app_data(Validator::new("{ item: string, quantity: int & >0 | *1 }"))
+//!         .service(create)
+//! })
+//! .bind(("127.0.0.1", 8080))?
+//! .run()
+//! .await
+//! # }
+//! ```
+//!
+//! Requests that fail are answered with a [`ValidationReport`] rendered as
+//! JSON: `400 Bad Request` if the body is not valid JSON and
+//! `422 Unprocessable Entity` if it violates the schema.
+//!
+//! libcue values are not shared between threads, so each worker thread
+//! compiles the schema once into the default context (see [`Ctx::global`]).
+
+use std::{future::Future, pin::Pin};
+
+use actix_web::{
+    FromRequest, HttpRequest, HttpResponse, ResponseError,
+    dev::Payload,
+    http::{StatusCode, header::ContentType},
+    web::{Bytes, Data},
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    Ctx, Value,
+    de::Deserializer,
+    report::{Severity, ValidationReport, Violation},
+    validator::Validator,
+};
+
+/// A request body validated against a CUE schema and deserialized as `T`.
+///
+/// The schema is taken from the [`Validator`] registered with
+/// `App::app_data`, either directly or wrapped in [`Data`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CueValidated<T>(pub T);
+
+impl<T> CueValidated<T> {
+    /// Returns the deserialized body.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> std::ops::Deref for CueValidated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> std::ops::DerefMut for CueValidated<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for CueValidated<T> {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(
+        req: &HttpRequest,
+        payload: &mut Payload,
+    ) -> Self::Future {
+        let validator = req
+            .app_data::<Validator>()
+            .or_else(|| req.app_data::<Data<Validator>>().map(Data::get_ref))
+            .cloned();
+        let body = Bytes::from_request(req, payload);
+        Box::pin(async move {
+            let validator = validator.ok_or_else(|| {
+                actix_web::error::ErrorInternalServerError(
+                    "no cue_rs Validator registered as app data",
+                )
+            })?;
+            let body = body.await?;
+            Ok(Self(extract(&validator, &body)?))
+        })
+    }
+}
+
+/// Validates `body` against the schema of `validator` and deserializes the
+/// result.
+fn extract<T: DeserializeOwned>(
+    validator: &Validator,
+    body: &[u8],
+) -> Result<T, Rejection> {
+    let ctx = Ctx::global().map_err(|err| Rejection::server(&err))?;
+    let schema = validator
+        .compile_global()
+        .map_err(|err| Rejection::server(&err))?;
+    // The body is compiled as CUE, so anything but JSON is refused first.
+    serde_json::from_slice::<serde::de::IgnoredAny>(body)
+        .map_err(|err| Rejection::bad_request(&format!("body is not valid JSON: {err}")))?;
+    let data = Value::compile_bytes(ctx, body).map_err(|err| {
+        Rejection::new(
+            StatusCode::BAD_REQUEST,
+            ValidationReport::from_error(&err, None),
+        )
+    })?;
+    let value = schema
+        .validate_data(&data, crate::value::ValidateOptions::default())
+        .map_err(|err| {
+            let report = ValidationReport::from_error(&err, Some(&data))
+                .with_document(&String::from_utf8_lossy(body));
+            Rejection::new(StatusCode::UNPROCESSABLE_ENTITY, report)
+        })?;
+    let de = Deserializer::new(&value).map_err(|err| Rejection::server(&err))?;
+    T::deserialize(&de).map_err(|err| Rejection::message(&err.to_string()))
+}
+
+/// The error response of a request rejected by [`CueValidated`].
+#[derive(Debug)]
+pub struct Rejection {
+    /// Status code of the response.
+    status: StatusCode,
+    /// Violations reported in the response body.
+    report: ValidationReport,
+}
+
+impl Rejection {
+    /// Creates a rejection answered with `status` and `report`.
+    fn new(
+        status: StatusCode,
+        report: ValidationReport,
+    ) -> Self {
+        Self { status, report }
+    }
+
+    /// Creates a `422` rejection with a single violation carrying `message`.
+    fn message(message: &str) -> Self {
+        Self::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            std::iter::once(Violation {
+                path: None,
+                pointer: None,
+                expected: None,
+                actual: None,
+                message: message.to_string(),
+                severity: Severity::Error,
+                location: None,
+            })
+            .collect(),
+        )
+    }
+
+    /// Creates a `400` rejection with a single violation carrying `message`.
+    fn bad_request(message: &str) -> Self {
+        let mut rejection = Self::message(message);
+        rejection.status = StatusCode::BAD_REQUEST;
+        rejection
+    }
+
+    /// Creates a `500` rejection for an error of the server's own setup.
+    fn server(err: &crate::error::Error) -> Self {
+        let mut rejection = Self::message(&err.to_string());
+        rejection.status = StatusCode::INTERNAL_SERVER_ERROR;
+        rejection
+    }
+
+    /// Returns the violations the request was rejected for.
+    #[must_use]
+    pub fn report(&self) -> &ValidationReport {
+        &self.report
+    }
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        let messages: Vec<&str> = self.report.iter().map(|v| v.message.as_str()).collect();
+        write!(f, "request rejected: {}", messages.join("; "))
+    }
+}
+
+impl ResponseError for Rejection {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status)
+            .content_type(ContentType::json())
+            .body(self.report.to_json())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{ResponseError, body::to_bytes, http::StatusCode};
+
+    use super::Rejection;
+
+    #[actix_web::test]
+    async fn test_rejection_response() {
+        let rejection = Rejection::message("unknown variant");
+        assert_eq!(rejection.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(rejection.to_string(), "request rejected: unknown variant");
+        let body = to_bytes(rejection.error_response().into_body())
+            .await
+            .unwrap();
+        assert_eq!(
+            body,
+            r#"{"valid":false,"violations":[{"path":null,"pointer":null,"expected":null,"actual":null,"message":"unknown variant","severity":"error"}]}"#
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_extract() {
+        use actix_web::{App, HttpResponse, test, web};
+
+        use super::CueValidated;
+        use crate::validator::Validator;
+
+        #[derive(serde::Deserialize)]
+        struct Order {
+            item: String,
+            quantity: u32,
+        }
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Validator::new("{ item: string, quantity: int & >0 | *1 }"))
+                .route(
+                    "/",
+                    web::post().to(|order: CueValidated<Order>| {
+                        async move {
+                            HttpResponse::Ok().body(format!("{} x {}", order.quantity, order.item))
+                        }
+                    }),
+                ),
+        )
+        .await;
+        let call = |body: &'static str| {
+            test::TestRequest::post()
+                .uri("/")
+                .set_payload(body)
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, call(r#"{ "item": "tea" }"#)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(test::read_body(resp).await, "1 x tea");
+
+        let resp = test::call_service(&app, call(r#"{ "item": "tea", "quantity": 0 }"#)).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let resp = test::call_service(&app, call("{ item: ")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, call(r#"{ item: "tea" }"#)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, call(r#"{ "item": "t" + "ea" }"#)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
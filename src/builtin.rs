@@ -0,0 +1,188 @@
+//! Calling functions of the CUE standard library from Rust.
+//!
+//! [`Ctx::call`] applies a builtin such as `strings.ToUpper` or
+//! `list.Sort` to values, and [`Ctx::builtin_pkg`] names a package once for
+//! several calls:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! use cue_rs::{Ctx, Value};
+//!
+//! let ctx = Ctx::new()?;
+//! let name = Value::compile_string(&ctx, r#""api""#)?;
+//! let upper = ctx.call("strings.ToUpper", &[&name])?;
+//! assert_eq!(upper.to_string()?, "API");
+//!
+//! let net = ctx.builtin_pkg("net");
+//! assert!(
+//!     net.call("IPv4", &[&Value::compile_string(&ctx, r#""10.0.0.1""#)?])?
+//!         .to_bool()?
+//! );
+//! let pi = ctx.builtin_pkg("math").get("Pi")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! libcue cannot pass values to a function directly, so each argument is
+//! exported as JSON and the call compiled as CUE source.  Arguments must
+//! therefore be concrete, and bytes arrive as their base64 string.
+
+use crate::{Ctx, Value, error::Error};
+
+/// A package of the CUE standard library, see [`Ctx::builtin_pkg`].
+#[derive(Clone, Copy)]
+pub struct Package<'a> {
+    /// Context calls are compiled in.
+    ctx: &'a Ctx,
+    /// Import path of the package, such as `encoding/json`.
+    path: &'a str,
+}
+
+impl std::fmt::Debug for Package<'_> {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.debug_struct("Package")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Ctx {
+    /// Calls the builtin `name`, a package import path and a function such as
+    /// `strings.ToUpper` or `encoding/json.Marshal`, with `args`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Package::call`], and
+    /// [`Error::InvalidBuiltin`] if `name` has no package.
+    pub fn call(
+        &self,
+        name: &str,
+        args: &[&Value],
+    ) -> Result<Value, Error> {
+        let (path, func) = name
+            .rsplit_once('.')
+            .ok_or_else(|| Error::InvalidBuiltin(name.to_string()))?;
+        self.builtin_pkg(path).call(func, args)
+    }
+
+    /// Returns the standard library package with the import path `path`,
+    /// such as `list` or `encoding/json`.
+    ///
+    /// The package is not checked until it is used.
+    #[must_use]
+    pub fn builtin_pkg<'a>(
+        &'a self,
+        path: &'a str,
+    ) -> Package<'a> {
+        Package { ctx: self, path }
+    }
+}
+
+impl Package<'_> {
+    /// Calls the function `func` of the package with `args`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidBuiltin`] if the package path or `func` is
+    /// malformed, the errors of [`Value::to_json_vec`] if an argument is not
+    /// concrete, and [`Error::Cue`] if the package or function does not
+    /// exist or the call fails.
+    pub fn call(
+        &self,
+        func: &str,
+        args: &[&Value],
+    ) -> Result<Value, Error> {
+        let mut call = format!("{}(", self.member(func)?);
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                call.push_str(", ");
+            }
+            let json = arg.to_json_vec()?;
+            call.push_str(&String::from_utf8_lossy(&json));
+        }
+        call.push(')');
+        self.compile(&call)
+    }
+
+    /// Returns the value `name` of the package, such as `math.Pi`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidBuiltin`] if the package path or `name` is
+    /// malformed, and [`Error::Cue`] if it does not exist.
+    pub fn get(
+        &self,
+        name: &str,
+    ) -> Result<Value, Error> {
+        let member = self.member(name)?;
+        self.compile(&member)
+    }
+
+    /// Returns the reference to the member `name` of the package, imported
+    /// as `pkg`, checking both names.
+    fn member(
+        &self,
+        name: &str,
+    ) -> Result<String, Error> {
+        let path_ok = !self.path.is_empty()
+            && self.path.split('/').all(|elem| {
+                !elem.is_empty()
+                    && elem
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+            });
+        let name_ok = name.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !path_ok || !name_ok {
+            return Err(Error::InvalidBuiltin(format!("{}.{name}", self.path)));
+        }
+        Ok(format!("pkg.{name}"))
+    }
+
+    /// Compiles `expr`, a reference to a member of the package, as the value
+    /// of the source.
+    fn compile(
+        &self,
+        expr: &str,
+    ) -> Result<Value, Error> {
+        Value::compile_string(self.ctx, &format!("import pkg {:?}\n\n{expr}\n", self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use crate::{Ctx, Value, error::Error};
+
+    #[test_case("strings.ToUpper", &[r#""api""#]   => r#""API""#       ; "strings")]
+    #[test_case("list.Max", &["[3, 1, 2]"]        => "3"             ; "list")]
+    #[test_case("math.Abs", &["-2.5"]             => "2.5"           ; "math")]
+    #[test_case("encoding/json.Marshal", &["{a: 1}"] => r#""{\"a\":1}""# ; "nested_path")]
+    fn test_call(
+        name: &str,
+        args: &[&str],
+    ) -> String {
+        let ctx = Ctx::new().unwrap();
+        let args = args
+            .iter()
+            .map(|src| Value::compile_string(&ctx, src).unwrap())
+            .collect::<Vec<_>>();
+        let args = args.iter().collect::<Vec<_>>();
+        let value = ctx.call(name, &args).unwrap();
+        String::from_utf8(value.to_json_vec().unwrap()).unwrap()
+    }
+
+    #[test_case("ToUpper"          ; "no_package")]
+    #[test_case("strings.toUpper"  ; "unexported")]
+    #[test_case("strings.ToUpper()" ; "parens")]
+    #[test_case("\"x\".ToUpper"    ; "quoted_path")]
+    #[test_case("/strings.ToUpper" ; "empty_element")]
+    fn test_call_invalid(name: &str) {
+        let ctx = Ctx::new().unwrap();
+        assert!(matches!(ctx.call(name, &[]), Err(Error::InvalidBuiltin(_))));
+    }
+}
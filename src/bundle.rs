@@ -0,0 +1,329 @@
+//! Process-wide schema bundles loaded at startup.
+//!
+//! Services usually ship their schemas with the binary and want a broken
+//! schema to fail the process at startup rather than the first request that
+//! uses it.  [`init_with_bundle`] loads a tar archive of `.cue` files into
+//! the process-wide [`SchemaSet`], checking that every schema compiles:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let bundle = std::fs::read("schemas.tar")?;
+//! cue_rs::init_with_bundle(&bundle)?;
+//! // ...later, on any thread:
+//! let schemas = cue_rs::bundle::schemas().unwrap();
+//! let deployment = schemas.get("k8s/deployment.cue")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! libcue values belong to the context they were compiled in, so the set
+//! keeps the sources and compiles a schema into the caller's context on
+//! each lookup.
+
+use std::sync::OnceLock;
+
+use crate::{Ctx, Value, error::Error};
+
+/// Size of a tar block.
+const BLOCK: usize = 512;
+
+/// The process-wide schema set, see [`init_with_bundle`].
+static SCHEMAS: OnceLock<SchemaSet> = OnceLock::new();
+
+/// A set of named CUE schema sources.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaSet {
+    /// Schema names and sources, in archive order.
+    schemas: Vec<(String, String)>,
+}
+
+impl SchemaSet {
+    /// Reads the `.cue` files of a tar archive (as written by `tar cf`),
+    /// named by their path in the archive without a leading `./`.
+    ///
+    /// Other files, directories and links are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidBundle`] if the archive is malformed or a
+    /// schema is not valid UTF-8.
+    pub fn from_tar(archive: &[u8]) -> Result<Self, Error> {
+        let invalid = |msg: &str| Error::InvalidBundle(msg.to_string());
+        let mut schemas = Vec::new();
+        let mut long_name = None;
+        let mut offset = 0;
+        loop {
+            if offset >= archive.len() {
+                break;
+            }
+            let header = archive
+                .get(offset..offset.saturating_add(BLOCK))
+                .ok_or_else(|| invalid("truncated archive"))?;
+            if header.iter().all(|&b| b == 0) {
+                break;
+            }
+            if octal(field(header, 148, 8)) != Some(checksum(header)) {
+                return Err(invalid("bad header checksum"));
+            }
+            let size = octal(field(header, 124, 12))
+                .and_then(|size| usize::try_from(size).ok())
+                .ok_or_else(|| invalid("bad entry size"))?;
+            let start = offset.saturating_add(BLOCK);
+            let data = start
+                .checked_add(size)
+                .and_then(|end| archive.get(start..end))
+                .ok_or_else(|| invalid("truncated archive"))?;
+            offset = start.saturating_add(size.next_multiple_of(BLOCK));
+
+            let name = long_name.take().unwrap_or_else(|| header_name(header));
+            match header.get(156).copied().unwrap_or(0) {
+                b'L' => long_name = Some(c_string(data)),
+                b'0' | 0
+                    if std::path::Path::new(&name)
+                        .extension()
+                        .is_some_and(|e| e == "cue") =>
+                {
+                    let source = String::from_utf8(data.to_vec())
+                        .map_err(|_| invalid(&format!("{name}: not valid UTF-8")))?;
+                    let name = name.strip_prefix("./").unwrap_or(&name).to_string();
+                    schemas.push((name, source));
+                },
+                _ => {},
+            }
+        }
+        Ok(Self { schemas })
+    }
+
+    /// Returns the names of the schemas, in archive order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.schemas.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the source of the schema called `name`.
+    #[must_use]
+    pub fn source(
+        &self,
+        name: &str,
+    ) -> Option<&str> {
+        self.schemas
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, source)| source.as_str())
+    }
+
+    /// Compiles the schema called `name` in `ctx`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Value::compile_string`].
+    pub fn compile(
+        &self,
+        ctx: &Ctx,
+        name: &str,
+    ) -> Result<Option<Value>, Error> {
+        self.source(name)
+            .map(|source| Value::compile_string(ctx, source))
+            .transpose()
+    }
+
+    /// Compiles the schema called `name` in the default context,
+    /// see [`Ctx::global`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Ctx::global`] and [`Value::compile_string`].
+    pub fn get(
+        &self,
+        name: &str,
+    ) -> Result<Option<Value>, Error> {
+        self.compile(Ctx::global()?, name)
+    }
+
+    /// Compiles and validates every schema in a fresh context.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidBundle`] naming the first schema that fails.
+    pub fn check(&self) -> Result<(), Error> {
+        let ctx = Ctx::new()?;
+        for (name, source) in &self.schemas {
+            Value::compile_string(&ctx, source)
+                .and_then(|value| value.is_valid())
+                .map_err(|err| Error::InvalidBundle(format!("{name}: {err}")))?;
+        }
+        Ok(())
+    }
+}
+
+/// Loads the tar archive `bundle` into the process-wide [`SchemaSet`] after
+/// checking that every schema compiles, and returns the set.
+///
+/// Meant to be called once at startup, before serving traffic.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidBundle`] if the archive is malformed or a schema
+/// fails to compile, or [`Error::BundleAlreadyLoaded`] if a bundle was
+/// already loaded.
+pub fn init_with_bundle(bundle: &[u8]) -> Result<&'static SchemaSet, Error> {
+    let set = SchemaSet::from_tar(bundle)?;
+    set.check()?;
+    let mut set = Some(set);
+    let schemas = SCHEMAS.get_or_init(|| set.take().unwrap_or_default());
+    if set.is_some() {
+        return Err(Error::BundleAlreadyLoaded);
+    }
+    Ok(schemas)
+}
+
+/// Returns the process-wide schema set, if [`init_with_bundle`] was called.
+#[must_use]
+pub fn schemas() -> Option<&'static SchemaSet> {
+    SCHEMAS.get()
+}
+
+/// Returns `len` bytes of `header` from `start`.
+fn field(
+    header: &[u8],
+    start: usize,
+    len: usize,
+) -> &[u8] {
+    header
+        .get(start..start.saturating_add(len))
+        .unwrap_or_default()
+}
+
+/// Decodes a NUL-terminated string.
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(bytes.get(..end).unwrap_or_default()).into_owned()
+}
+
+/// Returns the entry path of a header, joining the ustar prefix and name.
+fn header_name(header: &[u8]) -> String {
+    let name = c_string(field(header, 0, 100));
+    let prefix = c_string(field(header, 345, 155));
+    if field(header, 257, 5) == b"ustar" && !prefix.is_empty() {
+        format!("{prefix}/{name}")
+    } else {
+        name
+    }
+}
+
+/// Decodes an octal number field, padded with spaces or NULs.
+fn octal(bytes: &[u8]) -> Option<u64> {
+    let digits = c_string(bytes);
+    let digits = digits.trim();
+    if digits.is_empty() {
+        return None;
+    }
+    u64::from_str_radix(digits, 8).ok()
+}
+
+/// Computes the checksum of a header, counting its checksum field as spaces.
+fn checksum(header: &[u8]) -> u64 {
+    header.iter().enumerate().fold(0, |sum, (i, &b)| {
+        let b = if (148..156).contains(&i) { b' ' } else { b };
+        sum.saturating_add(u64::from(b))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{BLOCK, SchemaSet, init_with_bundle, schemas};
+    use crate::error::Error;
+
+    /// Appends a ustar entry to `archive`.
+    fn entry(
+        archive: &mut Vec<u8>,
+        name: &str,
+        kind: u8,
+        data: &[u8],
+    ) {
+        let mut header = [0u8; BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+        header[148..155].copy_from_slice(format!("{sum:06o}\0").as_bytes());
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(data);
+        archive.resize(archive.len().next_multiple_of(BLOCK), 0);
+    }
+
+    fn archive(entries: &[(&str, u8, &str)]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        for (name, kind, data) in entries {
+            entry(&mut archive, name, *kind, data.as_bytes());
+        }
+        archive.extend_from_slice(&[0; 2 * BLOCK]);
+        archive
+    }
+
+    #[test]
+    fn test_from_tar() {
+        let long = format!("{}/deep.cue", "d".repeat(120));
+        let tar = archive(&[
+            ("./schemas/", b'5', ""),
+            ("./schemas/a.cue", b'0', "a: int"),
+            ("./README.md", b'0', "# schemas"),
+            ("././@LongLink", b'L', &long),
+            ("truncated-name", b'0', "b: string"),
+        ]);
+        let set = SchemaSet::from_tar(&tar).unwrap();
+        assert_eq!(set.names().collect::<Vec<_>>(), [
+            "schemas/a.cue",
+            long.as_str()
+        ]);
+        assert_eq!(set.source("schemas/a.cue"), Some("a: int"));
+        assert_eq!(set.source(&long), Some("b: string"));
+        assert_eq!(set.source("README.md"), None);
+    }
+
+    #[test_case(&[0; 100] => "invalid schema bundle: truncated archive"; "partial block")]
+    #[test_case(&[1; BLOCK] => "invalid schema bundle: bad header checksum"; "garbage")]
+    fn test_from_tar_invalid(archive: &[u8]) -> String {
+        SchemaSet::from_tar(archive).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_from_tar_empty() {
+        assert_eq!(SchemaSet::from_tar(&[]).unwrap().names().count(), 0);
+    }
+
+    #[test]
+    fn test_from_tar_truncated_data() {
+        let mut tar = archive(&[("a.cue", b'0', "a: int")]);
+        tar.truncate(BLOCK + 2);
+        assert!(matches!(
+            SchemaSet::from_tar(&tar),
+            Err(Error::InvalidBundle(msg)) if msg == "truncated archive"
+        ));
+    }
+
+    #[test]
+    fn test_init_with_bundle() {
+        let broken = archive(&[("ok.cue", b'0', "a: int"), ("bad.cue", b'0', "a: 1 & 2")]);
+        assert!(matches!(
+            init_with_bundle(&broken),
+            Err(Error::InvalidBundle(msg)) if msg.starts_with("bad.cue: ")
+        ));
+        assert!(schemas().is_none());
+
+        let bundle = archive(&[("a.cue", b'0', "a: int & >0")]);
+        init_with_bundle(&bundle).unwrap();
+        assert!(matches!(
+            init_with_bundle(&bundle),
+            Err(Error::BundleAlreadyLoaded)
+        ));
+        let a = schemas().unwrap().get("a.cue").unwrap().unwrap();
+        assert!(a.lookup("a").is_ok());
+        assert!(schemas().unwrap().get("b.cue").unwrap().is_none());
+    }
+}
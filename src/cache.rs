@@ -0,0 +1,129 @@
+//! Reuse of compiled schemas.
+//!
+//! Services often compile the same handful of schemas for every request.
+//! A [`SchemaCache`] sits in front of [`Value::compile_string`] and
+//! [`Value::compile_bytes`] and hands out the value compiled the first time
+//! a source was seen:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! use cue_rs::{Ctx, Value, cache::SchemaCache};
+//!
+//! let ctx = Ctx::new()?;
+//! let mut cache = SchemaCache::new(&ctx);
+//! for body in [r#"{ "port": 80 }"#, r#"{ "port": 443 }"#] {
+//!     let schema = cache.compile_string("{ port: int }")?;
+//!     Value::unify(&schema, &Value::compile_string(&ctx, body)?).is_valid()?;
+//! }
+//! assert_eq!(cache.len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{Ctx, Value, error::Error};
+
+/// A cache of values compiled in one context, keyed by their source.
+///
+/// Only successful compilations are cached.  Values are immutable, so a
+/// cached value can be shared freely by its users.
+pub struct SchemaCache<'a> {
+    /// Context the values are compiled in.
+    ctx: &'a Ctx,
+    /// Compiled values by source.
+    values: HashMap<Box<[u8]>, Rc<Value>>,
+}
+
+impl<'a> SchemaCache<'a> {
+    /// Creates an empty cache compiling values in `ctx`.
+    #[must_use]
+    pub fn new(ctx: &'a Ctx) -> Self {
+        Self {
+            ctx,
+            values: HashMap::new(),
+        }
+    }
+
+    /// Returns the value compiled from `src`, compiling it with
+    /// [`Value::compile_string`] if it is not cached yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Value::compile_string`].
+    pub fn compile_string(
+        &mut self,
+        src: &str,
+    ) -> Result<Rc<Value>, Error> {
+        self.get_or_compile(src.as_bytes(), |ctx| Value::compile_string(ctx, src))
+    }
+
+    /// Returns the value compiled from `src`, compiling it with
+    /// [`Value::compile_bytes`] if it is not cached yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Value::compile_bytes`].
+    pub fn compile_bytes(
+        &mut self,
+        src: &[u8],
+    ) -> Result<Rc<Value>, Error> {
+        self.get_or_compile(src, |ctx| Value::compile_bytes(ctx, src))
+    }
+
+    /// Returns the number of cached values.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if no value is cached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Removes every cached value.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Looks `src` up, calling `compile` and caching its value on a miss.
+    fn get_or_compile(
+        &mut self,
+        src: &[u8],
+        compile: impl FnOnce(&Ctx) -> Result<Value, Error>,
+    ) -> Result<Rc<Value>, Error> {
+        if let Some(value) = self.values.get(src) {
+            return Ok(Rc::clone(value));
+        }
+        let value = Rc::new(compile(self.ctx)?);
+        self.values.insert(src.into(), Rc::clone(&value));
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::SchemaCache;
+    use crate::Ctx;
+
+    #[test]
+    fn test_schema_cache() {
+        let ctx = Ctx::new().unwrap();
+        let mut cache = SchemaCache::new(&ctx);
+        let a = cache.compile_string("{ a: int }").unwrap();
+        let b = cache.compile_bytes(b"{ a: int }").unwrap();
+        assert!(Rc::ptr_eq(&a, &b));
+        cache.compile_string("{ b: int }").unwrap();
+        assert_eq!(cache.len(), 2);
+
+        assert!(cache.compile_string("{ a: }").is_err());
+        assert_eq!(cache.len(), 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
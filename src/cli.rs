@@ -0,0 +1,265 @@
+//! Validating command line arguments parsed by [`clap`] against a CUE
+//! schema.
+//!
+//! clap checks each argument on its own; interdependencies between them
+//! ("`--tls-cert` is required when `--port` is 443", "at most one of ...")
+//! are easier to state in CUE:
+//!
+//! ```text
+//! port:       int & >0 & <65536
+//! "tls-cert"?: string
+//! if port == 443 {
+//!     "tls-cert"!: string
+//! }
+//! ```
+//!
+//! [`validate_matches`] turns the parsed arguments into a struct keyed by
+//! argument ID, unifies it with such a schema and reports violations as a
+//! [`clap::Error`], which prints and exits like clap's own errors:
+//!
+//! ```no_run
+//! use clap::{CommandFactory, FromArgMatches, Parser};
+//! use cue_rs::{Ctx, Value, cli};
+//!
+//! #[derive(Parser)]
+//! struct Args {
+//!     #[arg(long)]
+//!     port: u16,
+//!     #[arg(long)]
+//!     tls_cert: Option<String>,
+//! }
+//!
+//! let ctx = Ctx::new().unwrap();
+//! let schema = Value::compile_string(
+//!     &ctx,
+//!     r#"{ port: int, tls_cert?: string, if port == 443 { tls_cert!: string } }"#,
+//! )
+//! .unwrap();
+//! let cmd = Args::command();
+//! let matches = cmd.clone().get_matches();
+//! cli::validate_matches(&ctx, &schema, &cmd, &matches).unwrap_or_else(|err| err.exit());
+//! let args = Args::from_arg_matches(&matches).unwrap();
+//! ```
+//!
+//! Flags become booleans, counted flags and numeric arguments numbers,
+//! arguments taking several values lists and other values strings.  The
+//! arguments of a subcommand are nested under its name.
+
+use std::{ffi::OsString, path::PathBuf};
+
+use clap::{ArgAction, ArgMatches, Command, error::ErrorKind};
+use serde_json::{Map, Value as Json};
+
+use crate::{Ctx, Value, report::Report};
+
+/// Validates the arguments in `matches`, parsed by `cmd`, against `schema`,
+/// returning the unified value.
+///
+/// `ctx` must be the context the schema was compiled in.
+///
+/// # Errors
+///
+/// Returns a [`clap::Error`] formatted for `cmd` listing every violation.
+/// Its kind is [`ErrorKind::MissingRequiredArgument`] if the schema
+/// requires an argument that was not given, [`ErrorKind::ArgumentConflict`]
+/// if the schema does not allow an argument and
+/// [`ErrorKind::ValueValidation`] otherwise.
+pub fn validate_matches(
+    ctx: &Ctx,
+    schema: &Value,
+    cmd: &Command,
+    matches: &ArgMatches,
+) -> Result<Value, clap::Error> {
+    // Argument settings such as the number of values are only final once
+    // the command is built.
+    let mut cmd = cmd.clone();
+    cmd.build();
+    let args = Json::Object(matches_json(&cmd, matches));
+    let result = serde_json::to_vec(&args)
+        .map_err(|err| crate::error::Error::Serialize(err.to_string()))
+        .and_then(|data| schema.validate_json(ctx, &data));
+    result.map_err(|err| {
+        let (kind, message) = clap_message(&cmd, &Report::from(&err));
+        clap::Error::raw(kind, message).format(&mut cmd)
+    })
+}
+
+/// Converts parsed arguments into a JSON object keyed by argument ID, with
+/// the arguments of the subcommand, if any, nested under its name.
+fn matches_json(
+    cmd: &Command,
+    matches: &ArgMatches,
+) -> Map<String, Json> {
+    let mut args = Map::new();
+    for arg in cmd.get_arguments() {
+        let id = arg.get_id().as_str();
+        let many = matches!(arg.get_action(), ArgAction::Append)
+            || arg.get_num_args().is_some_and(|n| n.max_values() > 1);
+        if let Some(values) = arg_values(matches, id) {
+            let value = match <[Json; 1]>::try_from(values) {
+                Ok([value]) if !many => value,
+                Ok(values) => Json::Array(values.into()),
+                Err(values) => Json::Array(values),
+            };
+            args.insert(id.to_string(), value);
+        }
+    }
+    if let Some((name, sub)) = matches.subcommand()
+        && let Some(sub_cmd) = cmd.find_subcommand(name)
+    {
+        args.insert(name.to_string(), Json::Object(matches_json(sub_cmd, sub)));
+    }
+    args
+}
+
+/// Returns the values of the argument `id`, converted according to the
+/// type clap parsed them into, or `None` if it has none.
+fn arg_values(
+    matches: &ArgMatches,
+    id: &str,
+) -> Option<Vec<Json>> {
+    /// Tries each type in turn, as values are stored type-erased.
+    macro_rules! typed {
+        ($($ty:ty => $conv:expr),* $(,)?) => {
+            $(
+                if let Ok(values) = matches.try_get_many::<$ty>(id) {
+                    return values.map(|values| values.map($conv).collect());
+                }
+            )*
+        };
+    }
+    typed!(
+        bool => |v| Json::Bool(*v),
+        u8 => |v| Json::from(*v),
+        u16 => |v| Json::from(*v),
+        u32 => |v| Json::from(*v),
+        u64 => |v| Json::from(*v),
+        usize => |v| Json::from(*v),
+        i8 => |v| Json::from(*v),
+        i16 => |v| Json::from(*v),
+        i32 => |v| Json::from(*v),
+        i64 => |v| Json::from(*v),
+        isize => |v| Json::from(*v),
+        f32 => |v| Json::from(f64::from(*v)),
+        f64 => |v| Json::from(*v),
+        String => |v| Json::String(v.clone()),
+        PathBuf => |v| Json::String(v.to_string_lossy().into_owned()),
+        OsString => |v| Json::String(v.to_string_lossy().into_owned()),
+    );
+    None
+}
+
+/// Renders the diagnostics of `report` as a clap error message, naming the
+/// arguments as clap does (e.g. `--port <PORT>`).
+fn clap_message(
+    cmd: &Command,
+    report: &Report,
+) -> (ErrorKind, String) {
+    let mut kind = ErrorKind::ValueValidation;
+    let mut lines = Vec::new();
+    for d in report.diagnostics() {
+        let arg = d
+            .path
+            .as_deref()
+            .map(|path| arg_name(cmd, path))
+            .unwrap_or_default();
+        let line = match d.rule_id() {
+            "cue/incomplete-value" => {
+                kind = ErrorKind::MissingRequiredArgument;
+                format!("the argument '{arg}' is required ({})", d.message)
+            },
+            "cue/field-not-allowed" => {
+                kind = ErrorKind::ArgumentConflict;
+                format!("the argument '{arg}' is not allowed here")
+            },
+            _ if arg.is_empty() => d.message.clone(),
+            _ => format!("invalid value for '{arg}': {}", d.message),
+        };
+        lines.push(line);
+    }
+    (kind, lines.join("\n"))
+}
+
+/// Returns the display name of the argument at the error path `path`,
+/// following subcommands, or the path itself if no argument matches.
+fn arg_name(
+    cmd: &Command,
+    path: &str,
+) -> String {
+    let mut cmd = cmd;
+    for label in path.split('.') {
+        let label = label.trim_matches('"');
+        if let Some(arg) = cmd.get_arguments().find(|a| a.get_id() == label) {
+            return arg.to_string();
+        }
+        match cmd.find_subcommand(label) {
+            Some(sub) => cmd = sub,
+            None => break,
+        }
+    }
+    path.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{Arg, ArgAction, Command, error::ErrorKind, value_parser};
+    use serde_json::json;
+
+    use super::{clap_message, matches_json};
+    use crate::report::Report;
+
+    fn command() -> Command {
+        let mut cmd = Command::new("app")
+            .arg(
+                Arg::new("port")
+                    .long("port")
+                    .value_parser(value_parser!(u16)),
+            )
+            .arg(Arg::new("verbose").short('v').action(ArgAction::Count))
+            .arg(
+                Arg::new("dry-run")
+                    .long("dry-run")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(Arg::new("tag").long("tag").action(ArgAction::Append))
+            .subcommand(
+                Command::new("serve")
+                    .arg(Arg::new("dir").value_parser(value_parser!(std::path::PathBuf))),
+            );
+        cmd.build();
+        cmd
+    }
+
+    #[test]
+    fn test_matches_json() {
+        let cmd = command();
+        let matches = cmd
+            .clone()
+            .try_get_matches_from(["app", "--port", "443", "-vv", "--tag", "a", "serve", "www"])
+            .unwrap();
+        assert_eq!(
+            serde_json::Value::Object(matches_json(&cmd, &matches)),
+            json!({
+                "port": 443,
+                "verbose": 2,
+                "dry-run": false,
+                "tag": ["a"],
+                "serve": { "dir": "www" }
+            })
+        );
+    }
+
+    #[test]
+    fn test_clap_message() {
+        let report = Report::parse(
+            "port: invalid value 0 (out of bound >0)\nserve.dir: incomplete value string",
+        );
+        let (kind, message) = clap_message(&command(), &report);
+        assert_eq!(kind, ErrorKind::MissingRequiredArgument);
+        assert_eq!(
+            message,
+            "invalid value for '--port <port>': invalid value 0 (out of bound >0)\nthe argument \
+             '[dir]' is required (incomplete value string)"
+        );
+    }
+}
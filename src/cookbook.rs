@@ -0,0 +1,144 @@
+//! Known-good building blocks for common CUE validation tasks.
+//!
+//! Each recipe is a small function over the core [`Value`] API, so it can be
+//! used directly or copied as a starting point:
+//!
+//! - [`validate_k8s_manifest`] checks a Kubernetes manifest against a built-in schema
+//!   covering object metadata and `Deployment` specs.
+//! - [`layered_config`] unifies configuration layers (e.g. defaults, then environment,
+//!   then overrides) into one validated value.
+//! - [`schema_regressions`] is a CI check that finds sample documents accepted by an old
+//!   schema but rejected by a new one.
+//! - [`validate_helm_values`] checks a Helm chart's `values.yaml` against the chart's CUE
+//!   schema, and [`render_helm_values`] writes evaluated values, such as the schema's
+//!   defaults, back out as YAML.
+
+use crate::{Ctx, Value, error::Error};
+
+/// Schema used by [`validate_k8s_manifest`].
+///
+/// Covers the object metadata shared by every Kubernetes kind and the
+/// required parts of a `Deployment`; other fields are left open.
+pub const K8S_MANIFEST_SCHEMA: &str = include_str!("lint/kubernetes.cue");
+
+/// Validates a Kubernetes manifest, given as JSON, against
+/// [`K8S_MANIFEST_SCHEMA`].
+///
+/// # Errors
+///
+/// Returns [`Error::Cue`] if the manifest cannot be compiled or does not
+/// satisfy the schema.
+pub fn validate_k8s_manifest(
+    ctx: &Ctx,
+    manifest: &[u8],
+) -> Result<(), Error> {
+    let schema = Value::compile_string(ctx, K8S_MANIFEST_SCHEMA)?;
+    let data = Value::compile_bytes(ctx, manifest)?;
+    Value::unify(&schema, &data).is_valid()
+}
+
+/// Compiles every layer and unifies them in order into a single value.
+///
+/// Layers are typically a schema with defaults followed by increasingly
+/// specific configuration; since unification is commutative, a later layer
+/// can only refine earlier ones, never silently override them.
+///
+/// # Errors
+///
+/// Returns [`Error::Cue`] if a layer cannot be compiled or the layers
+/// conflict.
+pub fn layered_config(
+    ctx: &Ctx,
+    layers: &[&str],
+) -> Result<Value, Error> {
+    let mut config = Value::compile_string(ctx, "_")?;
+    for layer in layers {
+        config = Value::unify(&config, &Value::compile_string(ctx, layer)?);
+    }
+    config.is_valid()?;
+    Ok(config)
+}
+
+/// Returns the indices of the `samples` that `old_schema` accepts but
+/// `new_schema` rejects.
+///
+/// An empty result means the new schema is backwards compatible with respect
+/// to the given samples, which makes this suitable as a CI gate on schema
+/// changes.
+///
+/// # Errors
+///
+/// Returns [`Error::Cue`] if a schema or a sample cannot be compiled.
+pub fn schema_regressions(
+    ctx: &Ctx,
+    old_schema: &str,
+    new_schema: &str,
+    samples: &[&[u8]],
+) -> Result<Vec<usize>, Error> {
+    let old_schema = Value::compile_string(ctx, old_schema)?;
+    let new_schema = Value::compile_string(ctx, new_schema)?;
+    let mut regressions = Vec::new();
+    for (i, sample) in samples.iter().enumerate() {
+        let data = Value::compile_bytes(ctx, sample)?;
+        if Value::unify(&old_schema, &data).is_valid().is_ok()
+            && Value::unify(&new_schema, &data).is_valid().is_err()
+        {
+            regressions.push(i);
+        }
+    }
+    Ok(regressions)
+}
+
+/// Validates the Helm values `values`, the contents of a `values.yaml`,
+/// against the CUE schema of a chart, returning the values with the
+/// schema's defaults filled in.
+///
+/// # Errors
+///
+/// Returns [`Error::Cue`] if the schema or the values cannot be compiled,
+/// the values do not satisfy the schema or leave a field without a default
+/// unset.
+pub fn validate_helm_values(
+    ctx: &Ctx,
+    schema: &str,
+    values: &str,
+) -> Result<Value, Error> {
+    Value::compile_string(ctx, schema)?.validate_yaml(ctx, values)
+}
+
+/// Renders evaluated Helm values as YAML, e.g. to generate the default
+/// `values.yaml` of a chart from its schema:
+///
+/// ```no_run
+/// # fn main() -> Result<(), cue_rs::error::Error> {
+/// use cue_rs::{Ctx, Value, cookbook};
+///
+/// let ctx = Ctx::new()?;
+/// let schema = Value::compile_string(
+///     &ctx,
+///     r#"{ replicaCount: int & >0 | *1, image: { repository: string | *"nginx", tag?: string } }"#,
+/// )?;
+/// assert_eq!(
+///     cookbook::render_helm_values(&ctx, &schema)?,
+///     "replicaCount: 1\nimage:\n  repository: nginx\n"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Optional fields without a value are left out.
+///
+/// # Errors
+///
+/// Returns [`Error::Cue`] if a required field of `values` has no concrete
+/// value.
+pub fn render_helm_values(
+    ctx: &Ctx,
+    values: &Value,
+) -> Result<String, Error> {
+    // JSON is valid CUE, so the exported values can be marshalled as a
+    // literal.
+    let json = String::from_utf8_lossy(&values.to_json_vec()?).into_owned();
+    let src = format!("import \"encoding/yaml\"\n\nyaml.Marshal({json})\n");
+    Value::compile_string(ctx, &src)?.to_string()
+}
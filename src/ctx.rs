@@ -1,6 +1,8 @@
 //! CUE evaluation context, wrapping the `cue_ctx` handle from libcue.
 
-use crate::{drop, error::Error};
+use std::sync::{Arc, OnceLock};
+
+use crate::{drop, error::Error, hooks::Hooks, limits::Limits};
 
 /// Opaque handle to a libcue context (`cue_ctx` = `uintptr_t`).
 type CueCtxHandle = usize;
@@ -11,24 +13,57 @@ unsafe extern "C" {
     fn cue_newctx() -> CueCtxHandle;
 }
 
+/// Lazily created process-wide default context, see [`Ctx::global`].
+static GLOBAL: OnceLock<Ctx> = OnceLock::new();
+
 /// A CUE evaluation context backed by a libcue `cue_ctx` handle.
 ///
 /// This is the entry point for all CUE operations. Obtain one via
 /// [`Ctx::new`]; the underlying context is freed automatically when this
 /// value is dropped.
-pub struct Ctx(CueCtxHandle);
+pub struct Ctx {
+    /// Raw libcue context handle.
+    handle: CueCtxHandle,
+    /// Resource limits enforced by operations using this context.
+    limits: Limits,
+    /// Lifecycle hooks, shared with the values created in this context.
+    hooks: Option<Arc<Hooks>>,
+}
 
 impl Drop for Ctx {
     /// Frees the underlying libcue context via `cue_free`.
     fn drop(&mut self) {
-        unsafe { drop::cue_free(self.0) }
+        unsafe { drop::cue_free(self.handle) }
     }
 }
 
 impl Ctx {
     /// Returns the raw libcue context handle.
     pub(crate) fn handle(&self) -> usize {
-        self.0
+        self.handle
+    }
+
+    /// Returns the resource limits of this context.
+    #[must_use]
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Returns the lifecycle hooks of this context, if any are registered.
+    pub(crate) fn hooks(&self) -> Option<&Arc<Hooks>> {
+        self.hooks.as_ref()
+    }
+
+    /// Registers lifecycle hooks, replacing any previously set.
+    ///
+    /// The hooks apply to values compiled from now on and to the values
+    /// derived from them; values compiled earlier keep the hooks they were
+    /// created with.
+    pub fn set_hooks(
+        &mut self,
+        hooks: Hooks,
+    ) {
+        self.hooks = (!hooks.is_empty()).then(|| Arc::new(hooks));
     }
 
     /// Creates a new CUE evaluation context.
@@ -40,11 +75,46 @@ impl Ctx {
     /// Returns [`Error::ContextCreationFailed`] if `cue_newctx` returns 0,
     /// indicating that the libcue runtime could not allocate a context.
     pub fn new() -> Result<Self, Error> {
+        Self::with_limits(Limits::default())
+    }
+
+    /// Creates a new CUE evaluation context enforcing the given resource
+    /// limits, for evaluating untrusted input.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ContextCreationFailed`] if `cue_newctx` returns 0.
+    pub fn with_limits(limits: Limits) -> Result<Self, Error> {
         let handle = unsafe { cue_newctx() };
         if handle == 0 {
             return Err(Error::ContextCreationFailed);
         }
-        Ok(Self(handle))
+        Ok(Self {
+            handle,
+            limits,
+            hooks: None,
+        })
+    }
+
+    /// Returns the default context of the process.
+    ///
+    /// The context is created on the first successful call and shared by
+    /// every thread, so callers that do not need several contexts don't have
+    /// to thread a `&Ctx` through every call site.  It lives until the
+    /// process exits and is never freed.  If it cannot be created, the next
+    /// call tries again.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ContextCreationFailed`] if the context could not be
+    /// created.
+    pub fn global() -> Result<&'static Self, Error> {
+        if let Some(ctx) = GLOBAL.get() {
+            return Ok(ctx);
+        }
+        // A context created by a racing thread wins; this one is dropped.
+        let ctx = Self::new()?;
+        Ok(GLOBAL.get_or_init(|| ctx))
     }
 }
 
@@ -56,4 +126,14 @@ mod tests {
     fn test_new_succeeds() {
         assert!(Ctx::new().is_ok());
     }
+
+    #[test]
+    fn test_global_is_shared() {
+        let a = Ctx::global().unwrap().handle();
+        assert_eq!(a, Ctx::global().unwrap().handle());
+        let b = std::thread::spawn(|| Ctx::global().unwrap().handle())
+            .join()
+            .unwrap();
+        assert_eq!(a, b);
+    }
 }
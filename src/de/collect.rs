@@ -0,0 +1,771 @@
+//! A deserializer that keeps going after a field error, see
+//! [`Deserializer::deserialize_all`](super::Deserializer::deserialize_all).
+//!
+//! It walks the parsed JSON tree and, wherever the data does not match what
+//! the visitor expects (wrong type, out of range integer, missing field,
+//! unknown variant), records a [`FieldError`] at the current path and feeds
+//! the visitor a placeholder (zero, empty string, empty list, ...) instead,
+//! so the remaining fields are still checked.
+
+use std::cell::RefCell;
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, Expected, IntoDeserializer, MapAccess,
+    SeqAccess, Unexpected, VariantAccess, Visitor, value::StrDeserializer,
+};
+use serde_json::Value as Json;
+
+/// An error found while deserializing a single field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Path of the field, such as `spec.containers[0].image`; empty for the
+    /// root value.
+    pub path: String,
+    /// Human readable error message.
+    pub message: String,
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+/// Error type of the collecting deserializer.
+///
+/// Only raised by visitors rejecting a placeholder or by custom
+/// `Deserialize` implementations; it aborts deserialization.
+#[derive(Debug)]
+struct Error {
+    /// The error message.
+    message: String,
+    /// Whether the error was already added to the collected errors.
+    recorded: bool,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+            recorded: false,
+        }
+    }
+}
+
+/// Null value fed to the payload of a unit-like enum variant.
+static NULL: Json = Json::Null;
+
+/// Deserializes `json` as a `T`, returning every error found.
+pub(super) fn deserialize_all<T: DeserializeOwned>(json: &Json) -> Result<T, Vec<FieldError>> {
+    let errors = RefCell::new(Vec::new());
+    let result = T::deserialize(Collect {
+        input: Input::Present(json),
+        path: String::new(),
+        errors: &errors,
+    });
+    let mut errors = errors.into_inner();
+    match result {
+        Ok(value) if errors.is_empty() => Ok(value),
+        Ok(_) => Err(errors),
+        Err(err) => {
+            if !err.recorded {
+                errors.push(FieldError {
+                    path: String::new(),
+                    message: err.message,
+                });
+            }
+            Err(errors)
+        },
+    }
+}
+
+/// What a [`Collect`] deserializer reads from.
+#[derive(Debug, Clone, Copy)]
+enum Input<'a> {
+    /// A value present in the data.
+    Present(&'a Json),
+    /// A struct field or tuple element absent from the data.
+    Missing,
+    /// A placeholder below an error that was already reported.
+    Silent,
+}
+
+/// A deserializer over one node of the JSON tree that records mismatches.
+struct Collect<'a, 'e> {
+    /// The node to deserialize.
+    input: Input<'a>,
+    /// Path of the node, for error reporting.
+    path: String,
+    /// Errors collected so far.
+    errors: &'e RefCell<Vec<FieldError>>,
+}
+
+impl<'a> Collect<'a, '_> {
+    /// Returns a deserializer for the struct field or map entry `key`.
+    fn field(
+        &self,
+        key: &str,
+        input: Input<'a>,
+    ) -> Self {
+        let path = if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{key}", self.path)
+        };
+        self.child(path, input)
+    }
+
+    /// Returns a deserializer for the list element `index`.
+    fn element(
+        &self,
+        index: usize,
+        input: Input<'a>,
+    ) -> Self {
+        self.child(format!("{}[{index}]", self.path), input)
+    }
+
+    /// Returns a deserializer for `input` at `path`; below a silent node
+    /// every node is silent.
+    fn child(
+        &self,
+        path: String,
+        input: Input<'a>,
+    ) -> Self {
+        let input = match self.input {
+            Input::Silent => Input::Silent,
+            _ => input,
+        };
+        Self {
+            input,
+            path,
+            errors: self.errors,
+        }
+    }
+
+    /// Records `message` at the current path.
+    fn record(
+        &self,
+        message: String,
+    ) {
+        self.errors.borrow_mut().push(FieldError {
+            path: self.path.clone(),
+            message,
+        });
+    }
+
+    /// Records that the input does not have the type `expected` wants.
+    fn mismatch(
+        &self,
+        expected: &dyn Expected,
+    ) {
+        match self.input {
+            Input::Present(json) => {
+                self.record(<Error as de::Error>::invalid_type(unexpected(json), expected).message);
+            },
+            Input::Missing => self.record("missing field".to_string()),
+            Input::Silent => {},
+        }
+    }
+
+    /// Records that the input has the right type but a value `expected`
+    /// rejects.
+    fn invalid(
+        &self,
+        expected: &dyn Expected,
+    ) {
+        if let Input::Present(json) = self.input {
+            self.record(<Error as de::Error>::invalid_value(unexpected(json), expected).message);
+        }
+    }
+
+    /// Records an error returned by a visitor, which aborts deserialization.
+    fn finish<T>(
+        &self,
+        result: Result<T, Error>,
+    ) -> Result<T, Error> {
+        result.map_err(|mut err| {
+            if !err.recorded {
+                self.record(err.message.clone());
+                err.recorded = true;
+            }
+            err
+        })
+    }
+
+    /// Visits the elements of `items`, padded with missing elements up to
+    /// `len`.
+    fn visit_seq<'de, V: Visitor<'de>>(
+        self,
+        items: &'a [Json],
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let mut inputs: Vec<Input<'a>> = items.iter().map(Input::Present).collect();
+        inputs.resize(len.max(items.len()), Input::Missing);
+        let seq = Seq {
+            parent: &self,
+            items: inputs.into_iter().enumerate(),
+        };
+        self.finish(visitor.visit_seq(seq))
+    }
+
+    /// Visits the entries of `entries` followed by the missing `fields`.
+    fn visit_map<'de, V: Visitor<'de>>(
+        self,
+        entries: Option<&'a serde_json::Map<String, Json>>,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let mut inputs: Vec<(&'a str, Input<'a>)> = entries
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), Input::Present(value)))
+            .collect();
+        let missing = fields
+            .iter()
+            .filter(|field| !entries.is_some_and(|entries| entries.contains_key(**field)));
+        inputs.extend(missing.map(|field| (*field, Input::Missing)));
+        let map = Map {
+            parent: &self,
+            entries: inputs.into_iter(),
+            value: None,
+        };
+        self.finish(visitor.visit_map(map))
+    }
+}
+
+/// Describes `json` for error messages.
+fn unexpected(json: &Json) -> Unexpected<'_> {
+    match json {
+        Json::Null => Unexpected::Unit,
+        Json::Bool(b) => Unexpected::Bool(*b),
+        Json::Number(n) => {
+            if let Some(n) = n.as_u64() {
+                Unexpected::Unsigned(n)
+            } else if let Some(n) = n.as_i64() {
+                Unexpected::Signed(n)
+            } else {
+                Unexpected::Float(n.as_f64().unwrap_or(f64::NAN))
+            }
+        },
+        Json::String(s) => Unexpected::Str(s),
+        Json::Array(_) => Unexpected::Seq,
+        Json::Object(_) => Unexpected::Map,
+    }
+}
+
+/// Implements integer deserialization, with zero as the placeholder.
+macro_rules! integer {
+    ($($method:ident: $ty:ty, $visit:ident, $as:ident;)*) => {$(
+        fn $method<V: Visitor<'de>>(
+            self,
+            visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            if let Input::Present(Json::Number(n)) = self.input {
+                if let Some(v) = n.$as().and_then(|v| <$ty>::try_from(v).ok()) {
+                    return self.finish(visitor.$visit(v));
+                }
+                self.invalid(&visitor);
+            } else {
+                self.mismatch(&visitor);
+            }
+            self.finish(visitor.$visit(0))
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for Collect<'_, '_> {
+    type Error = Error;
+
+    integer! {
+        deserialize_i8: i8, visit_i8, as_i64;
+        deserialize_i16: i16, visit_i16, as_i64;
+        deserialize_i32: i32, visit_i32, as_i64;
+        deserialize_i64: i64, visit_i64, as_i64;
+        deserialize_i128: i128, visit_i128, as_i64;
+        deserialize_u8: u8, visit_u8, as_u64;
+        deserialize_u16: u16, visit_u16, as_u64;
+        deserialize_u32: u32, visit_u32, as_u64;
+        deserialize_u64: u64, visit_u64, as_u64;
+        deserialize_u128: u128, visit_u128, as_u64;
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.input {
+            Input::Present(Json::Null) => self.finish(visitor.visit_unit()),
+            Input::Present(Json::Bool(b)) => self.finish(visitor.visit_bool(*b)),
+            Input::Present(Json::Number(n)) => {
+                if let Some(n) = n.as_u64() {
+                    self.finish(visitor.visit_u64(n))
+                } else if let Some(n) = n.as_i64() {
+                    self.finish(visitor.visit_i64(n))
+                } else {
+                    self.finish(visitor.visit_f64(n.as_f64().unwrap_or(f64::NAN)))
+                }
+            },
+            Input::Present(Json::String(s)) => self.finish(visitor.visit_str(s)),
+            Input::Present(Json::Array(items)) => self.visit_seq(items, 0, visitor),
+            Input::Present(Json::Object(entries)) => self.visit_map(Some(entries), &[], visitor),
+            Input::Missing | Input::Silent => {
+                self.mismatch(&visitor);
+                self.finish(visitor.visit_unit())
+            },
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if let Input::Present(Json::Bool(b)) = self.input {
+            return self.finish(visitor.visit_bool(*b));
+        }
+        self.mismatch(&visitor);
+        self.finish(visitor.visit_bool(false))
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if let Input::Present(Json::Number(n)) = self.input
+            && let Some(n) = n.as_f64()
+        {
+            return self.finish(visitor.visit_f64(n));
+        }
+        self.mismatch(&visitor);
+        self.finish(visitor.visit_f64(0.0))
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if let Input::Present(Json::String(s)) = self.input {
+            let mut chars = s.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                return self.finish(visitor.visit_char(c));
+            }
+            self.invalid(&visitor);
+        } else {
+            self.mismatch(&visitor);
+        }
+        self.finish(visitor.visit_char('\0'))
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if let Input::Present(Json::String(s)) = self.input {
+            return self.finish(visitor.visit_str(s));
+        }
+        self.mismatch(&visitor);
+        self.finish(visitor.visit_str(""))
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.input {
+            Input::Present(Json::String(s)) => self.finish(visitor.visit_bytes(s.as_bytes())),
+            Input::Present(Json::Array(items)) => self.visit_seq(items, 0, visitor),
+            _ => {
+                self.mismatch(&visitor);
+                self.finish(visitor.visit_bytes(&[]))
+            },
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.input {
+            Input::Present(Json::Null) | Input::Missing | Input::Silent => {
+                self.finish(visitor.visit_none())
+            },
+            Input::Present(_) => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if !matches!(self.input, Input::Present(Json::Null)) {
+            self.mismatch(&visitor);
+        }
+        self.finish(visitor.visit_unit())
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(0, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if let Input::Present(Json::Array(items)) = self.input {
+            return self.visit_seq(items, len, visitor);
+        }
+        self.mismatch(&visitor);
+        let silent = self.child(self.path.clone(), Input::Silent);
+        silent.visit_seq(&[], len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_struct("", &[], visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if let Input::Present(Json::Object(entries)) = self.input {
+            return self.visit_map(Some(entries), fields, visitor);
+        }
+        self.mismatch(&visitor);
+        let silent = self.child(self.path.clone(), Input::Silent);
+        silent.visit_map(None, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (variant, content) = match self.input {
+            Input::Present(Json::String(s)) => (s.as_str(), Input::Present(&NULL)),
+            Input::Present(Json::Object(entries)) if entries.len() == 1 => {
+                match entries.iter().next() {
+                    Some((key, value)) => (key.as_str(), Input::Present(value)),
+                    None => ("", Input::Silent),
+                }
+            },
+            _ => {
+                self.mismatch(&visitor);
+                ("", Input::Silent)
+            },
+        };
+        let (variant, content) = if variants.contains(&variant) {
+            (variant, content)
+        } else {
+            if !variant.is_empty() {
+                self.record(<Error as de::Error>::unknown_variant(variant, variants).message);
+            }
+            let Some(first) = variants.first() else {
+                return Err(Error {
+                    message: "enum has no variants".to_string(),
+                    recorded: false,
+                });
+            };
+            (*first, Input::Silent)
+        };
+        let content = self.field(variant, content);
+        self.finish(visitor.visit_enum(Enum { variant, content }))
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+}
+
+/// Sequence access over the elements of a list.
+struct Seq<'p, 'a, 'e> {
+    /// The list node.
+    parent: &'p Collect<'a, 'e>,
+    /// Remaining elements, with their index.
+    items: std::iter::Enumerate<std::vec::IntoIter<Input<'a>>>,
+}
+
+impl<'de> SeqAccess<'de> for Seq<'_, '_, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        self.items
+            .next()
+            .map(|(index, input)| seed.deserialize(self.parent.element(index, input)))
+            .transpose()
+    }
+}
+
+/// Map access over the entries of a struct.
+struct Map<'p, 'a, 'e> {
+    /// The struct node.
+    parent: &'p Collect<'a, 'e>,
+    /// Remaining entries.
+    entries: std::vec::IntoIter<(&'a str, Input<'a>)>,
+    /// Value of the entry whose key was returned last.
+    value: Option<Collect<'a, 'e>>,
+}
+
+impl<'de> MapAccess<'de> for Map<'_, '_, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let Some((key, input)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some(self.parent.field(key, input));
+        let key: StrDeserializer<'_, Error> = key.into_deserializer();
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::custom("value requested before key")),
+        }
+    }
+}
+
+/// Enum access over a variant and its content.
+struct Enum<'a, 'e> {
+    /// Name of the variant.
+    variant: &'a str,
+    /// Content of the variant.
+    content: Collect<'a, 'e>,
+}
+
+impl<'de, 'a, 'e> EnumAccess<'de> for Enum<'a, 'e> {
+    type Error = Error;
+    type Variant = Collect<'a, 'e>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant: StrDeserializer<'_, Error> = self.variant.into_deserializer();
+        Ok((seed.deserialize(variant)?, self.content))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Collect<'_, '_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_struct(self, "", fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::deserialize_all;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Config {
+        name: String,
+        replicas: u8,
+        ports: Vec<u16>,
+        tls: Option<Tls>,
+        mode: Mode,
+        labels: BTreeMap<String, String>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Tls {
+        cert: String,
+        key: String,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Mode {
+        Fast,
+        Safe { retries: u32 },
+    }
+
+    fn errors(json: &serde_json::Value) -> Vec<String> {
+        deserialize_all::<Config>(json)
+            .unwrap_err()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        let json = json!({
+            "name": "api",
+            "replicas": 3,
+            "ports": [80],
+            "mode": { "safe": { "retries": 2 } },
+            "labels": { "app": "api" },
+        });
+        assert_eq!(deserialize_all::<Config>(&json).unwrap(), Config {
+            name: "api".to_string(),
+            replicas: 3,
+            ports: vec![80],
+            tls: None,
+            mode: Mode::Safe { retries: 2 },
+            labels: BTreeMap::from([("app".to_string(), "api".to_string())]),
+        });
+    }
+
+    #[test]
+    fn test_all_errors() {
+        let json = json!({
+            "replicas": 300,
+            "ports": [80, "443", -1],
+            "tls": { "cert": 1 },
+            "mode": "slow",
+            "labels": { "app": true },
+        });
+        assert_eq!(errors(&json), [
+            "labels.app: invalid type: boolean `true`, expected a string",
+            "mode: unknown variant `slow`, expected `fast` or `safe`",
+            "ports[1]: invalid type: string \"443\", expected u16",
+            "ports[2]: invalid value: integer `-1`, expected u16",
+            "replicas: invalid value: integer `300`, expected u8",
+            "tls.cert: invalid type: integer `1`, expected a string",
+            "tls.key: missing field",
+            "name: missing field",
+        ]);
+    }
+
+    #[test]
+    fn test_wrong_container() {
+        let json = json!({
+            "name": "api",
+            "replicas": 1,
+            "ports": {},
+            "tls": [],
+            "mode": { "safe": 1 },
+            "labels": {},
+        });
+        assert_eq!(errors(&json), [
+            "mode.safe: invalid type: integer `1`, expected struct variant Mode::Safe",
+            "ports: invalid type: map, expected a sequence",
+            "tls: invalid type: sequence, expected struct Tls",
+        ]);
+    }
+}
@@ -0,0 +1,268 @@
+//! A [`serde::Deserializer`] over the evaluated content of a CUE [`Value`].
+//!
+//! The value is exported once by libcue (`cue_dec_json`) and the deserializer
+//! then streams over that buffer, so it can be piped straight into any
+//! [`serde::Serializer`] (e.g. with `serde_transcode`) or used to deserialize
+//! Rust types, without building an intermediate tree.
+//!
+//! CUE `bytes` values are exported as base64 encoded strings, following the
+//! CUE JSON encoding.
+//!
+//! Deserialization recurses once per nested list or struct, so the nesting
+//! depth of the exported document is checked up front against a configurable
+//! limit ([`DEFAULT_MAX_DEPTH`] by default) to protect against stack overflows
+//! on adversarial input.
+//!
+//! [`Deserializer::deserialize_all`] reports every mismatching field at once
+//! instead of stopping at the first one, for users fixing a configuration by
+//! hand.
+//!
+//! In the other direction, [`Value`] implements [`serde::Deserialize`], so
+//! CUE values can be embedded in structs read from JSON or YAML files.  Such
+//! values are compiled in the default context; use [`ValueSeed`] to
+//! pick the context instead.
+
+mod collect;
+mod seed;
+
+use serde::de::{DeserializeOwned, Visitor};
+use serde_json::de::SliceRead;
+
+pub use self::{collect::FieldError, seed::ValueSeed};
+use crate::{Value, error::Error, limits::Limits};
+
+/// Default maximum nesting depth accepted by [`Deserializer::new`].
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// A [`serde::Deserializer`] over an evaluated CUE value.
+///
+/// Deserialization is implemented for `&Deserializer`, borrowing strings
+/// directly from the exported buffer where possible.
+#[derive(Debug, Clone)]
+pub struct Deserializer {
+    /// JSON encoding of the value, as exported by libcue.
+    json: bytes::Bytes,
+}
+
+impl Deserializer {
+    /// Creates a deserializer over the evaluated content of `value`, accepting
+    /// at most [`DEFAULT_MAX_DEPTH`] levels of nesting.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the value cannot be exported (e.g. it is not
+    /// concrete), or [`Error::LimitExceeded`] if it is nested too deeply.
+    pub fn new(value: &Value) -> Result<Self, Error> {
+        Self::with_max_depth(value, DEFAULT_MAX_DEPTH)
+    }
+
+    /// Creates a deserializer over the evaluated content of `value`, accepting
+    /// at most `max_depth` levels of nested lists and structs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the value cannot be exported (e.g. it is not
+    /// concrete), or [`Error::LimitExceeded`] if it is nested deeper than
+    /// `max_depth`.
+    pub fn with_max_depth(
+        value: &Value,
+        max_depth: usize,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            json: value.to_json_bytes_limited(&Limits::default().with_max_depth(max_depth))?,
+        })
+    }
+
+    /// Deserializes a `T`, returning every field error instead of only the
+    /// first one.
+    ///
+    /// Well-formed data is deserialized as by `T::deserialize(&self)`.  On
+    /// failure the document is checked again field by field, substituting a
+    /// placeholder for each mismatching field so the remaining ones are still
+    /// reported.  Errors raised by custom `Deserialize` implementations
+    /// cannot be recovered from and end the check.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors found, with the path of the offending field.
+    pub fn deserialize_all<T: DeserializeOwned>(&self) -> Result<T, Vec<FieldError>> {
+        let err = match T::deserialize(self) {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        let first = || {
+            vec![FieldError {
+                path: String::new(),
+                message: err.to_string(),
+            }]
+        };
+        let Ok(json): Result<serde_json::Value, _> =
+            self.with(|de| serde::Deserialize::deserialize(de))
+        else {
+            return Err(first());
+        };
+        match collect::deserialize_all::<T>(&json) {
+            Ok(_) => Err(first()),
+            Err(errors) => Err(errors),
+        }
+    }
+
+    /// Runs `f` over a fresh JSON deserializer for the exported buffer and
+    /// checks that the whole input was consumed.
+    ///
+    /// `serde_json`'s own fixed recursion limit is disabled, as the depth was
+    /// already checked against the configured limit on construction.
+    fn with<'de, T>(
+        &'de self,
+        f: impl FnOnce(&mut serde_json::Deserializer<SliceRead<'de>>) -> Result<T, serde_json::Error>,
+    ) -> Result<T, serde_json::Error> {
+        let mut de = serde_json::Deserializer::from_slice(&self.json);
+        de.disable_recursion_limit();
+        let value = f(&mut de)?;
+        de.end()?;
+        Ok(value)
+    }
+}
+
+/// Forwards `serde::Deserializer` methods to the underlying JSON
+/// deserializer.
+macro_rules! forward {
+    ($($method:ident $(($($arg:ident: $ty:ty),*))?;)*) => {$(
+        fn $method<V: Visitor<'de>>(
+            self,
+            $($($arg: $ty,)*)?
+            visitor: V,
+        ) -> Result<V::Value, Self::Error> {
+            self.with(|de| serde::Deserializer::$method(de, $($($arg,)*)? visitor))
+        }
+    )*};
+}
+
+impl<'de> serde::Deserializer<'de> for &'de Deserializer {
+    type Error = serde_json::Error;
+
+    forward! {
+        deserialize_any;
+        deserialize_bool;
+        deserialize_i8;
+        deserialize_i16;
+        deserialize_i32;
+        deserialize_i64;
+        deserialize_i128;
+        deserialize_u8;
+        deserialize_u16;
+        deserialize_u32;
+        deserialize_u64;
+        deserialize_u128;
+        deserialize_f32;
+        deserialize_f64;
+        deserialize_char;
+        deserialize_str;
+        deserialize_string;
+        deserialize_bytes;
+        deserialize_byte_buf;
+        deserialize_option;
+        deserialize_unit;
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq;
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map;
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier;
+        deserialize_ignored_any;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::{DEFAULT_MAX_DEPTH, Deserializer};
+    use crate::{Ctx, Value, error::Error, limits::Limit};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Config<'a> {
+        name: &'a str,
+        replicas: u32,
+        labels: Vec<String>,
+        debug: Option<bool>,
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let ctx = Ctx::new().unwrap();
+        let v = Value::compile_string(
+            &ctx,
+            r#"{ name: "api", replicas: 1 + 2, labels: ["a", "b"], debug: null }"#,
+        )
+        .unwrap();
+        let de = Deserializer::new(&v).unwrap();
+        assert_eq!(Config::deserialize(&de).unwrap(), Config {
+            name: "api",
+            replicas: 3,
+            labels: vec!["a".to_string(), "b".to_string()],
+            debug: None,
+        });
+    }
+
+    #[test]
+    fn test_deserialize_all() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Config {
+            name: String,
+            replicas: u32,
+        }
+
+        let ctx = Ctx::new().unwrap();
+        let v = Value::compile_string(&ctx, r#"{ replicas: "3" }"#).unwrap();
+        let errors = Deserializer::new(&v)
+            .unwrap()
+            .deserialize_all::<Config>()
+            .unwrap_err();
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(errors, [
+            r#"replicas: invalid type: string "3", expected u32"#,
+            "name: missing field",
+        ]);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let ctx = Ctx::new().unwrap();
+        let v = Value::compile_string(&ctx, "[[[1]]]").unwrap();
+        assert!(Deserializer::with_max_depth(&v, 3).is_ok());
+        assert!(matches!(
+            Deserializer::with_max_depth(&v, 2),
+            Err(Error::LimitExceeded {
+                limit: Limit::Depth,
+                actual: 3,
+                max: 2
+            })
+        ));
+
+        let src = format!(
+            "{}{}",
+            "[".repeat(DEFAULT_MAX_DEPTH + 1),
+            "]".repeat(DEFAULT_MAX_DEPTH + 1)
+        );
+        let deep = Value::compile_string(&ctx, &src).unwrap();
+        assert!(Deserializer::new(&deep).is_err());
+    }
+
+    #[test]
+    fn test_transcode() {
+        let ctx = Ctx::new().unwrap();
+        let v = Value::compile_string(&ctx, "{ a: [1, 2.5, true, \"x\"] }").unwrap();
+        let de = Deserializer::new(&v).unwrap();
+        let mut out = Vec::new();
+        serde_transcode::transcode(&de, &mut serde_json::Serializer::pretty(&mut out)).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\n  \"a\": [\n    1,\n    2.5,\n    true,\n    \"x\"\n  ]\n}"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Deserializing CUE values out of other serde formats.
+
+use serde::{
+    Deserialize, Deserializer,
+    de::{DeserializeSeed, Error as _},
+};
+
+use crate::{Ctx, Value};
+
+/// A [`DeserializeSeed`] that reads arbitrary data and compiles it into a
+/// [`Value`] in the given context.
+///
+/// The data is transcoded to JSON and compiled as CUE, so any self-describing
+/// format works, but bytes, if the format has them, end up as lists of
+/// numbers rather than CUE `bytes`.
+#[derive(Clone, Copy)]
+pub struct ValueSeed<'a> {
+    /// Context the value is compiled in.
+    ctx: &'a Ctx,
+}
+
+impl<'a> ValueSeed<'a> {
+    /// Creates a seed compiling values in `ctx`.
+    #[must_use]
+    pub fn new(ctx: &'a Ctx) -> Self {
+        Self { ctx }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        let mut json = Vec::new();
+        serde_transcode::transcode(deserializer, &mut serde_json::Serializer::new(&mut json))
+            .map_err(D::Error::custom)?;
+        Value::compile_bytes(self.ctx, &json).map_err(D::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    /// Deserializes a value into the default context, see
+    /// [`Ctx::global`] and [`ValueSeed`].
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ValueSeed::new(Ctx::global().map_err(D::Error::custom)?).deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeSeed;
+    use serde_json::json;
+
+    use super::ValueSeed;
+    use crate::{Ctx, Value};
+
+    #[derive(serde::Deserialize)]
+    struct Config {
+        name: String,
+        schema: Value,
+    }
+
+    #[test]
+    fn test_deserialize_global() {
+        let config: Config =
+            serde_json::from_value(json!({ "name": "api", "schema": { "port": 8080 } })).unwrap();
+        assert_eq!(config.name, "api");
+        let port = config.schema.lookup("port").unwrap();
+        assert_eq!(port.to_int64().unwrap(), 8080);
+    }
+
+    #[test]
+    fn test_deserialize_seed() {
+        let ctx = Ctx::new().unwrap();
+        let mut de = serde_json::Deserializer::from_str(r#"["a", "\\(b)", 1.5, null]"#);
+        let value = ValueSeed::new(&ctx).deserialize(&mut de).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&value.to_json_vec().unwrap()).unwrap(),
+            json!(["a", "\\(b)", 1.5, null])
+        );
+    }
+}
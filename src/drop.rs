@@ -11,3 +11,50 @@ unsafe extern "C" {
     /// Frees memory allocated by the C library (libc).
     pub(crate) fn libc_free(ptr: *mut core::ffi::c_void);
 }
+
+/// A buffer allocated by libcue with `malloc`, freed with [`libc_free`] when
+/// dropped.
+pub(crate) struct CBuffer {
+    /// Start of the buffer; may be null if `len` is 0.
+    ptr: *mut core::ffi::c_void,
+    /// Length of the buffer in bytes.
+    len: usize,
+}
+
+// SAFETY: the buffer is exclusively owned and never mutated, and `free` may
+// be called from any thread.
+unsafe impl Send for CBuffer {}
+// SAFETY: see above; shared access only reads the buffer.
+unsafe impl Sync for CBuffer {}
+
+impl CBuffer {
+    /// Takes ownership of `len` bytes at `ptr`, as returned by libcue.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or point to `len` initialized bytes allocated with
+    /// `malloc` that are not freed elsewhere.
+    pub(crate) unsafe fn new(
+        ptr: *mut core::ffi::c_void,
+        len: usize,
+    ) -> Self {
+        Self { ptr, len }
+    }
+}
+
+impl AsRef<[u8]> for CBuffer {
+    fn as_ref(&self) -> &[u8] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+        unsafe { core::slice::from_raw_parts(self.ptr.cast::<u8>(), self.len) }
+    }
+}
+
+impl Drop for CBuffer {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            unsafe { libc_free(self.ptr) }
+        }
+    }
+}
@@ -4,6 +4,8 @@ use core::ffi::c_char;
 
 use thiserror::Error;
 
+use crate::limits::Limit;
+
 /// Opaque handle type matching `typedef uintptr_t cue_error` from libcue.
 type CueErrorHandle = usize;
 
@@ -39,7 +41,8 @@ pub enum Error {
     #[error("cue_newctx returned 0; the libcue runtime could not allocate a context")]
     ContextCreationFailed,
 
-    /// The string passed to `cue_compile_string` contains an interior nul byte.
+    /// A string passed to libcue as a C string, such as a path, contains an
+    /// interior nul byte.
     #[error("string contains an interior nul byte: {0}")]
     StringContainsNul(std::ffi::NulError),
 
@@ -50,4 +53,160 @@ pub enum Error {
     /// A string decoded from libcue was not valid UTF-8.
     #[error("decoded string is not valid UTF-8: {0}")]
     InvalidUtf8(std::str::Utf8Error),
+
+    /// A CUE integer does not fit in the requested Rust integer type.
+    #[error("CUE integer {value} is out of range for {target}")]
+    IntegerOutOfRange {
+        /// Decimal text of the CUE number.
+        value: String,
+        /// Name of the Rust target type (e.g. `i64`).
+        target: &'static str,
+    },
+
+    /// An operation is not defined for the kind of the value, such as the
+    /// length of an integer.
+    #[error("expected {expected}, found {found}")]
+    KindMismatch {
+        /// The kinds the operation accepts.
+        expected: &'static str,
+        /// The kind of the value.
+        found: crate::value::Kind,
+    },
+
+    /// A looked up field does not exist.
+    #[error("field not found: {0}")]
+    NotFound(String),
+
+    /// No schema is routed for the discriminator value of the data, see
+    /// [`SchemaRouter`](crate::router::SchemaRouter).
+    #[error("no schema for {path} = {value}")]
+    UnknownType {
+        /// Path of the discriminator field.
+        path: String,
+        /// JSON rendering of the discriminator value, `_` if it is not
+        /// concrete or `_|_` if the data has no discriminator.
+        value: String,
+    },
+
+    /// A source compiled in a sandboxed context imports a package other than
+    /// the pure packages of the CUE standard library, see
+    /// [`Limits::with_sandbox`](crate::limits::Limits::with_sandbox).
+    #[cfg(feature = "sandbox")]
+    #[error("import of {0:?} is not allowed in a sandbox")]
+    ForbiddenImport(String),
+
+    /// A tag injected with [`LoadOptions`](crate::load::LoadOptions) is not
+    /// used by any field or has a value of the wrong type.
+    #[error("invalid tag {name}: {reason}")]
+    InvalidTag {
+        /// Name of the tag.
+        name: String,
+        /// Why the tag was rejected.
+        reason: String,
+    },
+
+    /// A schema bundle is malformed or one of its schemas does not compile,
+    /// see [`init_with_bundle`](crate::init_with_bundle).
+    #[error("invalid schema bundle: {0}")]
+    InvalidBundle(String),
+
+    /// [`init_with_bundle`](crate::init_with_bundle) was called after a
+    /// bundle was already loaded.
+    #[error("a schema bundle was already loaded")]
+    BundleAlreadyLoaded,
+
+    /// A builtin passed to [`Ctx::call`](crate::Ctx::call) is not named by
+    /// a package path and an identifier, such as `strings.ToUpper`.
+    #[error("invalid builtin name: {0}")]
+    InvalidBuiltin(String),
+
+    /// An operation did not complete within its timeout.
+    #[cfg(feature = "isolated")]
+    #[error("operation timed out")]
+    Timeout,
+
+    /// An isolated worker process reported a CUE error; the message is the
+    /// worker's rendering of it.
+    #[cfg(feature = "isolated")]
+    #[error("{0}")]
+    IsolatedCue(String),
+
+    /// An isolated worker process could not be started, crashed or broke the
+    /// IPC protocol.
+    #[cfg(feature = "isolated")]
+    #[error("isolated worker failed: {0}")]
+    Worker(String),
+
+    /// Rust data could not be serialized into a value, see
+    /// [`Value::encode`](crate::Value::encode).
+    #[cfg(feature = "serde")]
+    #[error("serialization failed: {0}")]
+    Serialize(String),
+
+    /// A JSON Schema uses a construct that cannot be imported, see
+    /// [`interop::from_json_schema`](crate::interop::from_json_schema).
+    #[cfg(feature = "schemars")]
+    #[error("unsupported JSON Schema: {0}")]
+    UnsupportedSchema(String),
+
+    /// [`Generator`](crate::fake::Generator) found no value satisfying the
+    /// constraint of a field.
+    #[cfg(feature = "fake")]
+    #[error("cannot generate a value for {path}: {constraint}")]
+    Unsatisfiable {
+        /// CUE path of the field, `.` for the value itself.
+        path: String,
+        /// The constraint as reported by CUE.
+        constraint: String,
+    },
+
+    /// Source is not syntactically valid: CUE read by `ast::parse`, or a
+    /// JSON5 document read by
+    /// [`Value::compile_json5`](crate::Value::compile_json5).
+    #[error("syntax error at {line}:{column}: {message}")]
+    Syntax {
+        /// Line of the error, starting at 1.
+        line: usize,
+        /// Column of the error in characters, starting at 1.
+        column: usize,
+        /// What is wrong.
+        message: String,
+    },
+
+    /// A value could not be encoded as MessagePack.
+    #[cfg(feature = "rmp")]
+    #[error("MessagePack encoding failed: {0}")]
+    Msgpack(rmp_serde::encode::Error),
+
+    /// A CUE file or package directory could not be read.
+    #[error("cannot read {}: {source}", path.display())]
+    Io {
+        /// The file or directory that could not be read.
+        path: std::path::PathBuf,
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+
+    /// A resource limit configured via [`Limits`](crate::limits::Limits) was
+    /// exceeded.
+    #[error("{limit} limit exceeded: {actual} > {max}")]
+    LimitExceeded {
+        /// The limit that was exceeded.
+        limit: Limit,
+        /// The measured size or depth.
+        actual: usize,
+        /// The configured maximum.
+        max: usize,
+    },
+}
+
+#[cfg(feature = "ast")]
+impl From<crate::ast::SyntaxError> for Error {
+    fn from(err: crate::ast::SyntaxError) -> Self {
+        Self::Syntax {
+            line: err.line,
+            column: err.column,
+            message: err.message,
+        }
+    }
 }
@@ -0,0 +1,552 @@
+//! Building CUE constraints from Rust instead of formatting CUE source.
+//!
+//! An [`Expr`] is a CUE expression assembled with method calls, rendered as
+//! source with [`Display`] and compiled with
+//! [`Expr::compile`].  Literals are quoted and sub-expressions parenthesized
+//! as needed, so values taken from configuration or user input cannot change
+//! the shape of the constraint:
+//!
+//! ```
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! use cue_rs::{Ctx, expr::Expr};
+//!
+//! let port = Expr::int().ge(0).le(65535).default(8080);
+//! assert_eq!(port.to_string(), "int & >=0 & <=65535 | *8080");
+//!
+//! let service = Expr::structure()
+//!     .field("name", Expr::string().matches("^[a-z][a-z0-9-]*$"))
+//!     .field("port", port)
+//!     .optional("tags", Expr::list_of(Expr::string()));
+//! assert_eq!(
+//!     service.to_string(),
+//!     r#"{name: string & =~"^[a-z][a-z0-9-]*$", port: int & >=0 & <=65535 | *8080, tags?: [...string]}"#
+//! );
+//!
+//! let ctx = Ctx::new()?;
+//! let schema = service.compile(&ctx)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Expr::raw`] splices in CUE source as written, for references to
+//! definitions and anything the builder does not cover.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{Ctx, Value, error::Error, path::quote};
+
+/// A CUE expression, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr(Node);
+
+/// The syntax tree of an [`Expr`].
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// Source that needs no parentheses: a keyword, literal or reference.
+    Atom(String),
+    /// CUE source as written, parenthesized when it is an operand unless it
+    /// is a plain reference.
+    Raw(String),
+    /// A unary operator such as `>=` or `*` applied to an operand.
+    Unary(&'static str, Box<Expr>),
+    /// A binary `&` or `|`.
+    Binary(Op, Box<Expr>, Box<Expr>),
+    /// A list `[...elem]` of any number of `elem`s.
+    List(Box<Expr>),
+    /// A struct literal, as its fields.
+    Struct(Vec<Field>),
+    /// A call of a builtin such as `close`.
+    Call(&'static str, Box<Expr>),
+}
+
+/// A binary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    /// Unification, `&`.
+    And,
+    /// Disjunction, `|`.
+    Or,
+}
+
+/// A field of a struct literal.
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    /// The label, quoted if needed.
+    label: String,
+    /// The marker after the label: `""`, `"?"` or `"!"`.
+    marker: &'static str,
+    /// The value.
+    value: Expr,
+}
+
+impl Expr {
+    /// Top, `_`: any value.
+    #[must_use]
+    pub fn top() -> Self {
+        Self::atom("_")
+    }
+
+    /// Bottom, `_|_`: no value.
+    #[must_use]
+    pub fn bottom() -> Self {
+        Self::atom("_|_")
+    }
+
+    /// `null`.
+    #[must_use]
+    pub fn null() -> Self {
+        Self::atom("null")
+    }
+
+    /// Any boolean, `bool`.
+    #[must_use]
+    pub fn bool() -> Self {
+        Self::atom("bool")
+    }
+
+    /// Any integer, `int`.
+    #[must_use]
+    pub fn int() -> Self {
+        Self::atom("int")
+    }
+
+    /// Any float, `float`.
+    #[must_use]
+    pub fn float() -> Self {
+        Self::atom("float")
+    }
+
+    /// Any number, `number`.
+    #[must_use]
+    pub fn number() -> Self {
+        Self::atom("number")
+    }
+
+    /// Any string, `string`.
+    #[must_use]
+    pub fn string() -> Self {
+        Self::atom("string")
+    }
+
+    /// Any byte sequence, `bytes`.
+    #[must_use]
+    pub fn bytes() -> Self {
+        Self::atom("bytes")
+    }
+
+    /// CUE source as written, such as `#Service` or `strings.MinRunes(1)`.
+    ///
+    /// The source is parenthesized when it is an operand, unless it is a
+    /// plain reference, but not checked; errors surface when the expression
+    /// is compiled.
+    #[must_use]
+    pub fn raw(src: impl Into<String>) -> Self {
+        Self(Node::Raw(src.into()))
+    }
+
+    /// A list of any number of `elem`s, `[...elem]`.
+    #[must_use]
+    pub fn list_of(elem: impl Into<Self>) -> Self {
+        Self(Node::List(Box::new(elem.into())))
+    }
+
+    /// An empty struct literal, `{}`, to add fields to with
+    /// [`Expr::field`] and its siblings.
+    #[must_use]
+    pub fn structure() -> Self {
+        Self(Node::Struct(Vec::new()))
+    }
+
+    /// The disjunction of `values`, such as `"a" | "b" | "c"`; no values
+    /// give bottom.
+    #[must_use]
+    pub fn one_of<T: Into<Self>>(values: impl IntoIterator<Item = T>) -> Self {
+        values
+            .into_iter()
+            .map(Into::into)
+            .reduce(Self::or)
+            .unwrap_or_else(Self::bottom)
+    }
+
+    /// Unifies with `other`: `self & other`.
+    #[must_use]
+    pub fn and(
+        self,
+        other: impl Into<Self>,
+    ) -> Self {
+        Self(Node::Binary(
+            Op::And,
+            Box::new(self),
+            Box::new(other.into()),
+        ))
+    }
+
+    /// Adds `other` as an alternative: `self | other`.
+    #[must_use]
+    pub fn or(
+        self,
+        other: impl Into<Self>,
+    ) -> Self {
+        Self(Node::Binary(Op::Or, Box::new(self), Box::new(other.into())))
+    }
+
+    /// Adds `value` as the default alternative: `self | *value`.
+    #[must_use]
+    pub fn default(
+        self,
+        value: impl Into<Self>,
+    ) -> Self {
+        self.or(Self::unary("*", value))
+    }
+
+    /// Requires values to be greater than or equal to `bound`: `self & >=bound`.
+    #[must_use]
+    pub fn ge(
+        self,
+        bound: impl Into<Self>,
+    ) -> Self {
+        self.and(Self::unary(">=", bound))
+    }
+
+    /// Requires values to be greater than `bound`: `self & >bound`.
+    #[must_use]
+    pub fn gt(
+        self,
+        bound: impl Into<Self>,
+    ) -> Self {
+        self.and(Self::unary(">", bound))
+    }
+
+    /// Requires values to be less than or equal to `bound`: `self & <=bound`.
+    #[must_use]
+    pub fn le(
+        self,
+        bound: impl Into<Self>,
+    ) -> Self {
+        self.and(Self::unary("<=", bound))
+    }
+
+    /// Requires values to be less than `bound`: `self & <bound`.
+    #[must_use]
+    pub fn lt(
+        self,
+        bound: impl Into<Self>,
+    ) -> Self {
+        self.and(Self::unary("<", bound))
+    }
+
+    /// Excludes `value`: `self & !=value`.
+    #[must_use]
+    pub fn ne(
+        self,
+        value: impl Into<Self>,
+    ) -> Self {
+        self.and(Self::unary("!=", value))
+    }
+
+    /// Requires strings to match the RE2 regular expression `pattern`:
+    /// `self & =~"pattern"`.
+    #[must_use]
+    pub fn matches(
+        self,
+        pattern: &str,
+    ) -> Self {
+        self.and(Self::unary("=~", pattern))
+    }
+
+    /// Requires strings not to match the RE2 regular expression `pattern`:
+    /// `self & !~"pattern"`.
+    #[must_use]
+    pub fn not_matches(
+        self,
+        pattern: &str,
+    ) -> Self {
+        self.and(Self::unary("!~", pattern))
+    }
+
+    /// Closes a struct to the fields it defines: `close(self)`.
+    #[must_use]
+    pub fn close(self) -> Self {
+        Self(Node::Call("close", Box::new(self)))
+    }
+
+    /// Adds the regular field `name: value` to a struct literal, or unifies
+    /// any other expression with a struct of that field.
+    ///
+    /// The label is quoted unless it is a plain identifier.
+    #[must_use]
+    pub fn field(
+        self,
+        name: &str,
+        value: impl Into<Self>,
+    ) -> Self {
+        self.with_field(label(name), "", value.into())
+    }
+
+    /// Adds the optional field `name?: value`, see [`Expr::field`].
+    #[must_use]
+    pub fn optional(
+        self,
+        name: &str,
+        value: impl Into<Self>,
+    ) -> Self {
+        self.with_field(label(name), "?", value.into())
+    }
+
+    /// Adds the required field `name!: value`, see [`Expr::field`].
+    #[must_use]
+    pub fn required(
+        self,
+        name: &str,
+        value: impl Into<Self>,
+    ) -> Self {
+        self.with_field(label(name), "!", value.into())
+    }
+
+    /// Adds the definition `#name: value`, see [`Expr::field`].
+    ///
+    /// Definition names cannot be quoted, so a `name` that is not an
+    /// identifier, such as `a-b`, makes the whole expression bottom (`_|_`)
+    /// instead of being spliced into the source.
+    #[must_use]
+    pub fn definition(
+        self,
+        name: &str,
+        value: impl Into<Self>,
+    ) -> Self {
+        if !is_identifier(name) {
+            return Self::bottom();
+        }
+        self.with_field(format!("#{name}"), "", value.into())
+    }
+
+    /// Compiles the expression into a [`Value`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Value::compile_string`], such as
+    /// [`Error::Cue`] for a conflicting constraint or a bad [`Expr::raw`]
+    /// source.
+    pub fn compile(
+        &self,
+        ctx: &Ctx,
+    ) -> Result<Value, Error> {
+        Value::compile_string(ctx, &self.to_string())
+    }
+
+    /// Returns the expression for `src`, which needs no parentheses.
+    fn atom(src: &str) -> Self {
+        Self(Node::Atom(src.to_string()))
+    }
+
+    /// Returns the unary expression `op` `operand`.
+    pub(crate) fn unary(
+        op: &'static str,
+        operand: impl Into<Self>,
+    ) -> Self {
+        Self(Node::Unary(op, Box::new(operand.into())))
+    }
+
+    /// Adds a field to a struct literal, or unifies with a struct of it.
+    fn with_field(
+        self,
+        label: String,
+        marker: &'static str,
+        value: Self,
+    ) -> Self {
+        let field = Field {
+            label,
+            marker,
+            value,
+        };
+        match self.0 {
+            Node::Struct(mut fields) => {
+                fields.push(field);
+                Self(Node::Struct(fields))
+            },
+            node => Self(node).and(Self(Node::Struct(vec![field]))),
+        }
+    }
+
+    /// Binding strength of the expression: `|` binds loosest, then `&`,
+    /// then everything else.
+    fn precedence(&self) -> u8 {
+        match &self.0 {
+            Node::Binary(Op::Or, ..) => 1,
+            Node::Binary(Op::And, ..) => 2,
+            Node::Raw(src)
+                if !src
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | '#' | '.')) =>
+            {
+                0
+            },
+            _ => 3,
+        }
+    }
+
+    /// Writes the expression, parenthesized if it binds looser than `min`.
+    fn fmt_operand(
+        &self,
+        f: &mut Formatter<'_>,
+        min: u8,
+    ) -> fmt::Result {
+        if self.precedence() < min {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> fmt::Result {
+        match &self.0 {
+            Node::Atom(src) | Node::Raw(src) => f.write_str(src),
+            Node::Unary(op, operand) => {
+                f.write_str(op)?;
+                operand.fmt_operand(f, 3)
+            },
+            Node::Binary(op, lhs, rhs) => {
+                let (op, min) = match op {
+                    Op::And => ("&", 2),
+                    Op::Or => ("|", 1),
+                };
+                lhs.fmt_operand(f, min)?;
+                write!(f, " {op} ")?;
+                rhs.fmt_operand(f, min)
+            },
+            Node::List(elem) => {
+                f.write_str("[...")?;
+                elem.fmt_operand(f, 3)?;
+                f.write_str("]")
+            },
+            Node::Struct(fields) => {
+                f.write_str("{")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}{}: {}", field.label, field.marker, field.value)?;
+                }
+                f.write_str("}")
+            },
+            Node::Call(func, arg) => write!(f, "{func}({arg})"),
+        }
+    }
+}
+
+/// Returns `name` as a field label: as is if it is a plain identifier,
+/// otherwise quoted.
+fn label(name: &str) -> String {
+    let ident = is_identifier(name) && !matches!(name, "true" | "false" | "null");
+    if ident { name.to_string() } else { quote(name) }
+}
+
+/// Returns `true` if `name` can be written as a CUE identifier.
+fn is_identifier(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '$')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+impl From<&str> for Expr {
+    /// The string literal `value`.
+    fn from(value: &str) -> Self {
+        Self(Node::Atom(quote(value)))
+    }
+}
+
+impl From<String> for Expr {
+    /// The string literal `value`.
+    fn from(value: String) -> Self {
+        Self::from(value.as_str())
+    }
+}
+
+impl From<bool> for Expr {
+    /// `true` or `false`.
+    fn from(value: bool) -> Self {
+        Self::atom(if value { "true" } else { "false" })
+    }
+}
+
+impl From<f64> for Expr {
+    /// The float literal `value`; NaN and infinities, which CUE numbers
+    /// cannot represent, give bottom.
+    fn from(value: f64) -> Self {
+        if value.is_finite() {
+            Self(Node::Atom(format!("{value:?}")))
+        } else {
+            Self::bottom()
+        }
+    }
+}
+
+/// Implements `From` for integer types, as integer literals.
+macro_rules! from_int {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Expr {
+                /// The integer literal `value`.
+                fn from(value: $ty) -> Self {
+                    Self(Node::Atom(value.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+from_int!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::Expr;
+    use crate::{Ctx, Value};
+
+    #[test_case(&Expr::int().ge(0).le(65535) => "int & >=0 & <=65535" ; "bounds")]
+    #[test_case(&Expr::int().gt(-1).lt(10).ne(5) => "int & >-1 & <10 & !=5" ; "negative")]
+    #[test_case(&Expr::float().ge(0.5) => "float & >=0.5" ; "float")]
+    #[test_case(&Expr::from(f64::NAN) => "_|_" ; "nan")]
+    #[test_case(&Expr::string().matches(r"^\d+$").not_matches("0") => r#"string & =~"^\\d+$" & !~"0""# ; "regex")]
+    #[test_case(&Expr::one_of(["a", "b\"c"]) => r#""a" | "b\"c""# ; "one_of")]
+    #[test_case(&Expr::one_of(Vec::<Expr>::new()) => "_|_" ; "one_of_empty")]
+    #[test_case(&Expr::int().or(Expr::string()).and(Expr::int()) => "(int | string) & int" ; "precedence")]
+    #[test_case(&Expr::int().and(Expr::int().or(Expr::null())) => "int & (int | null)" ; "precedence_rhs")]
+    #[test_case(&Expr::int().ge(Expr::raw("#Min + 1")) => "int & >=(#Min + 1)" ; "raw_operand")]
+    #[test_case(&Expr::list_of(Expr::int().ge(0)) => "[...(int & >=0)]" ; "list")]
+    #[test_case(&Expr::string().default("info") => r#"string | *"info""# ; "default")]
+    #[test_case(&Expr::bool().default(true) => "bool | *true" ; "default_bool")]
+    #[test_case(
+        &Expr::structure().field("a-b", 1).optional("null", Expr::null()).required("x", 2u8).definition("D", Expr::top())
+        => r#"{"a-b": 1, "null"?: null, x!: 2, #D: _}"# ; "struct_literal")]
+    #[test_case(&Expr::structure().definition("A: _, b", Expr::int()) => "_|_" ; "definition_not_identifier")]
+    #[test_case(&Expr::raw("#Base").field("name", "x").close() => r#"close(#Base & {name: "x"})"# ; "field_on_expr")]
+    fn test_display(expr: &Expr) -> String {
+        expr.to_string()
+    }
+
+    #[test]
+    fn test_compile() {
+        let ctx = Ctx::new().unwrap();
+        let port = Expr::int()
+            .ge(0)
+            .le(65535)
+            .default(8080)
+            .compile(&ctx)
+            .unwrap();
+        assert_eq!(port.default_value().unwrap().to_int64().unwrap(), 8080);
+        let out_of_range = Value::compile_string(&ctx, "70000").unwrap();
+        assert!(Value::unify(&port, &out_of_range).is_valid().is_err());
+    }
+}
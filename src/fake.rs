@@ -0,0 +1,779 @@
+//! Random sample data satisfying a CUE schema.
+//!
+//! [`Generator`] turns a schema into a concrete value that satisfies it,
+//! respecting bounds, regular expressions, enumerations and defaults, for
+//! seeding test environments or filling in API examples.  The same seed
+//! always yields the same data:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! use cue_rs::{Ctx, Value, fake::Generator};
+//!
+//! let ctx = Ctx::new()?;
+//! let schema = Value::compile_string(
+//!     &ctx,
+//!     r#"{
+//!         name:  string & =~"^[a-z]{3,8}$"
+//!         port:  int & >=1024 & <=65535
+//!         level: "debug" | "info" | "warn"
+//!         retries: int | *3
+//!     }"#,
+//! )?;
+//! let sample = Generator::new(42).generate(&ctx, &schema)?;
+//! println!("{}", String::from_utf8_lossy(&sample.to_json_vec()?));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! libcue cannot enumerate the fields of a struct, so the generator walks
+//! the schema the way an export does: exporting stops at the first value
+//! that is not concrete and names its constraint, e.g.
+//! `port: incomplete value int & >=1024 & <=65535`.  The generator picks a
+//! value satisfying that constraint, unifies it into the schema and exports
+//! again until the export succeeds.  Defaults are kept, required fields are
+//! filled in and optional fields are left out.
+//!
+//! Every value is checked by unification before it is kept, so a constraint
+//! the generator does not understand, such as `!~` or a validator from the
+//! standard library, costs retries but never yields invalid data.
+//!
+//! Each field filled in costs an export of the whole value, so generating a
+//! value with `n` fields to fill in takes time quadratic in `n`.  The
+//! generator relies on the wording of the export errors of the vendored
+//! libcue, `incomplete value <constraint>` and `field is required but not
+//! present`; its tests pin that wording, so a libcue update changing it
+//! fails them rather than the generator silently giving up.
+
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+use crate::{
+    Ctx, Value,
+    error::Error,
+    report::{Report, json_string, lookup_selector, path_labels},
+    rng::Rng,
+    value::Kind,
+};
+
+/// Number of values tried for a field before giving up, unless set with
+/// [`Generator::with_attempts`].
+const DEFAULT_ATTEMPTS: usize = 32;
+
+/// Maximum number of fields filled in for a single value.
+const MAX_STEPS: usize = 10_000;
+
+/// Maximum number of repetitions beyond the minimum of a regular expression
+/// repetition such as `a*` or `a{2,}`.
+const MAX_REPEAT: u32 = 8;
+
+/// Generates random data satisfying CUE schemas.
+#[derive(Debug)]
+pub struct Generator {
+    /// Source of randomness.
+    rng: Rng,
+    /// Number of values tried for a field before giving up.
+    attempts: usize,
+}
+
+impl Generator {
+    /// Creates a generator seeded with `seed`.
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            attempts: DEFAULT_ATTEMPTS,
+        }
+    }
+
+    /// Sets the number of values tried for a field before
+    /// [`Generator::generate`] gives up (32 by default).
+    #[must_use]
+    pub fn with_attempts(
+        mut self,
+        attempts: usize,
+    ) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Generates a concrete value satisfying `schema`.
+    ///
+    /// `ctx` must be the context the schema was compiled in.  Calling this
+    /// again yields different data; create a new generator with the same
+    /// seed to replay it.  The value is exported again after each field is
+    /// filled in, see the [module documentation](self) for the cost.
+    ///
+    /// # Errors
+    ///
+    /// Returns the schema's own error if it is not valid,
+    /// [`Error::Unsatisfiable`] if no value satisfying the constraint of a
+    /// field was found, or the export error if a value cannot be made
+    /// concrete at all, such as an unresolved reference.
+    pub fn generate(
+        &mut self,
+        ctx: &Ctx,
+        schema: &Value,
+    ) -> Result<Value, Error> {
+        schema.is_valid()?;
+        let mut value = Value::unify(schema, &Value::compile_string(ctx, "_")?);
+        let mut last = None;
+        for _ in 0..MAX_STEPS {
+            let Err(err) = value.to_json_vec() else {
+                return Ok(value);
+            };
+            let report = Report::from(&err);
+            let Some(d) = report.diagnostics().first() else {
+                return Err(err);
+            };
+            let required = d.message.contains("is required");
+            if d.rule_id() != "cue/incomplete-value" && !required {
+                return Err(err);
+            }
+            let path = d.path.clone().unwrap_or_default();
+            let unsatisfiable = || {
+                Error::Unsatisfiable {
+                    path: if path.is_empty() {
+                        ".".to_string()
+                    } else {
+                        path.clone()
+                    },
+                    constraint: d.message.clone(),
+                }
+            };
+            // Filling in a field always moves the export on, to the next
+            // field or to the constraint of a required field.
+            if last.as_ref() == Some(&(path.clone(), d.message.clone())) {
+                return Err(unsatisfiable());
+            }
+            let labels = path_labels(&path);
+            let found;
+            let target = if labels.is_empty() {
+                &value
+            } else {
+                found = value.lookup(&lookup_selector(&path))?;
+                &found
+            };
+            let filled = (0..self.attempts).find_map(|_| {
+                let literal = if required {
+                    "_".to_string()
+                } else {
+                    self.literal(target, &d.message)?
+                };
+                let fill = Value::compile_string(ctx, &nest(&labels, &literal)).ok()?;
+                let candidate = Value::unify(&value, &fill);
+                candidate.is_valid().is_ok().then_some(candidate)
+            });
+            value = filled.ok_or_else(unsatisfiable)?;
+            last = Some((path, d.message.clone()));
+        }
+        Err(Error::Unsatisfiable {
+            path: ".".to_string(),
+            constraint: format!("more than {MAX_STEPS} fields to fill in"),
+        })
+    }
+
+    /// Returns the CUE source of a random value for `target`, a value that
+    /// failed to export with `message`, or `None` if none can be made.
+    fn literal(
+        &mut self,
+        target: &Value,
+        message: &str,
+    ) -> Option<String> {
+        if let Some(json) = target
+            .default_value()
+            .and_then(|default| default.to_json_vec().ok())
+        {
+            return String::from_utf8(json).ok();
+        }
+        let constraint = message
+            .strip_prefix("incomplete value ")
+            .map(|expr| {
+                let branches = split_top(expr, '|');
+                let branch = branches
+                    .get(self.rng.index(branches.len()))
+                    .copied()
+                    .unwrap_or_default();
+                Constraint::parse(branch)
+            })
+            .unwrap_or_default();
+        if let Some(literal) = constraint.literal {
+            return Some(literal);
+        }
+        let kind = constraint.kind.unwrap_or_else(|| {
+            if constraint.pattern.is_some() || constraint.min_len.is_some() {
+                Kind::String
+            } else if constraint.min.is_some() || constraint.max.is_some() {
+                Kind::Number
+            } else {
+                target.incomplete_kind()
+            }
+        });
+        self.value(&constraint, kind)
+    }
+
+    /// Returns the CUE source of a random value of `kind` satisfying
+    /// `constraint` as far as it is understood.
+    fn value(
+        &mut self,
+        constraint: &Constraint,
+        kind: Kind,
+    ) -> Option<String> {
+        Some(match kind {
+            Kind::Bottom => return None,
+            Kind::Null => "null".to_string(),
+            Kind::Bool => self.rng.one_in(2).to_string(),
+            Kind::Float => self.float(constraint),
+            Kind::Number if self.rng.one_in(2) => self.float(constraint),
+            Kind::Int | Kind::Number => self.int(constraint).to_string(),
+            Kind::String | Kind::Top => json_string(&self.string(constraint)),
+            Kind::Bytes => format!("'{}'", self.rng.word()),
+            Kind::Struct => "{}".to_string(),
+            Kind::List => format!("[{}...]", "_, ".repeat(constraint.min_len.unwrap_or(0))),
+        })
+    }
+
+    /// Returns a random integer within the bounds of `constraint`.
+    fn int(
+        &mut self,
+        constraint: &Constraint,
+    ) -> i64 {
+        let lo = constraint.min.and_then(|b| {
+            if b.inclusive {
+                whole(b.value.ceil())
+            } else {
+                whole(b.value.floor()).map(|n| n.saturating_add(1))
+            }
+        });
+        let hi = constraint.max.and_then(|b| {
+            if b.inclusive {
+                whole(b.value.floor())
+            } else {
+                whole(b.value.ceil()).map(|n| n.saturating_sub(1))
+            }
+        });
+        let (lo, hi) = match (lo, hi) {
+            (Some(lo), Some(hi)) => (lo, hi.max(lo)),
+            (Some(lo), None) => (lo, lo.saturating_add(100)),
+            (None, Some(hi)) => (hi.saturating_sub(100).max(0).min(hi), hi),
+            (None, None) => (0, 100),
+        };
+        self.rng.range(lo, hi)
+    }
+
+    /// Returns the CUE source of a random float within the bounds of
+    /// `constraint`, rounded to two decimals.
+    fn float(
+        &mut self,
+        constraint: &Constraint,
+    ) -> String {
+        let lo = constraint.min.map(|b| b.value);
+        let hi = constraint.max.map(|b| b.value);
+        let (lo, hi) = match (lo, hi) {
+            (Some(lo), Some(hi)) => (lo, hi.max(lo)),
+            (Some(lo), None) => (lo, lo + 100.0),
+            (None, Some(hi)) => (hi - 100.0, hi),
+            (None, None) => (0.0, 100.0),
+        };
+        let fraction = u32::try_from(self.rng.below(1_000_000)).unwrap_or(0);
+        let x = lo + (hi - lo) * (f64::from(fraction) / 1_000_000.0);
+        // Debug formatting always marks the number as a float (`3.0`).
+        format!("{:?}", (x * 100.0).round() / 100.0)
+    }
+
+    /// Returns a random string matching the pattern and length limits of
+    /// `constraint`.
+    fn string(
+        &mut self,
+        constraint: &Constraint,
+    ) -> String {
+        if let Some(s) = constraint
+            .pattern
+            .as_deref()
+            .and_then(|pattern| sample_regex(&mut self.rng, pattern))
+        {
+            return s;
+        }
+        let min = constraint.min_len.unwrap_or(1);
+        let max = constraint
+            .max_len
+            .unwrap_or_else(|| min.saturating_add(8))
+            .max(min);
+        let len = self.rng.range(
+            i64::try_from(min).unwrap_or(i64::MAX),
+            i64::try_from(max).unwrap_or(i64::MAX),
+        );
+        self.rng.letters(usize::try_from(len).unwrap_or(min))
+    }
+}
+
+/// Returns a [`proptest`] strategy generating JSON documents that satisfy
+/// `schema`, for property-based tests of code consuming such documents.
+///
+/// `ctx` must be the context the schema was compiled in:
+///
+/// ```no_run
+/// use cue_rs::{Ctx, Value, fake::cue_strategy};
+/// use proptest::prelude::*;
+///
+/// let ctx = Ctx::new().unwrap();
+/// let schema = Value::compile_string(&ctx, "{ port: int & >=1024 & <=65535 }").unwrap();
+/// proptest!(|(doc in cue_strategy(&ctx, &schema))| {
+///     prop_assert!(doc["port"].as_u64().unwrap() >= 1024);
+/// });
+/// ```
+///
+/// Each document comes from a [`Generator`] seeded by proptest, so failing
+/// cases replay from proptest's persisted seeds.  Shrinking moves to other
+/// seeds, which yields different rather than simpler documents.  Seeds for
+/// which no document is found are rejected.
+#[cfg(feature = "proptest")]
+pub fn cue_strategy(
+    ctx: &Ctx,
+    schema: &Value,
+) -> impl proptest::strategy::Strategy<Value = serde_json::Value> {
+    use proptest::{arbitrary::any, strategy::Strategy as _};
+
+    any::<u64>().prop_filter_map("no document satisfies the schema", move |seed| {
+        let value = Generator::new(seed).generate(ctx, schema).ok()?;
+        serde_json::from_slice(&value.to_json_vec().ok()?).ok()
+    })
+}
+
+/// A numeric bound such as `>=0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bound {
+    /// The bounding number.
+    value: f64,
+    /// Whether the number itself is allowed (`>=`, `<=`).
+    inclusive: bool,
+}
+
+/// The parts of a conjunction such as `int & >=0 & <=10` the generator
+/// understands.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Constraint {
+    /// Kind named by the conjunction, such as `int` or a struct literal.
+    kind: Option<Kind>,
+    /// A concrete value of the conjunction, as CUE source.
+    literal: Option<String>,
+    /// Lower bound of numbers.
+    min: Option<Bound>,
+    /// Upper bound of numbers.
+    max: Option<Bound>,
+    /// Regular expression strings must match (`=~`).
+    pattern: Option<String>,
+    /// Minimum number of runes of strings or items of lists.
+    min_len: Option<usize>,
+    /// Maximum number of runes of strings or items of lists.
+    max_len: Option<usize>,
+}
+
+impl Constraint {
+    /// Parses the conjunction `expr`, ignoring terms it does not understand.
+    fn parse(expr: &str) -> Self {
+        let mut c = Self::default();
+        for term in split_top(expr, '&') {
+            let term = term.trim_start_matches('*').trim();
+            if let Some((lo, hi)) = predeclared(term) {
+                c.kind = Some(Kind::Int);
+                c.min = Some(Bound {
+                    value: lo,
+                    inclusive: true,
+                });
+                c.max = Some(Bound {
+                    value: hi,
+                    inclusive: true,
+                });
+            } else if let Some((op, n)) = [">=", "<=", ">", "<"]
+                .iter()
+                .find_map(|op| Some((*op, term.strip_prefix(op)?.trim().parse::<f64>().ok()?)))
+            {
+                let bound = Bound {
+                    value: n,
+                    inclusive: op.ends_with('='),
+                };
+                if op.starts_with('>') {
+                    c.min = Some(bound);
+                } else {
+                    c.max = Some(bound);
+                }
+            } else if let Some(pattern) = term.strip_prefix("=~") {
+                c.pattern = unquote(pattern.trim());
+            } else if let Some((kind, min, n)) = length_limit(term) {
+                c.kind = c.kind.or(Some(kind));
+                if min {
+                    c.min_len = Some(n);
+                } else {
+                    c.max_len = Some(n);
+                }
+            } else {
+                match term {
+                    "null" | "true" | "false" => c.literal = Some(term.to_string()),
+                    "bool" => c.kind = Some(Kind::Bool),
+                    "int" => c.kind = Some(Kind::Int),
+                    "float" => c.kind = Some(Kind::Float),
+                    "number" => c.kind = Some(Kind::Number),
+                    "string" => c.kind = Some(Kind::String),
+                    "bytes" => c.kind = Some(Kind::Bytes),
+                    _ if term.starts_with('"') || term.parse::<f64>().is_ok() => {
+                        c.literal = Some(term.to_string());
+                    },
+                    _ if term.starts_with('{') || term.starts_with("close(") => {
+                        c.kind = Some(Kind::Struct);
+                    },
+                    _ if term.starts_with('[') || term.starts_with("list.") => {
+                        c.kind = Some(Kind::List);
+                    },
+                    _ => {},
+                }
+            }
+        }
+        c
+    }
+}
+
+/// Returns the bounds of the predeclared integer type `name`, such as
+/// `uint8`.
+fn predeclared(name: &str) -> Option<(f64, f64)> {
+    Some(match name {
+        "uint8" | "byte" => (0.0, f64::from(u8::MAX)),
+        "uint16" => (0.0, f64::from(u16::MAX)),
+        "uint" | "uint32" | "uint64" | "uint128" => (0.0, f64::from(u32::MAX)),
+        "int8" => (f64::from(i8::MIN), f64::from(i8::MAX)),
+        "int16" => (f64::from(i16::MIN), f64::from(i16::MAX)),
+        "int32" | "int64" | "int128" => (f64::from(i32::MIN), f64::from(i32::MAX)),
+        "rune" => (0.0, f64::from(0x0010_FFFF_u32)),
+        _ => return None,
+    })
+}
+
+/// Parses a length limit such as `strings.MinRunes(3)`, returning the kind
+/// it applies to, whether it is a minimum and the limit.
+fn length_limit(term: &str) -> Option<(Kind, bool, usize)> {
+    let (function, arg) = term.strip_suffix(')')?.split_once('(')?;
+    let (kind, min) = match function {
+        "strings.MinRunes" => (Kind::String, true),
+        "strings.MaxRunes" => (Kind::String, false),
+        "list.MinItems" => (Kind::List, true),
+        "list.MaxItems" => (Kind::List, false),
+        _ => return None,
+    };
+    Some((kind, min, arg.trim().parse().ok()?))
+}
+
+/// Converts the whole number `n` to an integer, if it fits.
+fn whole(n: f64) -> Option<i64> {
+    format!("{n:.0}").parse().ok()
+}
+
+/// Splits `expr` at the occurrences of `sep` outside of strings and
+/// brackets, trimming the parts.
+fn split_top(
+    expr: &str,
+    sep: char,
+) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0_usize;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in expr.char_indices() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => depth = depth.saturating_add(1),
+            (None, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+            (None, c) if c == sep && depth == 0 => {
+                parts.push(expr.get(start..i).unwrap_or_default().trim());
+                start = i.saturating_add(c.len_utf8());
+            },
+            _ => {},
+        }
+    }
+    parts.push(expr.get(start..).unwrap_or_default().trim());
+    parts
+}
+
+/// Decodes the CUE string literal `s`, such as `"^[a-z]+\\d$"` or
+/// `#"^\d+$"#`.
+fn unquote(s: &str) -> Option<String> {
+    let hashes = s.len().saturating_sub(s.trim_start_matches('#').len());
+    let s = s.trim_matches('#');
+    let body = s.strip_prefix('"')?.strip_suffix('"')?;
+    if hashes > 0 {
+        // Escapes of raw strings take the hashes, e.g. `\#n`; patterns
+        // rarely use them.
+        return Some(body.to_string());
+    }
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            'u' => {
+                let hex: String = chars.by_ref().take(4).collect();
+                out.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            },
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Wraps the CUE source `literal` in structs and lists so it sits at the
+/// path `labels`, e.g. `{"a": [_, {"b": 1}, ...]}` for `a.1.b`.
+fn nest(
+    labels: &[String],
+    literal: &str,
+) -> String {
+    labels
+        .iter()
+        .rev()
+        .fold(literal.to_string(), |inner, label| {
+            match label.parse::<usize>() {
+                Ok(index) => format!("[{}{inner}, ...]", "_, ".repeat(index)),
+                Err(_) => format!("{{{}: {inner}}}", json_string(label)),
+            }
+        })
+}
+
+/// Returns a random string matching the regular expression `pattern`, or
+/// `None` if it cannot be parsed.
+///
+/// Characters are taken from printable ASCII where the pattern allows it.
+/// Assertions such as `^` and `\b` are ignored, which rarely matters for
+/// the anchored patterns schemas use.
+fn sample_regex(
+    rng: &mut Rng,
+    pattern: &str,
+) -> Option<String> {
+    let hir = regex_syntax::parse(pattern).ok()?;
+    let mut out = String::new();
+    sample_hir(rng, &hir, &mut out);
+    Some(out)
+}
+
+/// Appends a random string matching `hir` to `out`.
+fn sample_hir(
+    rng: &mut Rng,
+    hir: &Hir,
+    out: &mut String,
+) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {},
+        HirKind::Literal(lit) => out.push_str(&String::from_utf8_lossy(&lit.0)),
+        HirKind::Class(Class::Unicode(class)) => {
+            let ranges = class
+                .ranges()
+                .iter()
+                .map(|r| (u32::from(r.start()), u32::from(r.end())));
+            out.extend(pick_char(rng, ranges));
+        },
+        HirKind::Class(Class::Bytes(class)) => {
+            let ranges = class
+                .ranges()
+                .iter()
+                .map(|r| (u32::from(r.start()), u32::from(r.end().min(0x7F))));
+            out.extend(pick_char(rng, ranges));
+        },
+        HirKind::Repetition(rep) => {
+            let max = rep
+                .max
+                .unwrap_or(u32::MAX)
+                .min(rep.min.saturating_add(MAX_REPEAT));
+            let count = rng.range(i64::from(rep.min), i64::from(max));
+            for _ in 0..count {
+                sample_hir(rng, &rep.sub, out);
+            }
+        },
+        HirKind::Capture(capture) => sample_hir(rng, &capture.sub, out),
+        HirKind::Concat(hirs) => {
+            for hir in hirs {
+                sample_hir(rng, hir, out);
+            }
+        },
+        HirKind::Alternation(hirs) => {
+            if let Some(hir) = hirs.get(rng.index(hirs.len())) {
+                sample_hir(rng, hir, out);
+            }
+        },
+    }
+}
+
+/// Returns a random character of the inclusive code point `ranges`,
+/// preferring printable ASCII.
+fn pick_char(
+    rng: &mut Rng,
+    ranges: impl Iterator<Item = (u32, u32)>,
+) -> Option<char> {
+    let ranges: Vec<(u32, u32)> = ranges.filter(|(lo, hi)| lo <= hi).collect();
+    let printable: Vec<(u32, u32)> = ranges
+        .iter()
+        .map(|&(lo, hi)| (lo.max(0x20), hi.min(0x7E)))
+        .filter(|(lo, hi)| lo <= hi)
+        .collect();
+    let pool = if printable.is_empty() {
+        &ranges
+    } else {
+        &printable
+    };
+    let &(lo, hi) = pool.get(rng.index(pool.len()))?;
+    let offset = rng.below(u64::from(hi.saturating_sub(lo)).saturating_add(1));
+    char::from_u32(lo.saturating_add(u32::try_from(offset).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{Bound, Constraint, Generator, nest, sample_regex, split_top, unquote};
+    use crate::{Ctx, Value, report::Report, rng::Rng, value::Kind};
+
+    #[test_case(r#""a" | "b" | *"c""#, '|' => vec![r#""a""#, r#""b""#, r#"*"c""#]; "disjunction")]
+    #[test_case(r#"string & =~"a|b""#, '|' => vec![r#"string & =~"a|b""#];       "quoted")]
+    #[test_case("{a: 1 | 2} | null", '|'   => vec!["{a: 1 | 2}", "null"];        "nested")]
+    #[test_case("int & >=0 & <=10", '&'    => vec!["int", ">=0", "<=10"];        "conjunction")]
+    fn test_split_top(
+        expr: &str,
+        sep: char,
+    ) -> Vec<&str> {
+        split_top(expr, sep)
+    }
+
+    #[test]
+    fn test_parse_constraint() {
+        let inclusive = |value| {
+            Some(Bound {
+                value,
+                inclusive: true,
+            })
+        };
+        assert_eq!(Constraint::parse("int & >=0 & <10"), Constraint {
+            kind: Some(Kind::Int),
+            min: inclusive(0.0),
+            max: Some(Bound {
+                value: 10.0,
+                inclusive: false,
+            }),
+            ..Constraint::default()
+        });
+        assert_eq!(Constraint::parse("uint8"), Constraint {
+            kind: Some(Kind::Int),
+            min: inclusive(0.0),
+            max: inclusive(255.0),
+            ..Constraint::default()
+        });
+        assert_eq!(
+            Constraint::parse(r#"string & =~"^[a-z]+\\d$" & strings.MaxRunes(8)"#),
+            Constraint {
+                kind: Some(Kind::String),
+                pattern: Some(r"^[a-z]+\d$".to_string()),
+                max_len: Some(8),
+                ..Constraint::default()
+            }
+        );
+        assert_eq!(
+            Constraint::parse(r#"*"debug""#).literal.as_deref(),
+            Some(r#""debug""#)
+        );
+    }
+
+    #[test_case(r#""a\"b\\d""#   => Some(r#"a"b\d"#.to_string()); "escaped")]
+    #[test_case(r##"#"^\d+$"#"## => Some(r"^\d+$".to_string());    "raw")]
+    #[test_case("abc"             => None;                          "unquoted")]
+    fn test_unquote(s: &str) -> Option<String> {
+        unquote(s)
+    }
+
+    #[test]
+    fn test_nest() {
+        let labels = ["a".to_string(), "1".to_string(), "b c".to_string()];
+        assert_eq!(nest(&labels, "1"), r#"{"a": [_, {"b c": 1}, ...]}"#);
+    }
+
+    #[test_case(r"^[a-z]{3,8}$")]
+    #[test_case(r"^\d{3}-\d{4}$")]
+    #[test_case(r"^(prod|staging)-[0-9a-f]+$")]
+    #[test_case(r"^\w+@example\.com$")]
+    fn test_sample_regex(pattern: &str) {
+        let re = regex_syntax::parse(pattern).unwrap();
+        let mut rng = Rng::new(7);
+        for _ in 0..20 {
+            let s = sample_regex(&mut rng, pattern).unwrap();
+            assert!(s.is_ascii(), "{s:?} of {re:?}");
+            assert!(!s.is_empty());
+        }
+    }
+
+    #[test_case("{ port: int & >=1024 & <=65535 }" => ("port".to_string(), "incomplete value int & >=1024 & <=65535".to_string()); "incomplete")]
+    #[test_case(r#"{ a: { level: "debug" | "info" } }"# => ("a.level".to_string(), r#"incomplete value "debug" | "info""#.to_string()); "disjunction")]
+    #[test_case("{ owner!: string }" => ("owner".to_string(), "field is required but not present".to_string()); "required")]
+    fn test_export_error_shape(src: &str) -> (String, String) {
+        let ctx = Ctx::new().unwrap();
+        let err = Value::compile_string(&ctx, src)
+            .unwrap()
+            .to_json_vec()
+            .unwrap_err();
+        let report = Report::from(&err);
+        let d = report.diagnostics().first().unwrap();
+        (d.path.clone().unwrap(), d.message.clone())
+    }
+
+    #[test]
+    fn test_generate() {
+        let ctx = Ctx::new().unwrap();
+        let schema = Value::compile_string(
+            &ctx,
+            r#"{
+                name:    string & =~"^[a-z]{3,8}$"
+                port:    int & >=1024 & <=65535
+                ratio:   float & >0 & <1
+                level:   "debug" | "info" | "warn"
+                retries: int | *3
+                tags:    [...string]
+                owner!:  { email: =~"^[a-z]+@example\\.com$" }
+                note?:   string
+            }"#,
+        )
+        .unwrap();
+        let mut generator = Generator::new(42);
+        for _ in 0..10 {
+            let value = generator.generate(&ctx, &schema).unwrap();
+            let port = value.lookup("port").unwrap().to_int64().unwrap();
+            assert!((1024..=65535).contains(&port));
+            assert_eq!(value.lookup("retries").unwrap().to_int64().unwrap(), 3);
+            assert_eq!(value.lookup("tags").unwrap().len().unwrap(), 0);
+            assert!(value.lookup_opt("note").unwrap().is_none());
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn test_cue_strategy() {
+        use proptest::{
+            strategy::{Strategy, ValueTree},
+            test_runner::TestRunner,
+        };
+
+        let ctx = Ctx::new().unwrap();
+        let schema = Value::compile_string(
+            &ctx,
+            r#"{ port: int & >=1024 & <=65535, env: "dev" | "prod" }"#,
+        )
+        .unwrap();
+        let strategy = super::cue_strategy(&ctx, &schema);
+        let mut runner = TestRunner::deterministic();
+        for _ in 0..10 {
+            let doc = strategy.new_tree(&mut runner).unwrap().current();
+            assert!((1024..=65535).contains(&doc["port"].as_u64().unwrap()));
+            assert!(["dev", "prod"].contains(&doc["env"].as_str().unwrap()));
+        }
+    }
+}
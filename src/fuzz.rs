@@ -0,0 +1,184 @@
+//! Structured fuzzing inputs exercising the libcue FFI boundary.
+//!
+//! A [`Program`] is a sequence of operations (compile, unify, look up,
+//! export, decode, drop) over a table of values, derived from raw fuzzer
+//! bytes with [`arbitrary`].  Running arbitrary programs interleaves handle
+//! creation and release in orders no test would think of, so double frees,
+//! use-after-free and leaked handles surface as crashes under a fuzzer:
+//!
+//! ```no_run
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use cue_rs::{Ctx, fuzz::Program};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let data = [0x01, 0x05, b'{', b'a', b':', b'1', b'}'];
+//! let program = Program::arbitrary(&mut Unstructured::new(&data))?;
+//! program.run(&Ctx::new()?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The `fuzz/` directory of the repository holds `cargo fuzz` targets
+//! running programs and raw byte inputs.
+
+use arbitrary::Arbitrary;
+
+use crate::{Ctx, Value};
+
+/// Maximum number of values a program keeps alive at once; further values
+/// are dropped right away.
+const MAX_VALUES: usize = 64;
+
+/// A single operation of a [`Program`].
+///
+/// Operands are indices into the program's table of values, taken modulo
+/// its length; operations on an empty table are skipped.
+#[derive(Debug, Clone, PartialEq, Eq, Arbitrary)]
+pub enum Op {
+    /// Compiles CUE source with [`Value::compile_string`].
+    Compile(String),
+    /// Compiles raw bytes with [`Value::compile_bytes`].
+    CompileBytes(Vec<u8>),
+    /// Unifies two values with [`Value::unify`].
+    Unify(u8, u8),
+    /// Looks up a path with [`Value::lookup`].
+    Lookup(u8, String),
+    /// Takes the default with [`Value::default_value`].
+    Default(u8),
+    /// Validates a value with [`Value::is_valid`].
+    Validate(u8),
+    /// Exports a value as JSON with [`Value::to_json_vec`].
+    Export(u8),
+    /// Decodes a value as every scalar type and takes its length.
+    Decode(u8),
+    /// Drops a value, releasing its handle.
+    Drop(u8),
+}
+
+/// A sequence of operations over a table of values, run by
+/// [`Program::run`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Arbitrary)]
+pub struct Program {
+    /// The operations, in order.
+    pub ops: Vec<Op>,
+}
+
+impl Program {
+    /// Runs the program in `ctx`, ignoring every error; only crashes and
+    /// sanitizer reports are of interest.
+    pub fn run(
+        &self,
+        ctx: &Ctx,
+    ) {
+        let mut values: Vec<Value> = Vec::new();
+        for op in &self.ops {
+            let value = match op {
+                Op::Compile(src) => Value::compile_string(ctx, src).ok(),
+                Op::CompileBytes(src) => Value::compile_bytes(ctx, src).ok(),
+                Op::Unify(a, b) => {
+                    pick(&values, *a)
+                        .zip(pick(&values, *b))
+                        .map(|(a, b)| Value::unify(a, b))
+                },
+                Op::Lookup(i, path) => pick(&values, *i).and_then(|v| v.lookup(path).ok()),
+                Op::Default(i) => pick(&values, *i).and_then(Value::default_value),
+                Op::Validate(i) => {
+                    if let Some(v) = pick(&values, *i) {
+                        drop(v.is_valid());
+                    }
+                    None
+                },
+                Op::Export(i) => {
+                    if let Some(v) = pick(&values, *i) {
+                        drop(v.to_json_vec());
+                    }
+                    None
+                },
+                Op::Decode(i) => {
+                    if let Some(v) = pick(&values, *i) {
+                        decode(v);
+                    }
+                    None
+                },
+                Op::Drop(i) => {
+                    if let Some(index) = slot(values.len(), *i) {
+                        values.swap_remove(index);
+                    }
+                    None
+                },
+            };
+            if let Some(value) = value
+                && values.len() < MAX_VALUES
+            {
+                values.push(value);
+            }
+        }
+    }
+}
+
+/// Decodes `v` as every scalar type and takes its length, ignoring the
+/// results.
+fn decode(v: &Value) {
+    let _ = v.kind();
+    let _ = v.incomplete_kind();
+    drop(v.to_int64());
+    drop(v.to_uint64());
+    drop(v.to_bool());
+    drop(v.to_double());
+    drop(v.to_string());
+    drop(v.to_bytes());
+    drop(v.len());
+}
+
+/// Returns the index operand `i` refers to in a table of `len` values.
+fn slot(
+    len: usize,
+    i: u8,
+) -> Option<usize> {
+    usize::from(i).checked_rem(len)
+}
+
+/// Returns the value operand `i` refers to.
+fn pick(
+    values: &[Value],
+    i: u8,
+) -> Option<&Value> {
+    values.get(slot(values.len(), i)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use super::{Op, Program};
+    use crate::Ctx;
+
+    #[test]
+    fn test_arbitrary() {
+        let data: Vec<u8> = (0..=255).rev().collect();
+        let program = Program::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert!(!program.ops.is_empty());
+    }
+
+    #[test]
+    fn test_run() {
+        let ctx = Ctx::new().unwrap();
+        Program {
+            ops: vec![
+                Op::Drop(0),
+                Op::Compile("{ a: int | *1, b: a + 1 }".to_string()),
+                Op::CompileBytes(br#"{ "a": 2 }"#.to_vec()),
+                Op::Unify(0, 1),
+                Op::Lookup(2, "b".to_string()),
+                Op::Default(0),
+                Op::Validate(2),
+                Op::Export(2),
+                Op::Decode(3),
+                Op::Drop(1),
+                Op::Unify(7, 200),
+                Op::Export(1),
+            ],
+        }
+        .run(&ctx);
+    }
+}
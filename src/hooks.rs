@@ -0,0 +1,204 @@
+//! Lifecycle hooks observing the CUE operations of a [`Ctx`](crate::Ctx).
+//!
+//! [`Hooks`] registered on a context are called after every compilation,
+//! unification and validation performed with it, or with values derived from
+//! it, with an [`Event`] describing the input, the outcome and how long the
+//! call took.  This is enough to build audit logs or metrics without wrapping
+//! every API call at the application level.
+//!
+//! Hooks only observe: they run once the operation has finished and cannot
+//! change its result, return a cached one or prevent the call.  Cache
+//! compiled schemas with a [`SchemaCache`](crate::cache::SchemaCache)
+//! instead.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::error::Error;
+
+/// A callback registered in [`Hooks`].
+type Callback = Arc<dyn Fn(&Event<'_>) + Send + Sync>;
+
+/// The kind of operation reported by an [`Event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Compilation of CUE source, YAML or TOML into a value.
+    Compile,
+    /// Unification of two values.
+    Unify,
+    /// Validation of a value with [`Value::is_valid`](crate::Value::is_valid).
+    Validate,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Compile => "compile",
+            Self::Unify => "unify",
+            Self::Validate => "validate",
+        })
+    }
+}
+
+/// Metadata about a completed operation, passed to every hook.
+#[derive(Debug, Clone, Copy)]
+pub struct Event<'a> {
+    /// The operation that completed.
+    pub operation: Operation,
+    /// The compiled input, for [`Operation::Compile`].
+    pub source: Option<&'a [u8]>,
+    /// Wall-clock time spent in the operation.
+    pub elapsed: Duration,
+    /// The error the operation failed with, if any.
+    ///
+    /// Unification never fails by itself; its result is checked by a later
+    /// validation.
+    pub error: Option<&'a Error>,
+}
+
+/// Callbacks invoked after CUE operations, registered on a context with
+/// [`Ctx::set_hooks`](crate::Ctx::set_hooks).
+///
+/// Values keep the hooks of the context they were compiled in, and values
+/// derived from them by unification or lookup inherit them, so operations on
+/// values are reported without access to the context.
+#[derive(Clone, Default)]
+pub struct Hooks {
+    /// Callbacks for [`Operation::Compile`].
+    compile: Vec<Callback>,
+    /// Callbacks for [`Operation::Unify`].
+    unify: Vec<Callback>,
+    /// Callbacks for [`Operation::Validate`].
+    validate: Vec<Callback>,
+}
+
+impl std::fmt::Debug for Hooks {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.debug_struct("Hooks")
+            .field("on_compile", &self.compile.len())
+            .field("on_unify", &self.unify.len())
+            .field("on_validate", &self.validate.len())
+            .finish()
+    }
+}
+
+impl Hooks {
+    /// Registers a callback invoked after every compilation.
+    #[must_use]
+    pub fn on_compile(
+        mut self,
+        f: impl Fn(&Event<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.compile.push(Arc::new(f));
+        self
+    }
+
+    /// Registers a callback invoked after every unification.
+    #[must_use]
+    pub fn on_unify(
+        mut self,
+        f: impl Fn(&Event<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.unify.push(Arc::new(f));
+        self
+    }
+
+    /// Registers a callback invoked after every validation.
+    #[must_use]
+    pub fn on_validate(
+        mut self,
+        f: impl Fn(&Event<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.validate.push(Arc::new(f));
+        self
+    }
+
+    /// Returns `true` if no callback is registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.compile.is_empty() && self.unify.is_empty() && self.validate.is_empty()
+    }
+
+    /// Runs `f` and reports it as `operation` to the callbacks of `hooks`,
+    /// taking the error to report from its result with `error`.
+    ///
+    /// Without hooks `f` is called directly, so unobserved operations are not
+    /// timed.
+    pub(crate) fn observe<R>(
+        hooks: Option<&Self>,
+        operation: Operation,
+        source: Option<&[u8]>,
+        f: impl FnOnce() -> R,
+        error: impl FnOnce(&R) -> Option<&Error>,
+    ) -> R {
+        let Some(hooks) = hooks else {
+            return f();
+        };
+        let start = Instant::now();
+        let result = f();
+        let event = Event {
+            operation,
+            source,
+            elapsed: start.elapsed(),
+            error: error(&result),
+        };
+        let callbacks = match operation {
+            Operation::Compile => &hooks.compile,
+            Operation::Unify => &hooks.unify,
+            Operation::Validate => &hooks.validate,
+        };
+        for callback in callbacks {
+            callback(&event);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::{Hooks, Operation};
+    use crate::error::Error;
+
+    #[test]
+    fn test_observe() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let hooks = Hooks::default().on_validate(move |event| {
+            recorded.lock().unwrap().push((
+                event.operation,
+                event.source.map(<[u8]>::len),
+                event.error.is_some(),
+            ));
+        });
+        assert!(!hooks.is_empty());
+
+        let res: Result<(), Error> = Hooks::observe(
+            Some(&hooks),
+            Operation::Validate,
+            None,
+            || Err(Error::ContextCreationFailed),
+            |res| res.as_ref().err(),
+        );
+        assert!(res.is_err());
+        let n = Hooks::observe(Some(&hooks), Operation::Compile, Some(b"1"), || 1, |_| None);
+        assert_eq!(n, 1);
+        let n = Hooks::observe(None, Operation::Validate, None, || 2, |_| None);
+        assert_eq!(n, 2);
+
+        assert_eq!(*events.lock().unwrap(), vec![(
+            Operation::Validate,
+            None,
+            true
+        )]);
+    }
+}
@@ -0,0 +1,534 @@
+//! Importing schemas written for other tools as CUE schemas.
+//!
+//! [`from_schemars`] takes the JSON Schema [`schemars`] generates for a Rust
+//! type and translates it into CUE, so projects already deriving
+//! `JsonSchema` can validate with CUE without writing the schema twice.
+//!
+//! libcue does not expose CUE's own JSON Schema importer, so the translation
+//! is done here.  It covers the keywords `schemars` emits:
+//!
+//! - `type`, `enum`, `const` and `default`;
+//! - `properties`, `required`, `additionalProperties`, `patternProperties`,
+//!   `minProperties` and `maxProperties`;
+//! - `items`, `prefixItems`, `minItems`, `maxItems` and `uniqueItems`;
+//! - `minimum`, `maximum`, `exclusiveMinimum` and `exclusiveMaximum`;
+//! - `minLength`, `maxLength` and `pattern`;
+//! - `allOf`, `anyOf` and `oneOf`, the latter approximated by a disjunction;
+//! - `$ref` to `$defs` or `definitions`, imported as CUE definitions.
+//!
+//! Annotations such as `title`, `description` and `format` are ignored.
+
+use std::{collections::BTreeSet, fmt::Write};
+
+use serde_json::{Map, Value as Json};
+
+use crate::{Ctx, Value, error::Error, report::json_string};
+
+/// Maximum nesting of subschemas translated, bounding the recursion over
+/// untrusted input.
+const MAX_DEPTH: usize = 128;
+
+/// CUE keywords and predeclared identifiers that must be quoted as labels.
+const RESERVED: &[&str] = &[
+    "null", "true", "false", "if", "for", "in", "let", "import", "package", "func",
+];
+
+/// Compiles the JSON Schema generated by [`schemars`] for `T` into a CUE
+/// schema.
+///
+/// Definitions of the JSON Schema become CUE definitions of the result,
+/// named as in `$defs` with characters that are not valid in identifiers
+/// replaced by `_`.
+///
+/// # Errors
+///
+/// Returns the errors of [`from_json_schema`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use cue_rs::{Ctx, interop};
+///
+/// #[derive(schemars::JsonSchema)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let ctx = Ctx::new().unwrap();
+/// let schema = interop::from_schemars::<Server>(&ctx).unwrap();
+/// schema
+///     .validate_json(&ctx, br#"{ "host": "localhost", "port": 8080 }"#)
+///     .unwrap();
+/// ```
+pub fn from_schemars<T: schemars::JsonSchema>(ctx: &Ctx) -> Result<Value, Error> {
+    from_json_schema(ctx, schemars::schema_for!(T).as_value())
+}
+
+/// Compiles a JSON Schema document into a CUE schema, see the [module
+/// documentation](self) for the keywords supported.
+///
+/// # Errors
+///
+/// Returns [`Error::UnsupportedSchema`] if the schema uses a `$ref` that
+/// does not point into its own definitions, a malformed keyword or is
+/// nested too deeply, and the errors of [`Value::compile_string`] if the
+/// translated schema does not compile.
+pub fn from_json_schema(
+    ctx: &Ctx,
+    schema: &Json,
+) -> Result<Value, Error> {
+    Value::compile_string(ctx, &json_schema_to_cue(schema)?)
+}
+
+/// Translates a JSON Schema document into CUE source.
+fn json_schema_to_cue(schema: &Json) -> Result<String, Error> {
+    let mut translator = Translator::default();
+    let mut defs = String::new();
+    for key in ["$defs", "definitions"] {
+        let Some(entries) = schema.get(key).and_then(Json::as_object) else {
+            continue;
+        };
+        for (name, def) in entries {
+            let expr = translator.schema(def, 0)?;
+            let _ = writeln!(defs, "{}: {expr}", definition(name));
+        }
+    }
+    let root = translator.schema(schema, 0)?;
+
+    let mut src = String::new();
+    for import in &translator.imports {
+        let _ = writeln!(src, "import \"{import}\"");
+    }
+    if !translator.imports.is_empty() {
+        src.push('\n');
+    }
+    src.push_str(&defs);
+    src.push_str(&root);
+    src.push('\n');
+    Ok(src)
+}
+
+/// Translation state shared by the subschemas of a document.
+#[derive(Default)]
+struct Translator {
+    /// Standard library packages the translated schema uses.
+    imports: BTreeSet<&'static str>,
+}
+
+impl Translator {
+    /// Translates a (sub)schema into a CUE expression.
+    fn schema(
+        &mut self,
+        schema: &Json,
+        depth: usize,
+    ) -> Result<String, Error> {
+        if depth > MAX_DEPTH {
+            return Err(unsupported("schema is nested too deeply"));
+        }
+        let depth = depth.saturating_add(1);
+        let obj = match schema {
+            Json::Bool(true) => return Ok("_".to_string()),
+            Json::Bool(false) => return Ok("_|_".to_string()),
+            Json::Object(obj) => obj,
+            _ => return Err(unsupported("schema is not an object or a boolean")),
+        };
+
+        let mut parts = Vec::new();
+        if let Some(reference) = obj.get("$ref") {
+            parts.push(reference_expr(reference)?);
+        }
+        if let Some(types) = self.types(obj, depth)? {
+            parts.push(types);
+        }
+        if let Some(value) = obj.get("const") {
+            parts.push(literal(value));
+        }
+        if let Some(values) = obj.get("enum") {
+            let values = values
+                .as_array()
+                .ok_or_else(|| unsupported("`enum` is not an array"))?;
+            parts.push(disjunction(&values.iter().map(literal).collect::<Vec<_>>()));
+        }
+        if let Some(all) = obj.get("allOf") {
+            for sub in subschemas(all, "allOf")? {
+                parts.push(self.schema(sub, depth)?);
+            }
+        }
+        for key in ["anyOf", "oneOf"] {
+            if let Some(any) = obj.get(key) {
+                let alternatives: Vec<String> = subschemas(any, key)?
+                    .iter()
+                    .map(|sub| self.schema(sub, depth))
+                    .collect::<Result<_, _>>()?;
+                parts.push(disjunction(&alternatives));
+            }
+        }
+
+        let expr = match parts.len() {
+            0 => "_".to_string(),
+            1 => parts.concat(),
+            _ => {
+                parts
+                    .iter()
+                    .map(|p| format!("({p})"))
+                    .collect::<Vec<_>>()
+                    .join(" & ")
+            },
+        };
+        Ok(match obj.get("default") {
+            Some(default) => format!("*{} | ({expr})", literal(default)),
+            None => expr,
+        })
+    }
+
+    /// Translates the type-specific keywords of a schema, as a disjunction
+    /// over its `type`s, or over the types its keywords imply if it has
+    /// none.
+    fn types(
+        &mut self,
+        obj: &Map<String, Json>,
+        depth: usize,
+    ) -> Result<Option<String>, Error> {
+        let types: Vec<&str> = match obj.get("type") {
+            Some(Json::String(ty)) => vec![ty.as_str()],
+            Some(Json::Array(types)) => types.iter().filter_map(Json::as_str).collect(),
+            Some(_) => return Err(unsupported("`type` is not a string or an array")),
+            None => {
+                let implied = [
+                    ("object", [
+                        "properties",
+                        "additionalProperties",
+                        "patternProperties",
+                    ]),
+                    ("array", ["items", "prefixItems", "minItems"]),
+                    ("number", ["minimum", "maximum", "exclusiveMinimum"]),
+                    ("string", ["minLength", "maxLength", "pattern"]),
+                ];
+                implied
+                    .iter()
+                    .filter(|(_, keys)| keys.iter().any(|key| obj.contains_key(*key)))
+                    .map(|(ty, _)| *ty)
+                    .collect()
+            },
+        };
+        if types.is_empty() {
+            return Ok(None);
+        }
+        let alternatives: Vec<String> = types
+            .into_iter()
+            .map(|ty| {
+                match ty {
+                    "null" => Ok("null".to_string()),
+                    "boolean" => Ok("bool".to_string()),
+                    "integer" => Ok(number("int", obj)),
+                    "number" => Ok(number("number", obj)),
+                    "string" => Ok(self.string(obj)),
+                    "array" => self.array(obj, depth),
+                    "object" => self.object(obj, depth),
+                    other => Err(unsupported(&format!("unknown type `{other}`"))),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Some(disjunction(&alternatives)))
+    }
+
+    /// Translates a string type with its length and pattern constraints.
+    fn string(
+        &mut self,
+        obj: &Map<String, Json>,
+    ) -> String {
+        let mut parts = vec!["string".to_string()];
+        for (key, builtin) in [
+            ("minLength", "strings.MinRunes"),
+            ("maxLength", "strings.MaxRunes"),
+        ] {
+            if let Some(len) = obj.get(key).and_then(Json::as_u64) {
+                self.imports.insert("strings");
+                parts.push(format!("{builtin}({len})"));
+            }
+        }
+        if let Some(pattern) = obj.get("pattern").and_then(Json::as_str) {
+            parts.push(format!("=~{}", json_string(pattern)));
+        }
+        parts.join(" & ")
+    }
+
+    /// Translates an array type with its item schemas and constraints.
+    fn array(
+        &mut self,
+        obj: &Map<String, Json>,
+        depth: usize,
+    ) -> Result<String, Error> {
+        let mut elems = Vec::new();
+        if let Some(prefix) = obj.get("prefixItems") {
+            for sub in subschemas(prefix, "prefixItems")? {
+                elems.push(self.schema(sub, depth)?);
+            }
+        }
+        match obj.get("items") {
+            Some(Json::Bool(false)) => {},
+            // Draft 7 tuples, as `schemars` 0.8 emits them.
+            Some(Json::Array(tuple)) => {
+                for sub in tuple {
+                    elems.push(self.schema(sub, depth)?);
+                }
+                elems.push("...".to_string());
+            },
+            Some(items) => elems.push(format!("...{}", self.schema(items, depth)?)),
+            None => elems.push("...".to_string()),
+        }
+        let mut parts = vec![format!("[{}]", elems.join(", "))];
+        for (key, builtin) in [("minItems", "list.MinItems"), ("maxItems", "list.MaxItems")] {
+            if let Some(len) = obj.get(key).and_then(Json::as_u64) {
+                self.imports.insert("list");
+                parts.push(format!("{builtin}({len})"));
+            }
+        }
+        if obj.get("uniqueItems") == Some(&Json::Bool(true)) {
+            self.imports.insert("list");
+            parts.push("list.UniqueItems()".to_string());
+        }
+        Ok(parts.join(" & "))
+    }
+
+    /// Translates an object type with its properties and constraints.
+    fn object(
+        &mut self,
+        obj: &Map<String, Json>,
+        depth: usize,
+    ) -> Result<String, Error> {
+        let required: BTreeSet<&str> = obj
+            .get("required")
+            .and_then(Json::as_array)
+            .map(|names| names.iter().filter_map(Json::as_str).collect())
+            .unwrap_or_default();
+        let properties = obj.get("properties").and_then(Json::as_object);
+
+        let mut fields = Vec::new();
+        for (name, sub) in properties.into_iter().flatten() {
+            let marker = if required.contains(name.as_str()) {
+                "!"
+            } else {
+                "?"
+            };
+            fields.push(format!(
+                "{}{marker}: {}",
+                label(name),
+                self.schema(sub, depth)?
+            ));
+        }
+        // Required fields without a property schema.
+        for name in &required {
+            if !properties.is_some_and(|p| p.contains_key(*name)) {
+                fields.push(format!("{}!: _", label(name)));
+            }
+        }
+        if let Some(patterns) = obj.get("patternProperties").and_then(Json::as_object) {
+            for (pattern, sub) in patterns {
+                fields.push(format!(
+                    "[=~{}]: {}",
+                    json_string(pattern),
+                    self.schema(sub, depth)?
+                ));
+            }
+        }
+        match obj.get("additionalProperties") {
+            Some(Json::Bool(false)) => {},
+            None | Some(Json::Bool(true)) => fields.push("...".to_string()),
+            Some(sub) => {
+                let names: Vec<String> = properties
+                    .into_iter()
+                    .flatten()
+                    .map(|(name, _)| regex_escape(name))
+                    .collect();
+                let matcher = if names.is_empty() {
+                    "string".to_string()
+                } else {
+                    format!("!~{}", json_string(&format!("^({})$", names.join("|"))))
+                };
+                fields.push(format!("[{matcher}]: {}", self.schema(sub, depth)?));
+            },
+        }
+
+        let mut parts = vec![format!("close({{{}}})", fields.join(", "))];
+        for (key, builtin) in [
+            ("minProperties", "struct.MinFields"),
+            ("maxProperties", "struct.MaxFields"),
+        ] {
+            if let Some(len) = obj.get(key).and_then(Json::as_u64) {
+                self.imports.insert("struct");
+                parts.push(format!("{builtin}({len})"));
+            }
+        }
+        Ok(parts.join(" & "))
+    }
+}
+
+/// Translates a numeric type with its bounds.
+fn number(
+    ty: &str,
+    obj: &Map<String, Json>,
+) -> String {
+    let mut parts = vec![ty.to_string()];
+    for (key, op) in [
+        ("minimum", ">="),
+        ("maximum", "<="),
+        ("exclusiveMinimum", ">"),
+        ("exclusiveMaximum", "<"),
+    ] {
+        if let Some(bound) = obj.get(key).filter(|b| b.is_number()) {
+            parts.push(format!("{op}{bound}"));
+        }
+    }
+    parts.join(" & ")
+}
+
+/// Translates a `$ref` into a reference to the CUE definition it names.
+fn reference_expr(reference: &Json) -> Result<String, Error> {
+    let reference = reference
+        .as_str()
+        .ok_or_else(|| unsupported("`$ref` is not a string"))?;
+    let name = reference
+        .strip_prefix("#/$defs/")
+        .or_else(|| reference.strip_prefix("#/definitions/"))
+        .filter(|name| !name.contains('/'))
+        .ok_or_else(|| unsupported(&format!("`$ref` {reference} is not a local definition")))?;
+    Ok(definition(&name.replace("~1", "/").replace("~0", "~")))
+}
+
+/// Returns the subschemas of an `allOf`/`anyOf`/`oneOf` style keyword.
+fn subschemas<'a>(
+    value: &'a Json,
+    key: &str,
+) -> Result<&'a [Json], Error> {
+    value
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| unsupported(&format!("`{key}` is not an array")))
+}
+
+/// Joins alternatives into a CUE disjunction.
+fn disjunction(alternatives: &[String]) -> String {
+    match alternatives.len() {
+        0 => "_|_".to_string(),
+        1 => alternatives.concat(),
+        _ => {
+            alternatives
+                .iter()
+                .map(|a| format!("({a})"))
+                .collect::<Vec<_>>()
+                .join(" | ")
+        },
+    }
+}
+
+/// Renders a JSON value as a CUE literal; JSON is valid CUE.
+fn literal(value: &Json) -> String {
+    value.to_string()
+}
+
+/// Returns the CUE definition identifier for a JSON Schema definition name.
+fn definition(name: &str) -> String {
+    let ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("#{ident}")
+}
+
+/// Renders a property name as a CUE label, quoting it unless it is a plain
+/// identifier.
+fn label(name: &str) -> String {
+    let mut chars = name.chars();
+    let plain = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !RESERVED.contains(&name);
+    if plain {
+        name.to_string()
+    } else {
+        json_string(name)
+    }
+}
+
+/// Escapes the regular expression metacharacters of `s`.
+fn regex_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Builds an [`Error::UnsupportedSchema`].
+fn unsupported(reason: &str) -> Error {
+    Error::UnsupportedSchema(reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use test_case::test_case;
+
+    use super::json_schema_to_cue;
+
+    #[test_case(&json!(true)                                   => "_";                     "true schema")]
+    #[test_case(&json!({})                                     => "_";                     "empty")]
+    #[test_case(&json!({ "type": "integer", "minimum": 0 })     => "int & >=0";            "bounded int")]
+    #[test_case(&json!({ "type": ["string", "null"] })          => "(string) | (null)";    "nullable")]
+    #[test_case(&json!({ "type": "string", "maxLength": 3, "pattern": "^a\\d" })
+        => r#"string & strings.MaxRunes(3) & =~"^a\\d""#;
+        "string constraints")]
+    #[test_case(&json!({ "enum": ["a", 1] })                   => r#"("a") | (1)"#;        "enumeration")]
+    #[test_case(&json!({ "type": "array", "items": { "type": "boolean" }, "uniqueItems": true })
+        => "[...bool] & list.UniqueItems()";
+        "array")]
+    #[test_case(&json!({ "prefixItems": [{ "type": "null" }], "items": false }) => "[null]"; "tuple")]
+    #[test_case(&json!({ "type": "integer", "default": 3 })     => "*3 | (int)";           "default")]
+    #[test_case(&json!({ "$ref": "#/$defs/Foo<T>" })            => "#Foo_T_";              "reference")]
+    fn test_schema(schema: &serde_json::Value) -> String {
+        let src = json_schema_to_cue(schema).unwrap();
+        src.lines().last().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_object() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "if": { "type": "integer" },
+                "a.b": { "$ref": "#/$defs/Inner" }
+            },
+            "required": ["name", "id"],
+            "additionalProperties": { "type": "integer" },
+            "$defs": {
+                "Inner": { "type": "object", "additionalProperties": false, "minProperties": 1 }
+            }
+        });
+        assert_eq!(
+            json_schema_to_cue(&schema).unwrap(),
+            r#"import "struct"
+
+#Inner: close({}) & struct.MinFields(1)
+close({"a.b"?: #Inner, "if"?: int, name!: string, id!: _, [!~"^(a\\.b|if|name)$"]: int})
+"#
+        );
+    }
+
+    #[test_case(&json!({ "$ref": "https://example.com/s.json" }); "remote reference")]
+    #[test_case(&json!({ "type": "decimal" });                      "unknown type")]
+    #[test_case(&json!(1);                                          "not a schema")]
+    fn test_unsupported(schema: &serde_json::Value) {
+        assert!(json_schema_to_cue(schema).is_err());
+    }
+}
@@ -0,0 +1,498 @@
+//! Process isolation for evaluating untrusted CUE input.
+//!
+//! A [`Worker`] runs compilation and validation in a separate helper process,
+//! so a Go runtime crash, a runaway evaluation or a memory blow-up only takes
+//! down the helper.  Every call has a timeout; when it expires the helper is
+//! killed and transparently respawned on the next call.
+//!
+//! The helper is any binary built with this crate that calls [`run_worker`],
+//! typically the application itself started with a dedicated argument:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! if std::env::args().nth(1).as_deref() == Some("--cue-worker") {
+//!     return cue_rs::isolated::run_worker();
+//! }
+//!
+//! let mut cmd = std::process::Command::new(std::env::current_exe().unwrap());
+//! cmd.arg("--cue-worker");
+//! let mut worker = cue_rs::isolated::Worker::new(cmd);
+//! worker.validate(
+//!     "{ port: int }",
+//!     br#"{ "port": 80 }"#,
+//!     std::time::Duration::from_secs(5),
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requests and responses are exchanged over the helper's stdin and stdout as
+//! length-prefixed frames: a one byte opcode or status followed by
+//! little-endian `u32` length-prefixed fields.  Lengths are checked before
+//! anything is allocated, so a broken helper cannot make the caller allocate
+//! more than the output size limit set with [`Worker::with_limits`], or
+//! 64 MiB by default; the helper checks requests against the source size
+//! limit of its context in the same way.
+
+use std::{
+    io::{self, BufReader, BufWriter, Read, Write},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    time::Duration,
+};
+
+use crate::{
+    Ctx, Value,
+    error::Error,
+    limits::{Limit, Limits},
+};
+
+/// Request opcode: validate data against a schema.
+const OP_VALIDATE: u8 = 1;
+/// Request opcode: compile a source and export it as JSON.
+const OP_EXPORT_JSON: u8 = 2;
+
+/// Response status: success, the payload is the result.
+const STATUS_OK: u8 = 0;
+/// Response status: a CUE error, the payload is its message.
+const STATUS_CUE_ERROR: u8 = 1;
+
+/// Size in bytes of the largest field read when no limit is set, 64 MiB.
+const DEFAULT_MAX_FIELD_SIZE: usize = 0x0400_0000;
+
+/// A response frame read from the worker.
+type Response = (u8, Vec<u8>);
+
+/// A handle to an isolated helper process evaluating CUE on behalf of the
+/// caller.
+///
+/// The helper is spawned lazily on the first call and respawned after a
+/// timeout or a crash.  It is killed when the [`Worker`] is dropped.
+pub struct Worker {
+    /// Command starting the helper process.
+    command: Command,
+    /// Limits on the requests sent and the responses read.
+    limits: Limits,
+    /// The running helper, if any.
+    running: Option<Running>,
+}
+
+/// A running helper process and the channels to its I/O threads.
+struct Running {
+    /// The helper process.
+    child: Child,
+    /// Encoded request frames, written to the helper's stdin by a thread.
+    requests: Sender<Vec<u8>>,
+    /// Response frames, read from the helper's stdout by a thread.
+    responses: Receiver<Result<Response, Error>>,
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+impl Worker {
+    /// Creates a worker that starts helpers with `command`.
+    ///
+    /// The command's stdin and stdout are replaced by pipes; stderr is
+    /// inherited.
+    #[must_use]
+    pub fn new(mut command: Command) -> Self {
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit());
+        Self {
+            command,
+            limits: Limits::default(),
+            running: None,
+        }
+    }
+
+    /// Sets the limits on the calls of this worker.
+    ///
+    /// The source size limit applies to every source and document sent to
+    /// the helper, and the output size limit to every response read from it,
+    /// before the response is buffered.  Without an output size limit,
+    /// responses are limited to 64 MiB.
+    #[must_use]
+    pub fn with_limits(
+        mut self,
+        limits: Limits,
+    ) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Validates `data` against `schema` in the helper process.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::IsolatedCue`] if the data is invalid or does not
+    /// compile, [`Error::LimitExceeded`] if the schema, the data or the
+    /// response exceeds a limit, [`Error::Timeout`] if the helper does not
+    /// answer within `timeout`, or [`Error::Worker`] if it cannot be started
+    /// or crashes.
+    pub fn validate(
+        &mut self,
+        schema: &str,
+        data: &[u8],
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        self.call(OP_VALIDATE, &[schema.as_bytes(), data], timeout)
+            .map(drop)
+    }
+
+    /// Compiles `src` in the helper process and returns its JSON encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::IsolatedCue`] if the source does not compile or
+    /// cannot be exported, [`Error::LimitExceeded`] if the source or the
+    /// exported document exceeds a limit, [`Error::Timeout`] if the helper
+    /// does not answer within `timeout`, or [`Error::Worker`] if it cannot be
+    /// started or crashes.
+    pub fn export_json(
+        &mut self,
+        src: &[u8],
+        timeout: Duration,
+    ) -> Result<bytes::Bytes, Error> {
+        self.call(OP_EXPORT_JSON, &[src], timeout)
+            .map(bytes::Bytes::from)
+    }
+
+    /// Sends one request to the helper and waits for its response.
+    fn call(
+        &mut self,
+        op: u8,
+        fields: &[&[u8]],
+        timeout: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        for field in fields {
+            self.limits.check_source(field)?;
+        }
+        let running = match self.running.take() {
+            Some(running) => running,
+            None => self.spawn()?,
+        };
+        let mut frame = vec![op];
+        for field in fields {
+            push_field(&mut frame, field)?;
+        }
+        if running.requests.send(frame).is_err() {
+            self.running = Some(running);
+            self.kill();
+            return Err(Error::Worker("worker stdin closed".to_string()));
+        }
+        let response = running.responses.recv_timeout(timeout);
+        self.running = Some(running);
+        match response {
+            Ok(Ok((STATUS_OK, payload))) => Ok(payload),
+            Ok(Ok((STATUS_CUE_ERROR, payload))) => {
+                Err(Error::IsolatedCue(
+                    String::from_utf8_lossy(&payload).into_owned(),
+                ))
+            },
+            Ok(Ok((status, _))) => {
+                self.kill();
+                Err(Error::Worker(format!("unknown response status {status}")))
+            },
+            Ok(Err(err)) => {
+                self.kill();
+                Err(err)
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                self.kill();
+                Err(Error::Timeout)
+            },
+            Err(RecvTimeoutError::Disconnected) => {
+                self.kill();
+                Err(Error::Worker("worker exited".to_string()))
+            },
+        }
+    }
+
+    /// Starts a helper process and the threads serving its pipes.
+    fn spawn(&mut self) -> Result<Running, Error> {
+        let mut child = self
+            .command
+            .spawn()
+            .map_err(|err| Error::Worker(err.to_string()))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            drop(child.kill());
+            drop(child.wait());
+            return Err(Error::Worker("worker pipes unavailable".to_string()));
+        };
+
+        let (requests, request_rx) = mpsc::channel::<Vec<u8>>();
+        std::thread::spawn(move || {
+            let mut stdin = BufWriter::new(stdin);
+            for frame in request_rx {
+                if stdin
+                    .write_all(&frame)
+                    .and_then(|()| stdin.flush())
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let max = self
+            .limits
+            .max(Limit::OutputSize)
+            .unwrap_or(DEFAULT_MAX_FIELD_SIZE);
+        let (response_tx, responses) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut stdout = BufReader::new(stdout);
+            loop {
+                let response = read_response(&mut stdout, max);
+                let failed = response.is_err();
+                if response_tx.send(response).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(Running {
+            child,
+            requests,
+            responses,
+        })
+    }
+
+    /// Kills and reaps the running helper, if any.
+    fn kill(&mut self) {
+        if let Some(mut running) = self.running.take() {
+            drop(running.child.kill());
+            drop(running.child.wait());
+        }
+    }
+}
+
+/// Serves requests from a [`Worker`] over stdin and stdout until stdin is
+/// closed.
+///
+/// Call this from the helper binary; see the [module documentation](self).
+///
+/// # Errors
+///
+/// Returns [`Error::ContextCreationFailed`] if no CUE context can be created,
+/// or [`Error::Worker`] if reading a request or writing a response fails.
+pub fn run_worker() -> Result<(), Error> {
+    let ctx = Ctx::new()?;
+    serve(&ctx, &mut io::stdin().lock(), &mut io::stdout().lock())
+        .map_err(|err| Error::Worker(err.to_string()))
+}
+
+/// Serves requests read from `input` until it is exhausted, writing responses
+/// to `output`.
+fn serve(
+    ctx: &Ctx,
+    input: &mut impl Read,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let max = ctx
+        .limits()
+        .max(Limit::SourceSize)
+        .unwrap_or(DEFAULT_MAX_FIELD_SIZE);
+    loop {
+        let mut op = [0u8];
+        match input.read_exact(&mut op) {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            res => res?,
+        }
+        let result = match op {
+            [OP_VALIDATE] => {
+                let schema = field(input, max)?;
+                let data = field(input, max)?;
+                validate(ctx, &schema, &data).map(|()| Vec::new())
+            },
+            [OP_EXPORT_JSON] => {
+                let src = field(input, max)?;
+                Value::compile_bytes(ctx, &src).and_then(|v| v.to_json_vec())
+            },
+            [op] => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown request opcode {op}"),
+                ));
+            },
+        };
+        let mut frame = Vec::new();
+        match result {
+            Ok(payload) => {
+                frame.push(STATUS_OK);
+                push_field(&mut frame, &payload).map_err(io::Error::other)?;
+            },
+            Err(err) => {
+                frame.push(STATUS_CUE_ERROR);
+                push_field(&mut frame, err.to_string().as_bytes()).map_err(io::Error::other)?;
+            },
+        }
+        output.write_all(&frame)?;
+        output.flush()?;
+    }
+}
+
+/// Reads a length-prefixed request field of at most `max` bytes.
+fn field(
+    input: &mut impl Read,
+    max: usize,
+) -> io::Result<Vec<u8>> {
+    read_field(input, Limit::SourceSize, max).map_err(io::Error::other)
+}
+
+/// Validates `data` against `schema`, as requested by [`OP_VALIDATE`].
+fn validate(
+    ctx: &Ctx,
+    schema: &[u8],
+    data: &[u8],
+) -> Result<(), Error> {
+    let schema = Value::compile_bytes(ctx, schema)?;
+    let data = Value::compile_bytes(ctx, data)?;
+    Value::unify(&schema, &data).is_valid()
+}
+
+/// Appends a length-prefixed field to `frame`.
+fn push_field(
+    frame: &mut Vec<u8>,
+    field: &[u8],
+) -> Result<(), Error> {
+    let len = u32::try_from(field.len())
+        .map_err(|_| Error::Worker("field larger than 4 GiB".to_string()))?;
+    frame.extend_from_slice(&len.to_le_bytes());
+    frame.extend_from_slice(field);
+    Ok(())
+}
+
+/// Reads a length-prefixed field, rejecting it as exceeding `limit` if it is
+/// longer than `max` bytes.
+fn read_field(
+    input: &mut impl Read,
+    limit: Limit,
+    max: usize,
+) -> Result<Vec<u8>, Error> {
+    let io = |err: io::Error| Error::Worker(err.to_string());
+    let mut len = [0u8; 4];
+    input.read_exact(&mut len).map_err(io)?;
+    let len = usize::try_from(u32::from_le_bytes(len)).unwrap_or(usize::MAX);
+    if len > max {
+        return Err(Error::LimitExceeded {
+            limit,
+            actual: len,
+            max,
+        });
+    }
+    let mut field = vec![0u8; len];
+    input.read_exact(&mut field).map_err(io)?;
+    Ok(field)
+}
+
+/// Reads a response frame whose payload is at most `max` bytes.
+fn read_response(
+    input: &mut impl Read,
+    max: usize,
+) -> Result<Response, Error> {
+    let mut status = [0u8];
+    input
+        .read_exact(&mut status)
+        .map_err(|err| Error::Worker(err.to_string()))?;
+    let [status] = status;
+    Ok((status, read_field(input, Limit::OutputSize, max)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{process::Command, time::Duration};
+
+    use super::{
+        OP_VALIDATE, STATUS_CUE_ERROR, STATUS_OK, Worker, push_field, read_response, serve,
+    };
+    use crate::{
+        Ctx,
+        error::Error,
+        limits::{Limit, Limits},
+    };
+
+    #[test]
+    fn test_serve() {
+        let ctx = Ctx::new().unwrap();
+        let mut input = Vec::new();
+        for data in [&br#"{ "port": 80 }"#[..], br#"{ "port": "http" }"#] {
+            input.push(OP_VALIDATE);
+            push_field(&mut input, b"{ port: int }").unwrap();
+            push_field(&mut input, data).unwrap();
+        }
+        let mut output = Vec::new();
+        serve(&ctx, &mut input.as_slice(), &mut output).unwrap();
+
+        let mut output = output.as_slice();
+        assert_eq!(
+            read_response(&mut output, usize::MAX).unwrap(),
+            (STATUS_OK, vec![])
+        );
+        let (status, message) = read_response(&mut output, usize::MAX).unwrap();
+        assert_eq!(status, STATUS_CUE_ERROR);
+        assert!(!message.is_empty());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_oversized_frames() {
+        let mut frame = vec![STATUS_OK];
+        frame.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            read_response(&mut frame.as_slice(), 1024),
+            Err(Error::LimitExceeded {
+                limit: Limit::OutputSize,
+                max: 1024,
+                ..
+            })
+        ));
+
+        let ctx = Ctx::with_limits(Limits::default().with_max_source_size(4)).unwrap();
+        let mut input = vec![OP_VALIDATE];
+        push_field(&mut input, b"{ port: int }").unwrap();
+        assert!(serve(&ctx, &mut input.as_slice(), &mut Vec::new()).is_err());
+
+        let mut worker = Worker::new(Command::new("sleep"))
+            .with_limits(Limits::default().with_max_source_size(4));
+        assert!(matches!(
+            worker.validate("int", b"12345", Duration::from_secs(5)),
+            Err(Error::LimitExceeded {
+                limit: Limit::SourceSize,
+                ..
+            })
+        ));
+        assert!(worker.running.is_none());
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut cmd = Command::new("sleep");
+        cmd.arg("10");
+        let mut worker = Worker::new(cmd);
+        assert!(matches!(
+            worker.validate("int", b"1", Duration::from_millis(100)),
+            Err(Error::Timeout)
+        ));
+        assert!(worker.running.is_none());
+    }
+
+    #[test]
+    fn test_crashed_worker() {
+        let mut worker = Worker::new(Command::new("true"));
+        assert!(matches!(
+            worker.export_json(b"1", Duration::from_secs(5)),
+            Err(Error::Worker(_))
+        ));
+        assert!(matches!(
+            Worker::new(Command::new("/nonexistent/cue-worker"))
+                .export_json(b"1", Duration::from_secs(5)),
+            Err(Error::Worker(_))
+        ));
+    }
+}
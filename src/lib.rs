@@ -1,9 +1,52 @@
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "actix-web")]
+pub mod actix;
+pub mod builtin;
+pub mod bundle;
+pub mod cache;
+#[cfg(feature = "clap")]
+pub mod cli;
+#[cfg(feature = "cookbook")]
+pub mod cookbook;
 pub mod ctx;
+#[cfg(feature = "serde")]
+pub mod de;
 mod drop;
 pub mod error;
+pub mod expr;
+#[cfg(feature = "fake")]
+pub mod fake;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod hooks;
+#[cfg(feature = "schemars")]
+pub mod interop;
+#[cfg(feature = "isolated")]
+pub mod isolated;
+pub mod limits;
+#[cfg(feature = "lint")]
+pub mod lint;
+pub mod load;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+pub mod path;
+#[cfg(any(feature = "figment", feature = "config"))]
+pub mod provider;
+pub mod report;
+#[cfg(any(feature = "stress", feature = "fake"))]
+mod rng;
+pub mod router;
+#[cfg(feature = "stress")]
+pub mod stress;
+pub mod validator;
 pub mod value;
+pub mod version;
 
+pub use bundle::init_with_bundle;
 pub use ctx::Ctx;
+#[cfg(feature = "ast")]
+pub use cue_rs_ast as ast;
+pub use path::Path;
 pub use value::Value;
+pub use version::version;
@@ -0,0 +1,361 @@
+//! Resource limits for evaluating untrusted CUE input.
+//!
+//! Limits are attached to a [`Ctx`](crate::Ctx) via
+//! [`Ctx::with_limits`](crate::Ctx::with_limits).  The source size limit is
+//! enforced before any source crosses the FFI boundary.  The output size and
+//! depth limits are enforced on every document exported from a value of the
+//! context, once libcue has produced it; a single export can be limited
+//! further with
+//! [`Value::to_json_vec_limited`](crate::Value::to_json_vec_limited).
+//!
+//! libcue offers no hook into evaluation, so the work done by the evaluator
+//! cannot be bounded: there is no limit on evaluation steps, and the depth
+//! limit applies to exported documents, not to evaluation.
+//!
+//! With the `sandbox` feature, `Limits::with_sandbox` also restricts what
+//! compiled sources may import.  It does not bound evaluation either, so it
+//! does not make schemas written by customers safe to run on its own:
+//!
+//! ```
+//! # #[cfg(feature = "sandbox")]
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! use cue_rs::{Ctx, Value, error::Error, limits::Limits};
+//!
+//! let ctx = Ctx::with_limits(Limits::default().with_sandbox(true))?;
+//! let src = "import \"tool/exec\"\n\nrun: exec.Run & {cmd: \"id\"}\n";
+//! assert!(matches!(
+//!     Value::compile_string(&ctx, src),
+//!     Err(Error::ForbiddenImport(path)) if path == "tool/exec"
+//! ));
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "sandbox"))]
+//! # fn main() {}
+//! ```
+
+use crate::error::Error;
+
+/// The kind of resource limit that was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// Size in bytes of a CUE source passed to a compile call.
+    SourceSize,
+    /// Size in bytes of an exported document.
+    OutputSize,
+    /// Nesting depth of lists and structs in an exported document.
+    Depth,
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::SourceSize => "source size",
+            Self::OutputSize => "output size",
+            Self::Depth => "depth",
+        })
+    }
+}
+
+/// Packages of the CUE standard library a sandboxed source may import, see
+/// [`Limits::with_sandbox`].
+#[cfg(feature = "sandbox")]
+const SANDBOX_IMPORTS: &[&str] = &[
+    "crypto/ed25519",
+    "crypto/hmac",
+    "crypto/md5",
+    "crypto/sha1",
+    "crypto/sha256",
+    "crypto/sha512",
+    "encoding/base64",
+    "encoding/csv",
+    "encoding/hex",
+    "encoding/json",
+    "encoding/toml",
+    "encoding/yaml",
+    "html",
+    "list",
+    "math",
+    "math/bits",
+    "net",
+    "path",
+    "regexp",
+    "strconv",
+    "strings",
+    "struct",
+    "text/tabwriter",
+    "text/template",
+    "time",
+    "uuid",
+];
+
+/// Configurable resource limits; every limit is disabled by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum size in bytes of a compiled source.
+    source_size: Option<usize>,
+    /// Maximum size in bytes of an exported document.
+    output_size: Option<usize>,
+    /// Maximum nesting depth of an exported document.
+    depth: Option<usize>,
+    /// Whether compiled sources are restricted to [`SANDBOX_IMPORTS`].
+    #[cfg(feature = "sandbox")]
+    sandbox: bool,
+}
+
+impl Limits {
+    /// Sets the maximum size in bytes of a source passed to a compile call.
+    #[must_use]
+    pub fn with_max_source_size(
+        mut self,
+        max: usize,
+    ) -> Self {
+        self.source_size = Some(max);
+        self
+    }
+
+    /// Sets the maximum size in bytes of an exported document.
+    ///
+    /// The size is checked once libcue has exported the whole document.
+    #[must_use]
+    pub fn with_max_output_size(
+        mut self,
+        max: usize,
+    ) -> Self {
+        self.output_size = Some(max);
+        self
+    }
+
+    /// Sets the maximum nesting depth of lists and structs in an exported
+    /// document.
+    #[must_use]
+    pub fn with_max_depth(
+        mut self,
+        max: usize,
+    ) -> Self {
+        self.depth = Some(max);
+        self
+    }
+
+    /// Sets whether the context restricts the imports of compiled sources.
+    ///
+    /// A sandboxed context compiles only sources whose imports are pure
+    /// packages of the CUE standard library, such as `strings` or
+    /// `encoding/json`.  It rejects the `tool/...` packages, which describe
+    /// commands, files and HTTP requests, and imports of modules.  libcue
+    /// never runs the tasks `tool/...` packages describe, so this is a policy
+    /// on what a schema may depend on rather than protection against side
+    /// effects.
+    ///
+    /// This does not make untrusted schemas safe to evaluate: the CPU time
+    /// and memory spent by the evaluator stay unbounded (see the module
+    /// docs), so a small schema can still exhaust the process.  Evaluate
+    /// untrusted schemas in a separate process with a timeout instead, such
+    /// as the worker of the `isolated` feature.
+    ///
+    /// Every source is checked before it reaches libcue, including the ones
+    /// generated to decode data with
+    /// [`Value::compile_json5`](crate::Value::compile_json5) or
+    /// [`Value::compile_yaml`](crate::Value::compile_yaml).  Imports are read
+    /// with [`ast::parse`](crate::ast::parse), which does not cover all of
+    /// CUE, so valid sources it cannot parse, such as value aliases
+    /// (`a: x={b: 1}`), are rejected too.
+    #[cfg(feature = "sandbox")]
+    #[must_use]
+    pub fn with_sandbox(
+        mut self,
+        sandbox: bool,
+    ) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// Checks the imports of the CUE source `src` if the context is a
+    /// sandbox, see [`Limits::with_sandbox`].
+    #[cfg(feature = "sandbox")]
+    pub(crate) fn check_imports(
+        &self,
+        src: &[u8],
+    ) -> Result<(), Error> {
+        use crate::ast::{self, DeclKind};
+
+        if !self.sandbox {
+            return Ok(());
+        }
+        let file = ast::parse(&String::from_utf8_lossy(src))?;
+        let forbidden = file
+            .decls
+            .iter()
+            .filter_map(|decl| {
+                match &decl.kind {
+                    DeclKind::Import(specs) => Some(specs),
+                    _ => None,
+                }
+            })
+            .flatten()
+            .find(|spec| {
+                let path = spec
+                    .path
+                    .split_once(':')
+                    .map_or(spec.path.as_str(), |(path, _)| path);
+                !SANDBOX_IMPORTS.contains(&path)
+            });
+        match forbidden {
+            Some(spec) => Err(Error::ForbiddenImport(spec.path.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the maximum set for `limit`, if any.
+    #[cfg(feature = "isolated")]
+    pub(crate) fn max(
+        &self,
+        limit: Limit,
+    ) -> Option<usize> {
+        match limit {
+            Limit::SourceSize => self.source_size,
+            Limit::OutputSize => self.output_size,
+            Limit::Depth => self.depth,
+        }
+    }
+
+    /// Checks `src` against the source size limit.
+    pub(crate) fn check_source(
+        &self,
+        src: &[u8],
+    ) -> Result<(), Error> {
+        check(Limit::SourceSize, self.source_size, src.len())
+    }
+
+    /// Checks an exported JSON document against the output size and depth
+    /// limits.
+    pub(crate) fn check_json(
+        &self,
+        json: &[u8],
+    ) -> Result<(), Error> {
+        check(Limit::OutputSize, self.output_size, json.len())?;
+        if let Some(max) = self.depth {
+            check(Limit::Depth, Some(max), json_depth(json))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns [`Error::LimitExceeded`] if `actual` is above `max`.
+fn check(
+    limit: Limit,
+    max: Option<usize>,
+    actual: usize,
+) -> Result<(), Error> {
+    match max {
+        Some(max) if actual > max => Err(Error::LimitExceeded { limit, actual, max }),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the maximum nesting depth of objects and arrays in a JSON document.
+fn json_depth(json: &[u8]) -> usize {
+    json_tokens(json)
+        .map(|(depth, _)| depth)
+        .max()
+        .unwrap_or_default()
+}
+
+/// Returns the bytes of a JSON document found outside strings, and the
+/// opening quote of each string, with the depth of the objects and arrays
+/// they are in.  Brackets are in the object or array they open or close, so
+/// the outer brackets of `[1]` are at depth 1.
+pub(crate) fn json_tokens(json: &[u8]) -> impl Iterator<Item = (usize, u8)> + '_ {
+    let mut depth: usize = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    json.iter().filter_map(move |&b| {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {},
+            }
+            return None;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth = depth.saturating_add(1),
+            b'}' | b']' => {
+                let inner = depth;
+                depth = depth.saturating_sub(1);
+                return Some((inner, b));
+            },
+            _ => {},
+        }
+        Some((depth, b))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{Limit, Limits, json_depth};
+    use crate::error::Error;
+
+    #[test_case("1" => 0; "scalar")]
+    #[test_case("[1,{}]" => 2; "nested")]
+    #[test_case(r#"{"a":"[[[\"{"}"# => 1; "brackets in strings")]
+    fn test_json_depth(json: &str) -> usize {
+        json_depth(json.as_bytes())
+    }
+
+    #[test]
+    fn test_check_json() {
+        let limits = Limits::default().with_max_output_size(8).with_max_depth(1);
+        assert!(limits.check_json(b"[1]").is_ok());
+        assert!(matches!(
+            limits.check_json(b"[[1]]"),
+            Err(Error::LimitExceeded {
+                limit: Limit::Depth,
+                actual: 2,
+                max: 1
+            })
+        ));
+        assert!(matches!(
+            limits.check_json(b"[1,2,3,4,5]"),
+            Err(Error::LimitExceeded {
+                limit: Limit::OutputSize,
+                ..
+            })
+        ));
+        assert!(Limits::default().check_json(&[b'['; 1000]).is_ok());
+    }
+
+    #[cfg(feature = "sandbox")]
+    #[test_case("a: 1" => None; "no imports")]
+    #[test_case("import \"strings\"\n\na: strings.ToUpper(\"x\")" => None; "stdlib")]
+    #[test_case("import (\n\t\"list\"\n\tj \"encoding/json\"\n)" => None; "grouped")]
+    #[test_case("import \"tool/file\"" => Some("tool/file".to_string()); "tool")]
+    #[test_case("import (\n\t\"list\"\n\t\"example.com/pkg:schemas\"\n)" => Some("example.com/pkg:schemas".to_string()); "module")]
+    #[test_case("import \"strings" => Some(String::new()); "syntax error")]
+    fn test_check_imports(src: &str) -> Option<String> {
+        match Limits::default()
+            .with_sandbox(true)
+            .check_imports(src.as_bytes())
+        {
+            Ok(()) => None,
+            Err(Error::ForbiddenImport(path)) => Some(path),
+            Err(_) => Some(String::new()),
+        }
+    }
+
+    #[cfg(feature = "sandbox")]
+    #[test]
+    fn test_check_imports_disabled() {
+        assert!(
+            Limits::default()
+                .check_imports(b"import \"tool/exec\"")
+                .is_ok()
+        );
+    }
+}
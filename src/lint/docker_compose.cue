@@ -0,0 +1,33 @@
+version?: string
+name?:    =~"^[a-z0-9][a-z0-9_-]*$"
+services: [=~"^[a-zA-Z0-9._-]+$"]: #Service
+networks?: [string]: null | {...}
+volumes?: [string]:  null | {...}
+secrets?: [string]: {...}
+configs?: [string]: {...}
+
+#Strings: string | [...string]
+
+#Service: {
+	image?: string & !=""
+	build?: string | {
+		context?:    string
+		dockerfile?: string
+		args?:       [...string] | {[string]: string | number | null}
+		...
+	}
+	command?:     #Strings
+	entrypoint?:  #Strings
+	environment?: [...string] | {[string]: string | number | bool | null}
+	"env_file"?:  #Strings
+	ports?: [...(string | int | {...})]
+	expose?: [...(string | int)]
+	volumes?: [...(string | {...})]
+	"depends_on"?: [...string] | {[string]: {
+		condition?: "service_started" | "service_healthy" | "service_completed_successfully"
+		...
+	}}
+	restart?: "no" | "always" | "on-failure" | "unless-stopped" | =~"^on-failure:[0-9]+$"
+	networks?: [...string] | {[string]: null | {...}}
+	...
+}
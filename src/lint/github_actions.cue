@@ -0,0 +1,43 @@
+#Env: [string]: string | number | bool
+
+name?:       string
+"run-name"?: string
+on:          string | [...string] | {[string]: _}
+env?:        #Env
+permissions?: "read-all" | "write-all" | {[string]: "read" | "write" | "none"}
+concurrency?: string | {
+	group:                 string
+	"cancel-in-progress"?: bool | string
+}
+defaults?: run?: {
+	shell?:               string
+	"working-directory"?: string
+}
+jobs: [=~"^[A-Za-z_][A-Za-z0-9_-]*$"]: #Job
+
+#Job: {
+	name?:                string
+	"runs-on"?:           string | [...string] | {...}
+	needs?:               string | [...string]
+	"if"?:                string | bool
+	env?:                 #Env
+	"timeout-minutes"?:   number | string
+	"continue-on-error"?: bool | string
+	steps?: [...#Step]
+	uses?: string
+	...
+}
+
+#Step: {
+	id?:                  string
+	name?:                string
+	"if"?:                string | bool
+	uses?:                string
+	run?:                 string
+	shell?:               string
+	with?:                #Env
+	env?:                 #Env
+	"timeout-minutes"?:   number | string
+	"continue-on-error"?: bool | string
+	...
+}
@@ -0,0 +1,27 @@
+#Name: =~"^[a-z0-9]([-a-z0-9.]*[a-z0-9])?$"
+
+apiVersion: string & !=""
+kind:       string & !=""
+metadata: {
+	name:       #Name
+	namespace?: #Name
+	labels?: [string]:      string
+	annotations?: [string]: string
+}
+
+if kind == "Deployment" {
+	spec: {
+		replicas?: int & >=0
+		selector: matchLabels: [string]: string
+		template: {
+			metadata: labels: [string]: string
+			spec: containers: [#Container, ...#Container]
+		}
+	}
+}
+
+#Container: {
+	name:  #Name
+	image: string & !=""
+	...
+}
@@ -0,0 +1,98 @@
+//! Ready-made schemas for common configuration file types.
+//!
+//! Each [`Preset`] embeds a CUE schema for a well-known format, so files of
+//! that type can be validated without writing a schema first:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! let ctx = cue_rs::Ctx::new()?;
+//! let preset = cue_rs::lint::preset("docker-compose").unwrap();
+//! preset.check_yaml(&ctx, "services:\n  web:\n    image: nginx\n")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The schemas check structure and the most common mistakes, and leave
+//! fields they do not know about open.
+
+use crate::{Ctx, Value, error::Error};
+
+/// A built-in lint preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preset {
+    /// Kubernetes object manifests.
+    Kubernetes,
+    /// GitHub Actions workflow files.
+    GithubActions,
+    /// Docker Compose files.
+    DockerCompose,
+}
+
+impl std::fmt::Display for Preset {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Preset {
+    /// Every preset, in declaration order.
+    pub const ALL: [Self; 3] = [Self::Kubernetes, Self::GithubActions, Self::DockerCompose];
+
+    /// Returns the name the preset is looked up by with [`preset`].
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Kubernetes => "kubernetes",
+            Self::GithubActions => "github-actions",
+            Self::DockerCompose => "docker-compose",
+        }
+    }
+
+    /// Returns the CUE source of the preset's schema.
+    #[must_use]
+    pub fn schema(self) -> &'static str {
+        match self {
+            Self::Kubernetes => include_str!("kubernetes.cue"),
+            Self::GithubActions => include_str!("github_actions.cue"),
+            Self::DockerCompose => include_str!("docker_compose.cue"),
+        }
+    }
+
+    /// Validates `document` against the preset's schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the document does not satisfy the schema.
+    pub fn check(
+        self,
+        ctx: &Ctx,
+        document: &Value,
+    ) -> Result<(), Error> {
+        let schema = Value::compile_string(ctx, self.schema())?;
+        Value::unify(&schema, document).is_valid()
+    }
+
+    /// Decodes a YAML document and validates it against the preset's schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the document cannot be decoded or does not
+    /// satisfy the schema, or [`Error::LimitExceeded`] if it is larger than
+    /// the context's source size limit.
+    pub fn check_yaml(
+        self,
+        ctx: &Ctx,
+        yaml: &str,
+    ) -> Result<(), Error> {
+        self.check(ctx, &Value::compile_yaml(ctx, yaml)?)
+    }
+}
+
+/// Returns the preset called `name` (e.g. `"github-actions"`), if any.
+#[must_use]
+pub fn preset(name: &str) -> Option<Preset> {
+    Preset::ALL.into_iter().find(|p| p.name() == name)
+}
@@ -0,0 +1,683 @@
+//! Options applied when loading CUE source, mirroring flags of the `cue`
+//! command line tool.
+//!
+//! [`LoadOptions::with_tag`] is the equivalent of `cue -t key=value`: fields
+//! marked with `@tag(key)` are unified with the injected value.
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! use cue_rs::{Ctx, load::LoadOptions};
+//!
+//! let ctx = cue_rs::Ctx::new()?;
+//! let config = LoadOptions::new()
+//!     .with_tag("env", "prod")
+//!     .with_tag("replicas", "3")
+//!     .compile(
+//!         &ctx,
+//!         r#"
+//!         env:      *"dev" | "staging" | "prod" @tag(env)
+//!         replicas: int | *1 @tag(replicas,type=int)
+//!         "#,
+//!     )?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! libcue compiles source without the loader that processes tags, so they
+//! are injected by rewriting `field: expr @tag(key)` into
+//! `field: (expr) & value @tag(key)` before compiling.  Error positions stay
+//! on the same line.  The `short` and `var` tag options are not supported.
+
+use std::{iter::Peekable, str::Chars};
+
+use crate::{Ctx, Value, error::Error, report::json_string};
+
+/// Options for compiling CUE source.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Tag values injected into `@tag` attributes, by tag name.
+    tags: Vec<(String, String)>,
+}
+
+impl LoadOptions {
+    /// Creates options with no tags.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Injects `value` into the fields marked `@tag(name)`, like
+    /// `cue -t name=value`.
+    ///
+    /// The value is a string unless the attribute sets `type=int`,
+    /// `type=number` or `type=bool`.
+    #[must_use]
+    pub fn with_tag(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.tags.push((name.into(), value.into()));
+        self
+    }
+
+    /// Injects each `(name, value)` pair, see [`LoadOptions::with_tag`].
+    #[must_use]
+    pub fn with_tags(
+        self,
+        tags: &[(&str, &str)],
+    ) -> Self {
+        tags.iter().fold(self, |options, (name, value)| {
+            options.with_tag(*name, *value)
+        })
+    }
+
+    /// Compiles `src` in `ctx` with these options applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidTag`] if a tag is not used by any field or its
+    /// value does not match the attribute's type, otherwise the same errors
+    /// as [`Value::compile_string`].
+    pub fn compile(
+        &self,
+        ctx: &Ctx,
+        src: &str,
+    ) -> Result<Value, Error> {
+        if self.tags.is_empty() {
+            return Value::compile_string(ctx, src);
+        }
+        let mut used = vec![false; self.tags.len()];
+        let src = inject_tags(src, &self.tags, &mut used)?;
+        check_used(&self.tags, &used)?;
+        Value::compile_string(ctx, &src)
+    }
+
+    /// Compiles each of `srcs` in `ctx` with these options applied, each tag
+    /// having to be used by a field of any of them.
+    #[cfg(any(feature = "figment", feature = "config"))]
+    pub(crate) fn compile_each(
+        &self,
+        ctx: &Ctx,
+        srcs: &[&str],
+    ) -> Result<Vec<Value>, Error> {
+        let mut used = vec![false; self.tags.len()];
+        let srcs = srcs
+            .iter()
+            .map(|src| inject_tags(src, &self.tags, &mut used))
+            .collect::<Result<Vec<_>, _>>()?;
+        check_used(&self.tags, &used)?;
+        srcs.iter()
+            .map(|src| Value::compile_string(ctx, src))
+            .collect()
+    }
+
+    /// Reads and compiles the CUE file at `path`, or the package formed by
+    /// the `.cue` files of the directory `path`, with these options applied.
+    ///
+    /// The files of a package are merged into a single source, so their
+    /// declarations may refer to each other.  Files whose name starts with
+    /// `_` or `.` are ignored, as by the `cue` tool.  Error positions in a
+    /// package refer to lines of the merged source, not of its files.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if `path` cannot be read, its files belong to
+    /// different packages or bind an import name to different packages,
+    /// otherwise the same errors as
+    /// [`LoadOptions::compile`].
+    pub fn load(
+        &self,
+        ctx: &Ctx,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Value, Error> {
+        self.compile(ctx, &read_source(path.as_ref())?)
+    }
+}
+
+/// Reads the CUE source at `path`: a single file, or the package formed by
+/// the `.cue` files of a directory, merged by [`merge_package`] in file name
+/// order.
+pub(crate) fn read_source(path: &std::path::Path) -> Result<String, Error> {
+    let io = |path: &std::path::Path| {
+        let path = path.to_path_buf();
+        move |source| Error::Io { path, source }
+    };
+    if !path.is_dir() {
+        return std::fs::read_to_string(path).map_err(io(path));
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path).map_err(io(path))? {
+        let file = entry.map_err(io(path))?.path();
+        let name = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        if file.extension().is_some_and(|ext| ext == "cue")
+            && !name.starts_with(['_', '.'])
+            && file.is_file()
+        {
+            let src = std::fs::read_to_string(&file).map_err(io(&file))?;
+            files.push((file, src));
+        }
+    }
+    files.sort();
+    merge_package(
+        &files
+            .iter()
+            .map(|(_, src)| src.as_str())
+            .collect::<Vec<_>>(),
+    )
+    .map_err(|reason| {
+        Error::Io {
+            path: path.to_path_buf(),
+            source: std::io::Error::new(std::io::ErrorKind::InvalidData, reason),
+        }
+    })
+}
+
+/// Merges the files of a CUE package into a single source, so references
+/// between them resolve as in a package.
+///
+/// libcue compiles one source at a time, so the package and import clauses
+/// of the files are hoisted (imports de-duplicated) and their declarations
+/// concatenated.  Imports are file-scoped in CUE, so files binding the same
+/// name to different packages cannot be merged and are rejected.
+fn merge_package(sources: &[&str]) -> Result<String, String> {
+    let mut package: Option<&str> = None;
+    let mut imports: Vec<&str> = Vec::new();
+    let mut bindings: Vec<(&str, &str)> = Vec::new();
+    let mut bodies = Vec::new();
+    for src in sources {
+        let (name, specs, body) = split_header(src);
+        match (package, name) {
+            (Some(a), Some(b)) if a != b => return Err(format!("found packages {a} and {b}")),
+            (None, Some(_)) => package = name,
+            _ => {},
+        }
+        for spec in specs {
+            let (name, path) = import_binding(spec);
+            match bindings.iter().find(|(bound, _)| *bound == name) {
+                Some((_, other)) if *other != path => {
+                    return Err(format!("import name {name} bound to {other} and {path}"));
+                },
+                Some(_) => {},
+                None => {
+                    bindings.push((name, path));
+                    imports.push(spec);
+                },
+            }
+        }
+        bodies.push(body);
+    }
+    let mut out = String::new();
+    if let Some(package) = package {
+        out.push_str("package ");
+        out.push_str(package);
+        out.push_str("\n\n");
+    }
+    for spec in &imports {
+        out.push_str("import ");
+        out.push_str(spec);
+        out.push('\n');
+    }
+    if !imports.is_empty() {
+        out.push('\n');
+    }
+    out.push_str(&bodies.join("\n"));
+    Ok(out)
+}
+
+/// Returns the name an import spec binds and the package path it imports:
+/// the alias if any, else the package name qualifying the path, else the
+/// last path element.
+fn import_binding(spec: &str) -> (&str, &str) {
+    let (alias, path) = match spec.split_once('"') {
+        Some((alias, path)) => (alias.trim(), path.trim_end().trim_end_matches('"')),
+        None => ("", spec),
+    };
+    let (path, qualifier) = path.split_once(':').unwrap_or((path, ""));
+    let name = if !alias.is_empty() {
+        alias
+    } else if !qualifier.is_empty() {
+        qualifier
+    } else {
+        path.rsplit('/').next().unwrap_or(path)
+    };
+    (name, path)
+}
+
+/// Splits a CUE file into its package name, its import specs and the rest of
+/// the file.
+fn split_header(src: &str) -> (Option<&str>, Vec<&str>, &str) {
+    let mut package = None;
+    let mut specs = Vec::new();
+    let mut in_block = false;
+    let mut rest = src;
+    while !rest.is_empty() {
+        let (line, next) = rest.split_once('\n').unwrap_or((rest, ""));
+        let trimmed = line.trim();
+        let import = trimmed
+            .strip_prefix("import")
+            .filter(|spec| spec.starts_with([' ', '\t', '(', '"']))
+            .map(str::trim);
+        if in_block {
+            if trimmed == ")" {
+                in_block = false;
+            } else if !trimmed.is_empty() && !trimmed.starts_with("//") {
+                specs.push(trimmed);
+            }
+        } else if trimmed.is_empty() || trimmed.starts_with("//") {
+        } else if let Some(name) = trimmed.strip_prefix("package ") {
+            package = Some(name.trim());
+        } else if let Some(spec) = import {
+            match spec.strip_prefix('(') {
+                Some("") => in_block = true,
+                Some(inner) => specs.push(inner.trim_end_matches(')').trim()),
+                None => specs.push(spec),
+            }
+        } else {
+            break;
+        }
+        rest = next;
+    }
+    (package, specs, rest)
+}
+
+/// Rewrites the fields of `src` marked with the `tags` to unify with their
+/// values, flagging the tags found in `used`.
+fn inject_tags(
+    src: &str,
+    tags: &[(String, String)],
+    used: &mut [bool],
+) -> Result<String, Error> {
+    let mut out = String::with_capacity(src.len());
+    // Output offset where the value of the innermost field starts, per
+    // bracket nesting level.
+    let mut values: Vec<Option<usize>> = vec![None];
+    let mut last = ' ';
+    let mut chars = src.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                out.push(c);
+                while let Some(c) = chars.next_if(|&c| c != '\n') {
+                    out.push(c);
+                }
+                continue;
+            },
+            '"' | '\'' | '#' if starts_string(c, &chars) => {
+                copy_string(c, &mut chars, &mut out);
+                last = '"';
+                continue;
+            },
+            '@' if chars.clone().take(4).eq("tag(".chars()) => {
+                let attr = copy_attribute(&mut chars);
+                let (name, kind) = parse_tag(&attr);
+                if let Some(start) = values.last().copied().flatten()
+                    && let Some((_, value)) = tags.iter().rfind(|(n, _)| n == name)
+                {
+                    let literal = tag_literal(name, kind, value)?;
+                    let pad = out
+                        .get(start..)
+                        .map_or(0, |v| v.len().saturating_sub(v.trim_start().len()));
+                    out.insert(start.saturating_add(pad), '(');
+                    out.truncate(out.trim_end().len());
+                    out.push_str(") & ");
+                    out.push_str(&literal);
+                    out.push(' ');
+                    for (used, (n, _)) in used.iter_mut().zip(tags) {
+                        *used |= n == name;
+                    }
+                }
+                out.push('@');
+                out.push_str(&attr);
+                last = ')';
+                continue;
+            },
+            '{' | '[' | '(' => values.push(None),
+            '}' | ']' | ')' => {
+                values.pop();
+                if values.is_empty() {
+                    values.push(None);
+                }
+            },
+            ':' => {
+                out.push(c);
+                if let Some(value) = values.last_mut() {
+                    *value = Some(out.len());
+                }
+                last = c;
+                continue;
+            },
+            ',' => {
+                if let Some(value) = values.last_mut() {
+                    *value = None;
+                }
+            },
+            '\n' if ends_declaration(last) => {
+                if let Some(value) = values.last_mut() {
+                    *value = None;
+                }
+            },
+            _ => {},
+        }
+        out.push(c);
+        if !c.is_whitespace() {
+            last = c;
+        }
+    }
+    Ok(out)
+}
+
+/// Fails with [`Error::InvalidTag`] for the first of the `tags` not flagged
+/// in `used`.
+fn check_used(
+    tags: &[(String, String)],
+    used: &[bool],
+) -> Result<(), Error> {
+    match tags
+        .iter()
+        .zip(used)
+        .find_map(|(tag, used)| (!used).then_some(tag))
+    {
+        Some((name, _)) => {
+            Err(Error::InvalidTag {
+                name: name.clone(),
+                reason: "not used by any field".to_string(),
+            })
+        },
+        None => Ok(()),
+    }
+}
+
+/// Returns `true` if a newline after `last` ends a declaration, following
+/// CUE's automatic comma insertion.
+fn ends_declaration(last: char) -> bool {
+    last.is_alphanumeric() || matches!(last, '_' | '$' | '"' | ')' | ']' | '}')
+}
+
+/// Returns `true` if `c` followed by `rest` starts a string literal.
+fn starts_string(
+    c: char,
+    rest: &Peekable<Chars<'_>>,
+) -> bool {
+    if c != '#' {
+        return true;
+    }
+    let mut rest = rest.clone();
+    while rest.next_if_eq(&'#').is_some() {}
+    matches!(rest.next(), Some('"' | '\''))
+}
+
+/// Copies a string literal starting with `first` (a quote or the `#` of a
+/// raw string), including interpolations.
+fn copy_string(
+    first: char,
+    chars: &mut Peekable<Chars<'_>>,
+    out: &mut String,
+) {
+    let mut hashes = 0usize;
+    let mut quote = first;
+    out.push(first);
+    if first == '#' {
+        hashes = 1;
+        while let Some(c) = chars.next_if_eq(&'#') {
+            hashes = hashes.saturating_add(1);
+            out.push(c);
+        }
+        let Some(c) = chars.next() else { return };
+        quote = c;
+        out.push(c);
+    }
+    let mut rest = chars.clone();
+    let multiline = rest.next() == Some(quote) && rest.next() == Some(quote);
+    if multiline {
+        for _ in 0..2 {
+            out.extend(chars.next());
+        }
+    }
+    while let Some(c) = chars.next() {
+        out.push(c);
+        if c == '\\' && chars.clone().take(hashes).all(|c| c == '#') {
+            for _ in 0..hashes {
+                out.extend(chars.next());
+            }
+            match chars.next() {
+                Some('(') => {
+                    out.push('(');
+                    copy_interpolation(chars, out);
+                },
+                Some(c) => out.push(c),
+                None => return,
+            }
+        } else if c == quote {
+            let extra = if multiline { 2 } else { 0 };
+            let mut rest = chars.clone();
+            let quotes = rest.by_ref().take(extra).filter(|&c| c == quote).count();
+            let closing_hashes = rest.take(hashes).filter(|&c| c == '#').count();
+            if quotes == extra && closing_hashes == hashes {
+                for _ in 0..extra.saturating_add(hashes) {
+                    out.extend(chars.next());
+                }
+                return;
+            }
+        } else if c == '\n' && !multiline {
+            return;
+        }
+    }
+}
+
+/// Copies the expression of an interpolation whose `\(` was consumed, up to
+/// and including the closing parenthesis.
+fn copy_interpolation(
+    chars: &mut Peekable<Chars<'_>>,
+    out: &mut String,
+) {
+    let mut depth = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' | '#' if starts_string(c, chars) => {
+                copy_string(c, chars, out);
+                continue;
+            },
+            '(' => depth = depth.saturating_add(1),
+            ')' if depth == 0 => {
+                out.push(c);
+                return;
+            },
+            ')' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+        out.push(c);
+    }
+}
+
+/// Consumes an attribute whose `@` was consumed, returning its text from
+/// the name to the closing parenthesis.
+fn copy_attribute(chars: &mut Peekable<Chars<'_>>) -> String {
+    let mut attr = String::new();
+    let mut depth = 0usize;
+    for c in chars.by_ref() {
+        attr.push(c);
+        match c {
+            '(' => depth = depth.saturating_add(1),
+            ')' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    break;
+                }
+            },
+            _ => {},
+        }
+    }
+    attr
+}
+
+/// Returns the name and value type of a `tag(...)` attribute.
+fn parse_tag(attr: &str) -> (&str, &str) {
+    let args = attr
+        .strip_prefix("tag(")
+        .and_then(|args| args.strip_suffix(')'))
+        .unwrap_or_default();
+    let mut args = args.split(',').map(str::trim);
+    let name = args.next().unwrap_or_default();
+    let kind = args
+        .find_map(|arg| arg.strip_prefix("type=").map(str::trim))
+        .unwrap_or("string");
+    (name, kind)
+}
+
+/// Returns the CUE literal for `value` of the tag `name` of type `kind`.
+fn tag_literal(
+    name: &str,
+    kind: &str,
+    value: &str,
+) -> Result<String, Error> {
+    let invalid = |reason: String| {
+        Error::InvalidTag {
+            name: name.to_string(),
+            reason,
+        }
+    };
+    let valid = match kind {
+        "string" => return Ok(json_string(value)),
+        "int" => {
+            let digits = value.strip_prefix('-').unwrap_or(value);
+            !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+        },
+        "number" => {
+            value.parse::<f64>().is_ok_and(f64::is_finite)
+                && value.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+        },
+        "bool" => value == "true" || value == "false",
+        other => return Err(invalid(format!("unsupported type {other:?}"))),
+    };
+    if !valid {
+        return Err(invalid(format!("{value:?} is not {}", article(kind))));
+    }
+    Ok(value.to_string())
+}
+
+/// Returns `kind` with its indefinite article.
+fn article(kind: &str) -> String {
+    if kind.starts_with(['a', 'e', 'i', 'o', 'u']) {
+        format!("an {kind}")
+    } else {
+        format!("a {kind}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{check_used, import_binding, inject_tags, merge_package};
+    use crate::{Ctx, error::Error};
+
+    fn inject(
+        src: &str,
+        tags: &[(&str, &str)],
+    ) -> Result<String, String> {
+        let tags: Vec<(String, String)> = tags
+            .iter()
+            .map(|(n, v)| ((*n).to_string(), (*v).to_string()))
+            .collect();
+        let mut used = vec![false; tags.len()];
+        inject_tags(src, &tags, &mut used)
+            .and_then(|src| check_used(&tags, &used).map(|()| src))
+            .map_err(|err| err.to_string())
+    }
+
+    #[test_case(r#"env: *"dev" | string @tag(env)"#, &[("env", "prod")]
+        => Ok(r#"env: (*"dev" | string) & "prod" @tag(env)"#.to_string()); "string")]
+    #[test_case("n: int @tag(n, type=int)", &[("n", "-3")]
+        => Ok("n: (int) & -3 @tag(n, type=int)".to_string()); "int")]
+    #[test_case("a: { b: 1, c: [1, 2] @tag(c,type=bool) }", &[("c", "true")]
+        => Ok("a: { b: 1, c: ([1, 2]) & true @tag(c,type=bool) }".to_string()); "nested")]
+    #[test_case("a: b: string @tag(x)", &[("x", "v")]
+        => Ok(r#"a: b: (string) & "v" @tag(x)"#.to_string()); "shorthand")]
+    #[test_case("a: string @tag(x)\nb: string @tag(y)", &[("x", "1")]
+        => Ok("a: (string) & \"1\" @tag(x)\nb: string @tag(y)".to_string()); "unset tag")]
+    #[test_case(r#"s: "@tag(x): \("a:" + "b")" @tag(x)"#, &[("x", "v")]
+        => Ok(r#"s: ("@tag(x): \("a:" + "b")") & "v" @tag(x)"#.to_string()); "string with attribute")]
+    #[test_case("s: #\"a \"@tag(x)\" b\"# @tag(x)", &[("x", "v")]
+        => Ok("s: (#\"a \"@tag(x)\" b\"#) & \"v\" @tag(x)".to_string()); "raw string")]
+    #[test_case("s: \"\"\"\n\t@tag(x)\n\t\"\"\" @tag(x)", &[("x", "v")]
+        => Ok("s: (\"\"\"\n\t@tag(x)\n\t\"\"\") & \"v\" @tag(x)".to_string()); "multiline string")]
+    #[test_case("// a: 1 @tag(x)\na: int @tag(x, type=int)", &[("x", "1")]
+        => Ok("// a: 1 @tag(x)\na: (int) & 1 @tag(x, type=int)".to_string()); "comment")]
+    #[test_case("a: 1\n@tag(x)", &[("x", "v")]
+        => Err("invalid tag x: not used by any field".to_string()); "declaration attribute")]
+    #[test_case("a: int @tag(x)", &[("y", "v")]
+        => Err("invalid tag y: not used by any field".to_string()); "unused")]
+    #[test_case("a: int @tag(x,type=int)", &[("x", "1.5")]
+        => Err(r#"invalid tag x: "1.5" is not an int"#.to_string()); "bad int")]
+    #[test_case("a: bool @tag(x,type=bool)", &[("x", "yes")]
+        => Err(r#"invalid tag x: "yes" is not a bool"#.to_string()); "bad bool")]
+    fn test_inject_tags(
+        src: &str,
+        tags: &[(&str, &str)],
+    ) -> Result<String, String> {
+        inject(src, tags)
+    }
+
+    #[test]
+    fn test_compile() {
+        let ctx = Ctx::new().unwrap();
+        let v = super::LoadOptions::new()
+            .with_tags(&[("env", "prod"), ("replicas", "3")])
+            .compile(
+                &ctx,
+                r#"
+                env:      *"dev" | "staging" | "prod" @tag(env)
+                replicas: int | *1 @tag(replicas,type=int)
+                "#,
+            )
+            .unwrap();
+        assert_eq!(v.lookup("env").unwrap().to_string().unwrap(), "prod");
+        assert_eq!(v.lookup("replicas").unwrap().to_int64().unwrap(), 3);
+
+        let err = super::LoadOptions::new()
+            .with_tag("env", "qa")
+            .compile(&ctx, r#"env: "dev" | "prod" @tag(env)"#)
+            .and_then(|v| v.is_valid());
+        assert!(matches!(err, Err(Error::Cue(_))));
+    }
+
+    #[test]
+    fn test_merge_package() {
+        let a = "// Package config.\npackage config\n\nimport \"strings\"\n\nname: strings.ToUpper(n)\n";
+        let b = "package config\n\nimport (\n\t\"strings\"\n\tl \"list\"\n)\n\nn: \"x\"\nimports: l.Sum([1])\n";
+        assert_eq!(
+            merge_package(&[a, b]).unwrap(),
+            "package config\n\nimport \"strings\"\nimport l \"list\"\n\nname: \
+             strings.ToUpper(n)\n\nn: \"x\"\nimports: l.Sum([1])\n"
+        );
+        assert_eq!(
+            merge_package(&["package a\nx: 1", "package b\ny: 1"]),
+            Err("found packages a and b".to_string())
+        );
+        assert_eq!(merge_package(&["x: 1", "y: 2"]).unwrap(), "x: 1\ny: 2");
+        assert_eq!(
+            merge_package(&[
+                "import \"strings\"\nx: 1",
+                "import strings \"strings\"\ny: 1"
+            ])
+            .unwrap(),
+            "import \"strings\"\n\nx: 1\ny: 1"
+        );
+        assert_eq!(
+            merge_package(&["import l \"list\"\nx: 1", "import l \"strings\"\ny: 1"]),
+            Err("import name l bound to list and strings".to_string())
+        );
+    }
+
+    #[test_case(r#""strings""# => ("strings", "strings"); "plain")]
+    #[test_case(r#"s "strings""# => ("s", "strings"); "alias")]
+    #[test_case(r#""example.com/pkg/v1:pkg""# => ("pkg", "example.com/pkg/v1"); "qualified")]
+    #[test_case(r#""encoding/json""# => ("json", "encoding/json"); "nested")]
+    fn test_import_binding(spec: &str) -> (&str, &str) {
+        import_binding(spec)
+    }
+}
@@ -0,0 +1,625 @@
+//! Markdown documentation generated from CUE schemas.
+//!
+//! [`render`] documents the fields of a CUE file in a table and every
+//! definition in a section of its own, with the type and constraints of each
+//! field as written, whether data must set it, its default and its doc
+//! comment:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! use cue_rs::{Ctx, markdown};
+//!
+//! let ctx = Ctx::new()?;
+//! let docs = markdown::render(
+//!     &ctx,
+//!     r#"
+//! package service
+//!
+//! // A deployed service.
+//! #Service: {
+//!     // Name of the service.
+//!     name!: string & =~"^[a-z-]+$"
+//!     port?: int & >0 & <65536 | *8080 // Listening port.
+//!     tls?: #TLS
+//! }
+//!
+//! #TLS: { cert: string, key: string }
+//! "#,
+//! )?;
+//! std::fs::write("SERVICE.md", docs).ok();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Evaluation drops optional fields, comments and the constraints as
+//! written, so the documentation is generated from the syntax tree of the
+//! source, see [`ast`].  The source is compiled first, so only
+//! valid schemas are documented.  Fields of nested struct literals are
+//! listed with their dotted path, e.g. `tls.cert`, and elements of lists of
+//! structs as `items[].name`.  Hidden fields, `let` declarations and
+//! comprehensions are left out.
+
+use std::fmt::Write as _;
+
+use crate::{
+    Ctx, Value,
+    ast::{
+        self, BinaryOp, Constraint, Decl, DeclKind, Expr, ExprKind, Field, Label, LitKind, UnaryOp,
+    },
+    error::Error,
+    load::read_source,
+};
+
+/// Options for rendering Markdown documentation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MarkdownOptions {
+    /// Title of the document, if set.
+    title: Option<String>,
+}
+
+impl MarkdownOptions {
+    /// Creates the default options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the title of the document, by default `Package <name>` or
+    /// `Schema` if the file has no package clause.
+    #[must_use]
+    pub fn with_title(
+        mut self,
+        title: impl Into<String>,
+    ) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Renders the documentation of the CUE source `src`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Value::compile_string`] and
+    /// [`Value::is_valid`] if `src` is not a valid schema, or
+    /// [`Error::Syntax`] if it cannot be parsed.
+    pub fn render(
+        &self,
+        ctx: &Ctx,
+        src: &str,
+    ) -> Result<String, Error> {
+        Value::compile_string(ctx, src)?.is_valid()?;
+        self.render_source(src)
+    }
+
+    /// Reads and renders the documentation of the CUE file at `path`, or of
+    /// the package formed by the `.cue` files of the directory `path`, see
+    /// [`LoadOptions::load`](crate::load::LoadOptions::load).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if `path` cannot be read, otherwise the same
+    /// errors as [`MarkdownOptions::render`].
+    pub fn render_path(
+        &self,
+        ctx: &Ctx,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<String, Error> {
+        self.render(ctx, &read_source(path.as_ref())?)
+    }
+
+    /// Renders the documentation of `src` without compiling it.
+    fn render_source(
+        &self,
+        src: &str,
+    ) -> Result<String, Error> {
+        let file = ast::parse(src)?;
+        let doc = Doc { src };
+        let mut package = None;
+        let mut blocks = Vec::new();
+        let mut fields = Vec::new();
+        let mut definitions = Vec::new();
+        for decl in &file.decls {
+            match &decl.kind {
+                DeclKind::Package(name) => package = Some((&name.name, decl.doc_text())),
+                DeclKind::Field(f) if doc.is_definition(f) => {
+                    doc.collect_definitions(decl, f, "", &mut definitions);
+                },
+                DeclKind::Field(f) if !doc.is_hidden(f) => fields.push((decl, f)),
+                _ => {},
+            }
+        }
+        let names: Vec<&str> = definitions.iter().map(|(name, ..)| name.as_str()).collect();
+
+        let title = self.title.clone().unwrap_or_else(|| {
+            package.as_ref().map_or_else(
+                || "Schema".to_string(),
+                |(name, _)| format!("Package `{name}`"),
+            )
+        });
+        blocks.push(format!("# {title}"));
+        if let Some((_, package_doc)) = package
+            && !package_doc.is_empty()
+        {
+            blocks.push(package_doc);
+        }
+        if !fields.is_empty() {
+            blocks.push("## Fields".to_string());
+            let mut rows = Vec::new();
+            for (decl, f) in fields {
+                doc.collect_rows(decl, f, "", &mut rows);
+            }
+            blocks.push(doc.table(&rows, &names));
+        }
+        for (name, decl, f) in &definitions {
+            blocks.push(format!("## `{name}`"));
+            let definition_doc = decl.doc_text();
+            if !definition_doc.is_empty() {
+                blocks.push(definition_doc);
+            }
+            let Some(body) = body(&f.value) else {
+                let (ty, default) = doc.type_and_default(&f.value);
+                let mut block = format!("Type: {}", code(&ty, &names));
+                if let Some(default) = default {
+                    let _ = write!(block, "  \nDefault: `{default}`");
+                }
+                blocks.push(block);
+                continue;
+            };
+            let embeds: Vec<String> = body
+                .decls
+                .iter()
+                .filter_map(|d| {
+                    match &d.kind {
+                        DeclKind::Embed(expr) => Some(code(&doc.text(expr), &names)),
+                        _ => None,
+                    }
+                })
+                .collect();
+            if !embeds.is_empty() {
+                blocks.push(format!("Embeds {}.", embeds.join(", ")));
+            }
+            let mut rows = Vec::new();
+            for (child_decl, child) in body.fields() {
+                if !doc.is_definition(child) && !doc.is_hidden(child) {
+                    doc.collect_rows(
+                        child_decl,
+                        child,
+                        if body.list { "[]" } else { "" },
+                        &mut rows,
+                    );
+                }
+            }
+            if !rows.is_empty() {
+                blocks.push(doc.table(&rows, &names));
+            }
+        }
+        let mut out = blocks.join("\n\n");
+        out.push('\n');
+        Ok(out)
+    }
+}
+
+/// Renders the documentation of the CUE source `src` with the default
+/// [`MarkdownOptions`].
+///
+/// # Errors
+///
+/// Returns the same errors as [`MarkdownOptions::render`].
+pub fn render(
+    ctx: &Ctx,
+    src: &str,
+) -> Result<String, Error> {
+    MarkdownOptions::new().render(ctx, src)
+}
+
+/// A row of a field table: the path of the field, its declaration and the
+/// field.
+type Row<'a> = (String, &'a Decl, &'a Field);
+
+/// The declarations of a struct literal documented as the fields of a
+/// value.
+struct Body<'a> {
+    /// The declarations.
+    decls: &'a [Decl],
+    /// Whether the value is a list of such structs, `[...{ }]`.
+    list: bool,
+}
+
+impl<'a> Body<'a> {
+    /// Returns the fields of the struct, with their declarations.
+    fn fields(&self) -> impl Iterator<Item = (&'a Decl, &'a Field)> {
+        self.decls.iter().filter_map(|decl| {
+            match &decl.kind {
+                DeclKind::Field(f) => Some((decl, f)),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Returns the declarations of `value` if it is a struct literal, such as
+/// `{ a: int }` or `close({ a: int })`, or a list of them, `[...{ a: int }]`.
+fn body(value: &Expr) -> Option<Body<'_>> {
+    match &value.kind {
+        ExprKind::Struct(decls) => Some(Body { decls, list: false }),
+        ExprKind::Call { func, args } if matches!(&func.kind, ExprKind::Ident(name) if name == "close") =>
+        {
+            let [arg] = args.as_slice() else {
+                return None;
+            };
+            body(arg).filter(|body| !body.list)
+        },
+        ExprKind::List(elems) => {
+            let [
+                Expr {
+                    kind: ExprKind::Ellipsis(Some(element)),
+                    ..
+                },
+            ] = elems.as_slice()
+            else {
+                return None;
+            };
+            match &element.kind {
+                ExprKind::Struct(decls) => Some(Body { decls, list: true }),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+/// The source being documented, giving the text of its syntax tree.
+struct Doc<'s> {
+    /// The source.
+    src: &'s str,
+}
+
+impl<'s> Doc<'s> {
+    /// Returns the text of `expr` with its whitespace collapsed into single
+    /// spaces.
+    fn text(
+        &self,
+        expr: &Expr,
+    ) -> String {
+        normalize(expr.span.text(self.src))
+    }
+
+    /// Returns the label of `f` as written, e.g. `name`, `"x-y"` or
+    /// `[string]`.
+    fn label(
+        &self,
+        f: &Field,
+    ) -> &'s str {
+        f.label.span().text(self.src)
+    }
+
+    /// Returns `true` if `f` declares a definition, such as `#Service`.
+    fn is_definition(
+        &self,
+        f: &Field,
+    ) -> bool {
+        self.label(f).starts_with('#')
+    }
+
+    /// Returns `true` if `f` declares a hidden field or definition, such as
+    /// `_cache`.
+    fn is_hidden(
+        &self,
+        f: &Field,
+    ) -> bool {
+        self.label(f).starts_with('_')
+    }
+
+    /// Adds the definition `f` and the definitions nested in it to
+    /// `definitions`, named by their path below `parent`.
+    fn collect_definitions<'a>(
+        &self,
+        decl: &'a Decl,
+        f: &'a Field,
+        parent: &str,
+        definitions: &mut Vec<(String, &'a Decl, &'a Field)>,
+    ) {
+        let name = if parent.is_empty() {
+            self.label(f).to_string()
+        } else {
+            format!("{parent}.{}", self.label(f))
+        };
+        definitions.push((name.clone(), decl, f));
+        for (child_decl, child) in body(&f.value).iter().flat_map(Body::fields) {
+            if self.is_definition(child) {
+                self.collect_definitions(child_decl, child, &name, definitions);
+            }
+        }
+    }
+
+    /// Adds the row of `f`, a field below the path `parent`, and the rows of
+    /// the fields of its struct literal to `rows`.
+    fn collect_rows<'a>(
+        &self,
+        decl: &'a Decl,
+        f: &'a Field,
+        parent: &str,
+        rows: &mut Vec<Row<'a>>,
+    ) {
+        let path = match (parent, &f.label) {
+            ("", _) => self.label(f).to_string(),
+            (_, Label::Pattern { .. }) => format!("{parent}{}", self.label(f)),
+            _ => format!("{parent}.{}", self.label(f)),
+        };
+        rows.push((path.clone(), decl, f));
+        let Some(body) = body(&f.value) else {
+            return;
+        };
+        let prefix = if body.list { format!("{path}[]") } else { path };
+        for (child_decl, child) in body.fields() {
+            if !self.is_definition(child) && !self.is_hidden(child) {
+                self.collect_rows(child_decl, child, &prefix, rows);
+            }
+        }
+    }
+
+    /// Renders `rows` as a Markdown table, linking references to
+    /// `definitions`.
+    fn table(
+        &self,
+        rows: &[Row<'_>],
+        definitions: &[&str],
+    ) -> String {
+        let mut out = String::from(
+            "| Field | Type | Required | Default | Description |\n|---|---|---|---|---|",
+        );
+        for (path, decl, f) in rows {
+            let body = body(&f.value);
+            let (ty, default) = match &body {
+                Some(body) if body.list => ("[...{...}]".to_string(), None),
+                Some(_) => ("{...}".to_string(), None),
+                None => self.type_and_default(&f.value),
+            };
+            let required = match (&f.label, f.constraint) {
+                (_, Constraint::Required) => true,
+                (Label::Pattern { .. }, _) | (_, Constraint::Optional) => false,
+                // Structs and lists are exported empty if data does not set
+                // them.
+                (_, Constraint::Regular) => {
+                    default.is_none()
+                        && body.is_none()
+                        && !is_literal(&f.value)
+                        && !matches!(f.value.kind, ExprKind::Struct(_) | ExprKind::List(_))
+                },
+            };
+            let _ = write!(
+                out,
+                "\n| `{}` | {} | {} | {} | {} |",
+                cell(path),
+                code(&ty, definitions),
+                if required { "yes" } else { "no" },
+                default.map_or_else(String::new, |d| format!("`{}`", cell(&d))),
+                cell(&decl.doc_text().replace('\n', " ")),
+            );
+        }
+        out
+    }
+
+    /// Splits a value into its type and its default, e.g. `int` and `8080`
+    /// for `int | *8080`.
+    ///
+    /// A default among enumerated values, as in `"a" | *"b"`, stays part
+    /// of the type.
+    fn type_and_default(
+        &self,
+        value: &Expr,
+    ) -> (String, Option<String>) {
+        let mut branches = Vec::new();
+        disjuncts(value, &mut branches);
+        let mut defaults = branches.iter().filter_map(|b| default_of(b));
+        let default = defaults.next().filter(|_| defaults.next().is_none());
+        let Some(default) = default else {
+            return (self.text(value), None);
+        };
+        let enumeration = branches
+            .iter()
+            .all(|b| is_literal(default_of(b).unwrap_or(b)));
+        let ty: Vec<String> = branches
+            .iter()
+            .filter_map(|b| {
+                match default_of(b) {
+                    Some(b) if enumeration => Some(self.text(b)),
+                    Some(_) => None,
+                    None => Some(self.text(b)),
+                }
+            })
+            .collect();
+        let ty = if ty.is_empty() {
+            self.text(default)
+        } else {
+            ty.join(" | ")
+        };
+        (ty, Some(self.text(default)))
+    }
+}
+
+/// Adds the branches of the disjunction `expr` to `branches`, or `expr`
+/// itself if it is not a disjunction.
+fn disjuncts<'a>(
+    expr: &'a Expr,
+    branches: &mut Vec<&'a Expr>,
+) {
+    match &expr.kind {
+        ExprKind::Binary {
+            op: BinaryOp::Or,
+            lhs,
+            rhs,
+        } => {
+            disjuncts(lhs, branches);
+            disjuncts(rhs, branches);
+        },
+        _ => branches.push(expr),
+    }
+}
+
+/// Returns the marked value of a default branch, `1` for `*1`.
+fn default_of(branch: &Expr) -> Option<&Expr> {
+    match &branch.kind {
+        ExprKind::Unary {
+            op: UnaryOp::Default,
+            expr,
+        } => Some(expr),
+        _ => None,
+    }
+}
+
+/// Renders `ty` as inline code, linked to the section of the definition it
+/// refers to if it refers to exactly one of `definitions`.
+fn code(
+    ty: &str,
+    definitions: &[&str],
+) -> String {
+    let mut referenced = ty
+        .split(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | '#' | '.')))
+        .filter(|token| definitions.contains(token));
+    let span = format!("`{}`", cell(ty));
+    match (referenced.next(), referenced.next()) {
+        (Some(name), None) => format!("[{span}](#{})", anchor(name)),
+        _ => span,
+    }
+}
+
+/// Returns the anchor GitHub generates for the heading of the definition
+/// `name`.
+fn anchor(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Escapes the pipes of `text` for a table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', r"\|")
+}
+
+/// Collapses the whitespace of `value` into single spaces.
+fn normalize(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Returns `true` if `value` is a literal such as `"a"`, `-1` or `null`.
+fn is_literal(value: &Expr) -> bool {
+    match &value.kind {
+        ExprKind::Lit(_) => true,
+        ExprKind::Unary {
+            op: UnaryOp::Minus | UnaryOp::Plus,
+            expr,
+        } => matches!(expr.kind, ExprKind::Lit(LitKind::Int | LitKind::Float)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{Doc, MarkdownOptions};
+    use crate::{Ctx, ast};
+
+    #[test_case("int"                           => ("int".to_string(), None);                                    "plain")]
+    #[test_case("int | *8080"                   => ("int".to_string(), Some("8080".to_string()));               "default")]
+    #[test_case(r#""a" | "b" | *"c""#           => (r#""a" | "b" | "c""#.to_string(), Some(r#""c""#.to_string())); "enumeration")]
+    #[test_case("int &\n\t>0 |\n\t*1"           => ("int & >0".to_string(), Some("1".to_string()));              "multiline")]
+    #[test_case("*1 | *2 | int"                 => ("*1 | *2 | int".to_string(), None);                          "ambiguous")]
+    fn test_type_and_default(value: &str) -> (String, Option<String>) {
+        Doc { src: value }.type_and_default(&ast::parse_expr(value).unwrap())
+    }
+
+    const SRC: &str = r#"
+// Package service describes deployed services.
+package service
+
+// Region of the deployment.
+region: "eu" | *"us"
+
+// A deployed service.
+#Service: {
+    #Base
+
+    // Name of the service.
+    name!: string & =~"^[a-z-]+$"
+    port?: int & >0 & <65536 | *8080 // Listening port.
+    tls?: #TLS
+    env: [string]: string
+    replicas: [...{ zone: string, count: int | *1 }]
+    _cache: {}
+}
+
+#Base: { kind: "service" }
+
+#TLS: {
+    cert: string
+    key:  string
+}
+
+#Port: int & >0
+"#;
+
+    const EXPECTED: &str = r#"# Package `service`
+
+Package service describes deployed services.
+
+## Fields
+
+| Field | Type | Required | Default | Description |
+|---|---|---|---|---|
+| `region` | `"eu" \| "us"` | no | `"us"` | Region of the deployment. |
+
+## `#Service`
+
+A deployed service.
+
+Embeds [`#Base`](#base).
+
+| Field | Type | Required | Default | Description |
+|---|---|---|---|---|
+| `name` | `string & =~"^[a-z-]+$"` | yes |  | Name of the service. |
+| `port` | `int & >0 & <65536` | no | `8080` | Listening port. |
+| `tls` | [`#TLS`](#tls) | no |  |  |
+| `env` | `{...}` | no |  |  |
+| `env[string]` | `string` | no |  |  |
+| `replicas` | `[...{...}]` | no |  |  |
+| `replicas[].zone` | `string` | yes |  |  |
+| `replicas[].count` | `int` | no | `1` |  |
+
+## `#Base`
+
+| Field | Type | Required | Default | Description |
+|---|---|---|---|---|
+| `kind` | `"service"` | no |  |  |
+
+## `#TLS`
+
+| Field | Type | Required | Default | Description |
+|---|---|---|---|---|
+| `cert` | `string` | yes |  |  |
+| `key` | `string` | yes |  |  |
+
+## `#Port`
+
+Type: `int & >0`
+"#;
+
+    #[test]
+    fn test_render_source() {
+        assert_eq!(MarkdownOptions::new().render_source(SRC).unwrap(), EXPECTED);
+        let titled = MarkdownOptions::new()
+            .with_title("Services")
+            .render_source("a: int\n")
+            .unwrap();
+        assert!(titled.starts_with("# Services\n\n## Fields\n"));
+    }
+
+    #[test]
+    fn test_render() {
+        let ctx = Ctx::new().unwrap();
+        assert_eq!(super::render(&ctx, SRC).unwrap(), EXPECTED);
+        assert!(super::render(&ctx, "a: int & string & 1").is_err());
+    }
+}
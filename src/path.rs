@@ -0,0 +1,230 @@
+//! CUE paths used to look up values inside a [`Value`](crate::Value).
+
+use std::{ffi::CString, fmt::Write};
+
+use crate::error::Error;
+
+/// A CUE path such as `a.b[0]."c-d"` or `#Def.field`.
+///
+/// The path is converted to a C string once, on construction, so a [`Path`]
+/// built ahead of time can be reused for any number of lookups without
+/// further allocation.  Syntax is checked by libcue at lookup time.
+///
+/// The default path is empty and selects the root value.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Path(CString);
+
+impl Path {
+    /// Creates a new path from its CUE string form.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StringContainsNul`] if `path` contains interior nul
+    /// bytes.
+    pub fn new(path: &str) -> Result<Self, Error> {
+        CString::new(path)
+            .map(Self)
+            .map_err(Error::StringContainsNul)
+    }
+
+    /// Creates a path selecting the regular field called `name`.
+    ///
+    /// The name is quoted, so it may contain any characters, including ones
+    /// that have a meaning in paths such as `.` or a leading `#`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StringContainsNul`] if `name` contains nul bytes.
+    pub fn field(name: &str) -> Result<Self, Error> {
+        Self::new(&quote(name))
+    }
+
+    /// Returns the path selecting `other` relative to this one, such as
+    /// `a.b.c[0]` for `a.b` and `c[0]`.
+    #[must_use]
+    pub fn join(
+        &self,
+        other: &Self,
+    ) -> Self {
+        let (base, tail) = (self.0.as_bytes(), other.0.as_bytes());
+        if base.is_empty() {
+            return other.clone();
+        }
+        let mut joined = base.to_vec();
+        if !tail.is_empty() && !tail.starts_with(b"[") {
+            joined.push(b'.');
+        }
+        joined.extend_from_slice(tail);
+        // Neither part contains a nul byte.
+        Self(CString::new(joined).unwrap_or_default())
+    }
+
+    /// Returns the label of the last selector of the path: the name of a
+    /// field, unquoted, or the index of a list element; `None` for the empty
+    /// path.
+    ///
+    /// Definitions and hidden fields keep their `#` and `_` prefixes, so the
+    /// label of `#Config.#Port` is `#Port`.
+    #[must_use]
+    pub fn label(&self) -> Option<String> {
+        let (_, last) = split_last(self.0.to_str().ok()?);
+        let last = last
+            .strip_prefix('[')
+            .and_then(|index| index.strip_suffix(']'))
+            .unwrap_or(last);
+        match last {
+            "" => None,
+            _ if last.starts_with('"') => unquote(last),
+            _ => Some(last.to_string()),
+        }
+    }
+
+    /// Returns the path of the value this path selects from, such as `a.b`
+    /// for `a.b[0]`; `None` for the empty path.
+    pub(crate) fn parent(&self) -> Option<Self> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let (parent, _) = split_last(self.0.to_str().ok()?);
+        Self::new(parent).ok()
+    }
+
+    /// Returns the path as a C string, for passing to libcue.
+    pub(crate) fn as_c_str(&self) -> &std::ffi::CStr {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for Path {
+    type Error = Error;
+
+    fn try_from(path: &str) -> Result<Self, Self::Error> {
+        Self::new(path)
+    }
+}
+
+impl std::fmt::Display for Path {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(&self.0.to_string_lossy())
+    }
+}
+
+/// Splits `path` before its last selector, such as into `a.b` and `[0]` for
+/// `a.b[0]`, or `a` and `b` for `a.b`.
+fn split_last(path: &str) -> (&str, &str) {
+    let (mut end, mut start) = (0, 0);
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in path.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '.' if !quoted => (end, start) = (i, i.saturating_add(1)),
+            '[' if !quoted => (end, start) = (i, i),
+            _ => {},
+        }
+    }
+    (
+        path.get(..end).unwrap_or_default(),
+        path.get(start..).unwrap_or_default(),
+    )
+}
+
+/// Returns the value of the CUE string literal `s`, as written by [`quote`].
+fn unquote(s: &str) -> Option<String> {
+    let mut chars = s.strip_prefix('"')?.strip_suffix('"')?.chars();
+    let mut out = String::with_capacity(s.len());
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            'u' => {
+                let hex = chars.by_ref().take(4).collect::<String>();
+                out.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            },
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+/// Quotes `s` as a CUE string literal.
+pub(crate) fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len().saturating_add(2));
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", u32::from(c));
+            },
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::Path;
+
+    #[test_case("", "a" => "a"; "root")]
+    #[test_case("a.b", "c" => "a.b.c"; "field")]
+    #[test_case("a.b", "[0]" => "a.b[0]"; "index")]
+    #[test_case("a", "" => "a"; "empty")]
+    #[test_case("#Def", "\"c-d\".e" => "#Def.\"c-d\".e"; "quoted")]
+    fn test_join(
+        base: &str,
+        other: &str,
+    ) -> String {
+        Path::new(base)
+            .unwrap()
+            .join(&Path::new(other).unwrap())
+            .to_string()
+    }
+
+    #[test_case("" => None; "root")]
+    #[test_case("a" => Some("a".to_string()); "field")]
+    #[test_case("a.b[3]" => Some("3".to_string()); "index")]
+    #[test_case("#Config.#Port" => Some("#Port".to_string()); "definition")]
+    #[test_case("a._b" => Some("_b".to_string()); "hidden")]
+    #[test_case(r#"a."x.y[0]""# => Some("x.y[0]".to_string()); "quoted")]
+    #[test_case(r#"a."q\"\\\u00e9""# => Some("q\"\\é".to_string()); "escapes")]
+    fn test_label(path: &str) -> Option<String> {
+        Path::new(path).unwrap().label()
+    }
+
+    #[test_case("" => None; "root")]
+    #[test_case("a" => Some(String::new()); "field")]
+    #[test_case("a.b[3]" => Some("a.b".to_string()); "index")]
+    #[test_case("#Config.#Port" => Some("#Config".to_string()); "definition")]
+    #[test_case(r#"a."x.y[0]".z"# => Some(r#"a."x.y[0]""#.to_string()); "quoted")]
+    fn test_parent(path: &str) -> Option<String> {
+        Path::new(path)
+            .unwrap()
+            .parent()
+            .map(|parent| parent.to_string())
+    }
+
+    #[test]
+    fn test_field_label() {
+        for name in ["plain", "a.b", "#x", "tab\there", "\u{1}"] {
+            assert_eq!(Path::field(name).unwrap().label().as_deref(), Some(name));
+        }
+    }
+}
@@ -0,0 +1,299 @@
+//! Configuration providers backed by CUE evaluation.
+//!
+//! A [`CueProvider`] unifies a schema with any number of CUE configuration
+//! layers, enforces the schema on the result and hands the exported data to an
+//! existing configuration crate:
+//!
+//! - with the `figment` feature it is a [`figment::Provider`];
+//! - with the `config` feature it is a [`config::Source`].
+//!
+//! Applications built on either crate can therefore move their configuration
+//! to CUE without changing how it is layered and extracted.  Layers are CUE
+//! sources, files or packages, and may take values from the application
+//! through `@tag` attributes (see [`CueProvider::with_tag`]).
+
+use std::{borrow::Cow, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{
+    Ctx, Value,
+    de::Deserializer,
+    error::Error,
+    load::{LoadOptions, read_source},
+};
+
+/// A configuration layer of a [`CueProvider`].
+#[derive(Debug, Clone)]
+enum Layer {
+    /// CUE source text.
+    Source(String),
+    /// A CUE file or package directory, read on evaluation.
+    File(PathBuf),
+}
+
+/// A configuration source evaluating CUE layers against a schema.
+///
+/// Layers are compiled and unified in order on the default context
+/// (see [`Ctx::global`]) every time the configuration crate collects the
+/// provider.
+#[derive(Debug, Clone, Default)]
+pub struct CueProvider {
+    /// Schema the unified layers must satisfy, if any.
+    schema: Option<String>,
+    /// Layers unified into the configuration, in order.
+    layers: Vec<Layer>,
+    /// Options the layers are compiled with.
+    options: LoadOptions,
+}
+
+impl CueProvider {
+    /// Creates a provider with no schema and no layers.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the schema the configuration must satisfy.
+    #[must_use]
+    pub fn with_schema(
+        mut self,
+        schema: impl Into<String>,
+    ) -> Self {
+        self.schema = Some(schema.into());
+        self
+    }
+
+    /// Adds a CUE source unified into the configuration after the existing
+    /// layers.
+    #[must_use]
+    pub fn with_layer(
+        mut self,
+        src: impl Into<String>,
+    ) -> Self {
+        self.layers.push(Layer::Source(src.into()));
+        self
+    }
+
+    /// Adds the CUE file at `path`, or the package formed by the `.cue`
+    /// files of the directory `path`, as a layer unified after the existing
+    /// ones, as loaded by [`LoadOptions::load`].
+    ///
+    /// The files are read every time the provider is evaluated, so a
+    /// reloaded configuration sees their current contents.
+    ///
+    /// ```no_run
+    /// use cue_rs::provider::CueProvider;
+    ///
+    /// let provider = CueProvider::new()
+    ///     .with_schema("{ port: int & >0 & <65536 }")
+    ///     .with_file("config/");
+    /// let config = provider.evaluate().unwrap();
+    /// ```
+    #[must_use]
+    pub fn with_file(
+        mut self,
+        path: impl Into<PathBuf>,
+    ) -> Self {
+        self.layers.push(Layer::File(path.into()));
+        self
+    }
+
+    /// Injects `value` into the fields of the layers marked `@tag(name)`,
+    /// like `cue -t name=value`, see [`LoadOptions::with_tag`].
+    ///
+    /// Each tag must be used by a field of at least one layer.
+    #[must_use]
+    pub fn with_tag(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.options = self.options.with_tag(name, value);
+        self
+    }
+
+    /// Evaluates the schema and the layers and returns a deserializer over
+    /// the result.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if a file layer cannot be read,
+    /// [`Error::InvalidTag`] if a tag is not used or has an invalid value, and
+    /// [`Error::Cue`] if a source cannot be compiled, the layers conflict,
+    /// the schema is not satisfied or the result is not concrete.
+    pub fn evaluate(&self) -> Result<Deserializer, Error> {
+        let ctx = Ctx::global()?;
+        let mut config = Value::compile_string(ctx, self.schema.as_deref().unwrap_or("_"))?;
+        let srcs = self
+            .layers
+            .iter()
+            .map(|layer| {
+                match layer {
+                    Layer::Source(src) => Ok(Cow::Borrowed(src.as_str())),
+                    Layer::File(path) => read_source(path).map(Cow::Owned),
+                }
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let srcs: Vec<&str> = srcs.iter().map(AsRef::as_ref).collect();
+        for layer in self.options.compile_each(ctx, &srcs)? {
+            config = config.unify_with(&layer);
+        }
+        config.is_valid()?;
+        Deserializer::new(&config)
+    }
+
+    /// Evaluates the configuration and deserializes it as `T`.
+    fn extract<T: for<'de> Deserialize<'de>>(&self) -> Result<T, String> {
+        let de = self.evaluate().map_err(|err| err.to_string())?;
+        T::deserialize(&de).map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(feature = "figment")]
+impl figment::Provider for CueProvider {
+    /// Names the provider `CUE`, with the last file layer as its source.
+    fn metadata(&self) -> figment::Metadata {
+        let file = self.layers.iter().rev().find_map(|layer| {
+            match layer {
+                Layer::File(path) => Some(path.as_path()),
+                Layer::Source(_) => None,
+            }
+        });
+        match file {
+            Some(path) => figment::Metadata::named("CUE").source(path),
+            None => figment::Metadata::named("CUE"),
+        }
+    }
+
+    fn data(
+        &self
+    ) -> Result<figment::value::Map<figment::Profile, figment::value::Dict>, figment::Error> {
+        let dict = self.extract()?;
+        Ok(figment::Profile::Default.collect(dict))
+    }
+}
+
+#[cfg(feature = "config")]
+impl config::Source for CueProvider {
+    fn clone_into_box(&self) -> Box<dyn config::Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<config::Map<String, config::Value>, config::ConfigError> {
+        self.extract().map_err(config::ConfigError::Message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::CueProvider;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    fn provider(port: &str) -> CueProvider {
+        CueProvider::new()
+            .with_schema(r#"{ host: string | *"localhost", port: int & >0 & <65536 }"#)
+            .with_layer(format!("{{ port: {port} }}"))
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let de = provider("8080").evaluate().unwrap();
+        assert_eq!(Server::deserialize(&de).unwrap(), Server {
+            host: "localhost".to_string(),
+            port: 8080,
+        });
+        assert!(provider("0").evaluate().is_err());
+        assert!(
+            CueProvider::new()
+                .with_layer("{ a: int }")
+                .evaluate()
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "figment")]
+    #[test]
+    fn test_figment() {
+        use figment::Figment;
+
+        let server: Server = Figment::from(provider("8080"))
+            .merge(("host", "example.com"))
+            .extract()
+            .unwrap();
+        assert_eq!(server, Server {
+            host: "example.com".to_string(),
+            port: 8080,
+        });
+        assert!(Figment::from(provider("0")).extract::<Server>().is_err());
+    }
+
+    #[test]
+    fn test_with_file() {
+        let dir = std::env::temp_dir().join(format!("cue-rs-provider-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.cue"), "package config\n\nport: base + 80\n").unwrap();
+        std::fs::write(dir.join("b.cue"), "package config\n\nbase: 8000\n").unwrap();
+        let de = CueProvider::new()
+            .with_schema(r#"{ host: string | *"localhost", port: int, ... }"#)
+            .with_file(&dir)
+            .evaluate();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(Server::deserialize(&de.unwrap()).unwrap(), Server {
+            host: "localhost".to_string(),
+            port: 8080,
+        });
+
+        let err = CueProvider::new().with_file(&dir).evaluate();
+        assert!(matches!(err, Err(crate::error::Error::Io { .. })));
+    }
+
+    #[test]
+    fn test_with_tag() {
+        let provider = CueProvider::new()
+            .with_layer(r#"host: *"localhost" | string @tag(host)"#)
+            .with_layer("port: *80 | int @tag(port,type=int)");
+        let de = provider
+            .clone()
+            .with_tag("host", "example.com")
+            .with_tag("port", "8080")
+            .evaluate()
+            .unwrap();
+        assert_eq!(Server::deserialize(&de).unwrap(), Server {
+            host: "example.com".to_string(),
+            port: 8080,
+        });
+        assert!(matches!(
+            provider.with_tag("replicas", "3").evaluate(),
+            Err(crate::error::Error::InvalidTag { .. })
+        ));
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_config() {
+        let server: Server = config::Config::builder()
+            .add_source(provider("8080"))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+        assert_eq!(server, Server {
+            host: "localhost".to_string(),
+            port: 8080,
+        });
+        assert!(
+            config::Config::builder()
+                .add_source(provider("0"))
+                .build()
+                .is_err()
+        );
+    }
+}
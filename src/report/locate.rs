@@ -0,0 +1,484 @@
+//! Locating error paths in the JSON or YAML text of a validated document.
+//!
+//! Documents are compiled into CUE values before validation, so the
+//! positions CUE reports do not point into the user's file.  Instead, the
+//! path of each diagnostic is followed through the original text by a small
+//! scanner that only tracks where values start.  YAML is handled for block
+//! style mappings and sequences; a path is followed as far as the scanner
+//! can, so a field missing from the document points at its parent.
+
+/// Returns the 1-based line and byte column of the value at the path
+/// `labels` in `document`, or of its deepest ancestor present in the text.
+pub(super) fn locate(
+    document: &str,
+    labels: &[String],
+) -> Option<(usize, usize)> {
+    let trimmed = document.trim_start();
+    let offset = if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        let start = document.len().saturating_sub(trimmed.len());
+        json(document.as_bytes(), start, labels)
+    } else {
+        yaml(document, labels)
+    }?;
+    Some(line_column(document, offset))
+}
+
+/// Converts a byte offset into a 1-based line and column.
+fn line_column(
+    document: &str,
+    offset: usize,
+) -> (usize, usize) {
+    let before = document.get(..offset).unwrap_or_default();
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |at| at.saturating_add(1));
+    (
+        line.saturating_add(1),
+        offset.saturating_sub(line_start).saturating_add(1),
+    )
+}
+
+// ── JSON ─────────────────────────────────────────────────────────────
+
+/// Follows `labels` from the JSON value starting at `start`, returning the
+/// offset of the deepest value reached.
+fn json(
+    doc: &[u8],
+    mut start: usize,
+    labels: &[String],
+) -> Option<usize> {
+    for label in labels {
+        match doc.get(start) {
+            Some(b'{') => {
+                match object_member(doc, start, label)? {
+                    Member::Found(value) => start = value,
+                    Member::Missing => return Some(start),
+                }
+            },
+            Some(b'[') => {
+                match array_element(doc, start, label)? {
+                    Member::Found(value) => start = value,
+                    Member::Missing => return Some(start),
+                }
+            },
+            _ => return Some(start),
+        }
+    }
+    Some(start)
+}
+
+/// The outcome of looking a member up in a JSON object or array.
+enum Member {
+    /// The member's value starts at this offset.
+    Found(usize),
+    /// The object or array has no such member.
+    Missing,
+}
+
+/// Looks up member `name` of the object at `start`, returning `None` if the
+/// text is malformed.
+fn object_member(
+    doc: &[u8],
+    start: usize,
+    name: &str,
+) -> Option<Member> {
+    let mut pos = skip_ws(doc, start.saturating_add(1));
+    if doc.get(pos) == Some(&b'}') {
+        return Some(Member::Missing);
+    }
+    loop {
+        let key_end = skip_string(doc, pos)?;
+        let key = doc.get(pos.saturating_add(1)..key_end.saturating_sub(1))?;
+        pos = skip_ws(doc, key_end);
+        if doc.get(pos) != Some(&b':') {
+            return None;
+        }
+        let value = skip_ws(doc, pos.saturating_add(1));
+        if unescape(key).as_deref() == Some(name) {
+            return Some(Member::Found(value));
+        }
+        pos = skip_ws(doc, skip_value(doc, value)?);
+        match doc.get(pos) {
+            Some(b',') => pos = skip_ws(doc, pos.saturating_add(1)),
+            Some(b'}') => return Some(Member::Missing),
+            _ => return None,
+        }
+    }
+}
+
+/// Looks up element `index` of the array at `start`, like
+/// [`object_member`].
+fn array_element(
+    doc: &[u8],
+    start: usize,
+    index: &str,
+) -> Option<Member> {
+    let Ok(index) = index.parse::<usize>() else {
+        return Some(Member::Missing);
+    };
+    let mut pos = skip_ws(doc, start.saturating_add(1));
+    if doc.get(pos) == Some(&b']') {
+        return Some(Member::Missing);
+    }
+    for _ in 0..index {
+        pos = skip_ws(doc, skip_value(doc, pos)?);
+        match doc.get(pos) {
+            Some(b',') => pos = skip_ws(doc, pos.saturating_add(1)),
+            Some(b']') => return Some(Member::Missing),
+            _ => return None,
+        }
+    }
+    Some(Member::Found(pos))
+}
+
+/// Skips JSON whitespace from `pos`.
+fn skip_ws(
+    doc: &[u8],
+    mut pos: usize,
+) -> usize {
+    while doc.get(pos).is_some_and(u8::is_ascii_whitespace) {
+        pos = pos.saturating_add(1);
+    }
+    pos
+}
+
+/// Skips the string starting at `pos`, returning the offset after its
+/// closing quote.
+fn skip_string(
+    doc: &[u8],
+    pos: usize,
+) -> Option<usize> {
+    if doc.get(pos) != Some(&b'"') {
+        return None;
+    }
+    let mut escaped = false;
+    let rest = doc.get(pos.saturating_add(1)..)?;
+    let len = rest.iter().position(|&b| {
+        let end = b == b'"' && !escaped;
+        escaped = b == b'\\' && !escaped;
+        end
+    })?;
+    Some(pos.saturating_add(len).saturating_add(2))
+}
+
+/// Skips the value starting at `pos`, returning the offset after it.
+///
+/// Nested values are skipped by counting brackets rather than recursing, so
+/// deeply nested input cannot overflow the stack.
+fn skip_value(
+    doc: &[u8],
+    mut pos: usize,
+) -> Option<usize> {
+    let mut depth = 0usize;
+    loop {
+        match doc.get(pos)? {
+            b'"' => pos = skip_string(doc, pos)?,
+            b'{' | b'[' => {
+                depth = depth.saturating_add(1);
+                pos = pos.saturating_add(1);
+            },
+            b'}' | b']' => {
+                depth = depth.checked_sub(1)?;
+                pos = pos.saturating_add(1);
+            },
+            b',' | b':' if depth > 0 => pos = pos.saturating_add(1),
+            b if b.is_ascii_whitespace() && depth > 0 => pos = pos.saturating_add(1),
+            _ if depth > 0 => pos = pos.saturating_add(1),
+            _ => {
+                let rest = doc.get(pos..)?;
+                let len = rest
+                    .iter()
+                    .position(|b| matches!(b, b',' | b'}' | b']') || b.is_ascii_whitespace())
+                    .unwrap_or(rest.len());
+                pos = pos.saturating_add(len);
+            },
+        }
+        if depth == 0 {
+            return Some(pos);
+        }
+    }
+}
+
+/// Decodes the contents of a JSON string, between its quotes.
+fn unescape(raw: &[u8]) -> Option<String> {
+    let inner = std::str::from_utf8(raw).ok()?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            'b' => out.push('\u{8}'),
+            'f' => out.push('\u{c}'),
+            'u' => {
+                let hex: String = chars.by_ref().take(4).collect();
+                out.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+            },
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+// ── YAML ─────────────────────────────────────────────────────────────
+
+/// A structural token of a block style YAML line.
+#[derive(Debug)]
+struct Token {
+    /// Byte offset of the token in the document.
+    offset: usize,
+    /// Byte column of the token in its line.
+    col: usize,
+    /// Line index of the token.
+    line: usize,
+    /// What the token is.
+    kind: TokenKind,
+}
+
+/// The kind of a [`Token`].
+#[derive(Debug)]
+enum TokenKind {
+    /// A `- ` sequence entry indicator.
+    Dash,
+    /// A mapping key, with the offset of its value if on the same line.
+    Key {
+        /// The unquoted key.
+        name: String,
+        /// Offset of the value following the key on its line.
+        value: Option<usize>,
+    },
+    /// Any other content.
+    Scalar,
+}
+
+/// Follows `labels` through a block style YAML document, returning the
+/// offset of the deepest value reached.
+fn yaml(
+    doc: &str,
+    labels: &[String],
+) -> Option<usize> {
+    let tokens = tokenize(doc);
+    let mut node = 0;
+    let mut offset = tokens.first()?.offset;
+    for label in labels {
+        let token = tokens.get(node)?;
+        let child = match token.kind {
+            TokenKind::Key { .. } => mapping_value(&tokens, node, label),
+            TokenKind::Dash => sequence_item(&tokens, node, label),
+            TokenKind::Scalar => None,
+        };
+        match child {
+            Some(Child::Node(next)) => {
+                node = next;
+                offset = tokens.get(next)?.offset;
+            },
+            Some(Child::Inline(value)) => return Some(value),
+            None => return Some(offset),
+        }
+    }
+    Some(offset)
+}
+
+/// Where a child value of a YAML node is.
+enum Child {
+    /// The value starts at this token.
+    Node(usize),
+    /// The value is inline on a key's line, or empty, and is located at
+    /// this offset.
+    Inline(usize),
+}
+
+/// Finds the value of key `name` in the mapping whose first key is token
+/// `node`.
+fn mapping_value(
+    tokens: &[Token],
+    node: usize,
+    name: &str,
+) -> Option<Child> {
+    let col = tokens.get(node)?.col;
+    let (at, key) = tokens
+        .iter()
+        .enumerate()
+        .skip(node)
+        .take_while(|(i, t)| *i == node || t.col >= col)
+        .find(|(_, t)| {
+            t.col == col && matches!(&t.kind, TokenKind::Key { name: n, .. } if n == name)
+        })?;
+    if let TokenKind::Key {
+        value: Some(value), ..
+    } = key.kind
+    {
+        return Some(Child::Inline(value));
+    }
+    let next = at.saturating_add(1);
+    let Some(value) = tokens.get(next) else {
+        return Some(Child::Inline(key.offset));
+    };
+    let nested = value.line != key.line
+        && (value.col > col || (value.col == col && matches!(value.kind, TokenKind::Dash)));
+    Some(if nested {
+        Child::Node(next)
+    } else {
+        Child::Inline(key.offset)
+    })
+}
+
+/// Finds item `index` of the sequence whose first `- ` is token `node`.
+fn sequence_item(
+    tokens: &[Token],
+    node: usize,
+    index: &str,
+) -> Option<Child> {
+    let index: usize = index.parse().ok()?;
+    let col = tokens.get(node)?.col;
+    let (at, dash) = tokens
+        .iter()
+        .enumerate()
+        .skip(node)
+        .take_while(|(i, t)| *i == node || t.col >= col)
+        .filter(|(_, t)| t.col == col && matches!(t.kind, TokenKind::Dash))
+        .nth(index)?;
+    let next = at.saturating_add(1);
+    let item = tokens.get(next)?;
+    (item.line == dash.line || item.col > col).then_some(Child::Node(next))
+}
+
+/// Splits a YAML document into structural tokens, skipping blank lines,
+/// comments and document markers.
+fn tokenize(doc: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut line_start = 0usize;
+    for (line_no, line) in doc.split('\n').enumerate() {
+        let start = line_start;
+        line_start = line_start.saturating_add(line.len()).saturating_add(1);
+        let content = line.trim_end_matches('\r');
+        let trimmed = content.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("---") {
+            continue;
+        }
+        let mut col = content.len().saturating_sub(trimmed.len());
+        let mut rest = trimmed;
+        let mut push = |col: usize, kind| {
+            tokens.push(Token {
+                offset: start.saturating_add(col),
+                col,
+                line: line_no,
+                kind,
+            });
+        };
+        while rest == "-" || rest.starts_with("- ") {
+            push(col, TokenKind::Dash);
+            let after = rest.get(1..).unwrap_or_default();
+            let next = after.trim_start();
+            col = col
+                .saturating_add(1)
+                .saturating_add(after.len().saturating_sub(next.len()));
+            rest = next;
+        }
+        if rest.is_empty() {
+            continue;
+        }
+        match split_key(rest) {
+            Some((name, value)) => {
+                let value = value.map(|v| start.saturating_add(col).saturating_add(v));
+                push(col, TokenKind::Key { name, value });
+            },
+            None => push(col, TokenKind::Scalar),
+        }
+    }
+    tokens
+}
+
+/// Splits `key: value` content into the unquoted key and the offset of the
+/// value within `content`, if there is one before a comment.
+fn split_key(content: &str) -> Option<(String, Option<usize>)> {
+    let quote = content.chars().next().filter(|c| matches!(c, '"' | '\''));
+    let (name, after) = if let Some(quote) = quote {
+        let end = content.get(1..)?.find(quote)?.saturating_add(1);
+        (content.get(1..end)?.to_string(), end.saturating_add(1))
+    } else {
+        let colon = content.match_indices(':').map(|(at, _)| at).find(|&at| {
+            matches!(
+                content.as_bytes().get(at.saturating_add(1)),
+                None | Some(b' ' | b'\t')
+            )
+        })?;
+        (content.get(..colon)?.trim_end().to_string(), colon)
+    };
+    let rest = content.get(after..)?;
+    let value = rest.strip_prefix(':')?;
+    let trimmed = value.trim_start();
+    let value = (!trimmed.is_empty() && !trimmed.starts_with('#')).then(|| {
+        after
+            .saturating_add(1)
+            .saturating_add(value.len().saturating_sub(trimmed.len()))
+    });
+    Some((name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::locate;
+
+    const JSON: &str = r#"{
+  "name": "api",
+  "spec": {
+    "containers": [
+      { "name": "a", "image": "x" },
+      { "name": "b", "image": 1, "a/b": { "c\"d": true } }
+    ]
+  }
+}"#;
+
+    const YAML: &str = "# deployment
+name: api
+spec:
+  containers:
+  - name: a
+    image: x
+  -   name: b
+      image: 1
+      nested:
+        deep: true
+  empty:
+list:
+- - 1
+  - 2
+";
+
+    fn labels(path: &str) -> Vec<String> {
+        path.split('.')
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test_case(""                          => Some((1, 1));  "root")]
+    #[test_case("name"                      => Some((2, 11)); "field")]
+    #[test_case("spec.containers.1.image"   => Some((6, 31)); "nested element")]
+    #[test_case("spec.containers.1.a/b.c\"d" => Some((6, 51)); "escaped keys")]
+    #[test_case("spec.containers.5.image"   => Some((4, 19)); "missing element")]
+    #[test_case("spec.missing"              => Some((3, 11)); "missing field")]
+    #[test_case("name.x"                    => Some((2, 11)); "below scalar")]
+    fn test_locate_json(path: &str) -> Option<(usize, usize)> {
+        locate(JSON, &labels(path))
+    }
+
+    #[test_case(""                           => Some((2, 1));  "root")]
+    #[test_case("name"                       => Some((2, 7));  "field")]
+    #[test_case("spec.containers.0.image"    => Some((6, 12)); "sequence of mappings")]
+    #[test_case("spec.containers.1.image"    => Some((8, 14)); "wide indicator")]
+    #[test_case("spec.containers.1.nested.deep" => Some((10, 15)); "nested mapping")]
+    #[test_case("spec.empty"                 => Some((11, 3)); "empty value")]
+    #[test_case("spec.containers.2"          => Some((5, 3));  "missing element")]
+    #[test_case("list.0.1"                   => Some((14, 5)); "nested sequence")]
+    fn test_locate_yaml(path: &str) -> Option<(usize, usize)> {
+        locate(YAML, &labels(path))
+    }
+}
@@ -0,0 +1,730 @@
+//! Structured view over CUE error messages, with emitters for CI annotation
+//! formats.
+//!
+//! libcue reports errors as a single rendered string.  [`Report`] splits that
+//! string back into individual [`Diagnostic`]s (path, message and source
+//! positions) so they can be re-rendered in other formats.
+//!
+//! Every diagnostic is classified under a stable rule ID (see
+//! [`Diagnostic::rule_id`]), which code-scanning formats such as SARIF use to
+//! group findings across runs.  libcue reports no error codes, so the rule is
+//! found by matching the whole message against the message templates of the
+//! vendored CUE evaluator, such as `conflicting values % and %`, rather than
+//! by searching it for fragments that might as well come from user data.
+//!
+//! For terminal output, [`Report::render`] prints rustc-style diagnostics with
+//! an excerpt of the offending source lines.
+//!
+//! For UIs and API responses, a [`ValidationReport`] lists every violation of
+//! a validated document with the expected constraint and the actual value.
+//!
+//! CUE reports positions in the compiled representation of a JSON or YAML
+//! document rather than in its text; [`Report::with_document`] points the
+//! diagnostics at the document itself.
+
+mod locate;
+mod validation;
+
+use std::fmt::Write;
+
+pub use self::validation::{Severity, ValidationReport, Violation};
+use crate::error::Error;
+
+/// Rule IDs, the message templates identifying them and their description.
+///
+/// A template is the format string of the message in the CUE evaluator, with
+/// `%` standing for any text; it must match the whole diagnostic message.
+/// Templates are tried in order, so more specific ones come first.  IDs are
+/// part of the public output and must not change.
+const RULES: &[(&str, &str, &str)] = &[
+    (
+        "cue/conflicting-values",
+        "conflicting values % and %",
+        "Two values unified at the same path are incompatible.",
+    ),
+    (
+        "cue/field-not-allowed",
+        "field not allowed",
+        "A field is not permitted by a closed struct or definition.",
+    ),
+    (
+        "cue/out-of-bound",
+        "invalid value % (out of bound %)",
+        "A value violates a bound constraint such as `>=0`.",
+    ),
+    (
+        "cue/incomplete-value",
+        "incomplete value %",
+        "A value is required to be concrete but is not.",
+    ),
+    (
+        "cue/reference-not-found",
+        "reference % not found",
+        "A reference does not resolve to any field.",
+    ),
+    (
+        "cue/invalid-value",
+        "invalid value %",
+        "A value does not satisfy a constraint.",
+    ),
+    (
+        "cue/syntax-error",
+        "expected %, found %",
+        "The CUE source could not be parsed.",
+    ),
+];
+
+/// Rule ID of diagnostics that match no entry of [`RULES`].
+const FALLBACK_RULE: (&str, &str) = ("cue/error", "Any other CUE evaluation error.");
+
+/// A source position referenced by a [`Diagnostic`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    /// Name of the file the position refers to, as reported by CUE.
+    pub filename: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number.
+    pub column: usize,
+}
+
+/// A single error extracted from a CUE error message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// CUE path of the failing value (e.g. `spec.replicas`), if reported.
+    pub path: Option<String>,
+    /// Human readable error message, without the path prefix.
+    pub message: String,
+    /// Source positions involved in the error, the primary one first.
+    pub positions: Vec<Position>,
+}
+
+/// A collection of [`Diagnostic`]s produced by a failed CUE operation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Diagnostics in the order CUE reported them.
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl From<&Error> for Report {
+    fn from(err: &Error) -> Self {
+        Self::parse(&err.to_string())
+    }
+}
+
+impl Report {
+    /// Parses a rendered CUE error message into a [`Report`].
+    ///
+    /// Each unindented line starts a new diagnostic; indented
+    /// `file:line:column` lines that follow it are its positions.
+    pub(crate) fn parse(text: &str) -> Self {
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace)
+                && let Some(pos) = parse_position(line.trim())
+                && let Some(last) = diagnostics.last_mut()
+            {
+                last.positions.push(pos);
+                continue;
+            }
+            diagnostics.push(parse_diagnostic(line.trim()));
+        }
+        Self { diagnostics }
+    }
+
+    /// Returns the diagnostics of this report.
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Points the diagnostics at `document`, the JSON or YAML text that was
+    /// validated, reported under `filename`.
+    ///
+    /// The path of each diagnostic is followed through the text, and the
+    /// position of the value found is made its primary position.  A path
+    /// missing from the document (such as a required field it does not set)
+    /// is located at its closest ancestor.  YAML documents are followed
+    /// through block style mappings and sequences only.
+    #[must_use]
+    pub fn with_document(
+        mut self,
+        filename: &str,
+        document: &str,
+    ) -> Self {
+        for d in &mut self.diagnostics {
+            let Some(path) = &d.path else {
+                continue;
+            };
+            if let Some((line, column)) = locate::locate(document, &path_labels(path)) {
+                d.positions.insert(0, Position {
+                    filename: filename.to_string(),
+                    line,
+                    column,
+                });
+            }
+        }
+        self
+    }
+
+    /// Renders the report as GitHub Actions workflow commands.
+    ///
+    /// Each diagnostic becomes one `::error` line anchored at its primary
+    /// position, so the failure shows up inline on the pull request.
+    #[must_use]
+    pub fn to_github_annotations(&self) -> String {
+        let mut out = String::new();
+        for d in &self.diagnostics {
+            out.push_str("::error");
+            let mut props = Vec::new();
+            if let Some(pos) = d.positions.first() {
+                props.push(format!("file={}", escape_property(&pos.display_filename())));
+                props.push(format!("line={}", pos.line));
+                props.push(format!("col={}", pos.column));
+            }
+            if let Some(path) = &d.path {
+                props.push(format!("title={}", escape_property(path)));
+            }
+            if !props.is_empty() {
+                out.push(' ');
+                out.push_str(&props.join(","));
+            }
+            out.push_str("::");
+            out.push_str(&escape_data(&d.message));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the report as a Code Climate JSON array, the format consumed by
+    /// GitLab's code quality widget.
+    ///
+    /// Diagnostics without a source position are reported against line 1 of
+    /// an empty path.
+    #[must_use]
+    pub fn to_code_climate_json(&self) -> String {
+        let issues: Vec<String> = self
+            .diagnostics
+            .iter()
+            .map(|d| {
+                let (path, line) = d
+                    .positions
+                    .first()
+                    .map_or((String::new(), 1), |pos| (pos.display_filename(), pos.line));
+                format!(
+                    r#"{{"type":"issue","check_name":"cue","description":{},"severity":"major","fingerprint":"{:016x}","location":{{"path":{},"lines":{{"begin":{line}}}}}}}"#,
+                    json_string(&d.to_string()),
+                    d.fingerprint(),
+                    json_string(&path),
+                )
+            })
+            .collect();
+        format!("[{}]", issues.join(","))
+    }
+
+    /// Renders the report as rustc-style diagnostics with source excerpts.
+    ///
+    /// `sources` maps file names (as reported by CUE, with or without the
+    /// leading `./`) to their contents.  Each position whose file is found is
+    /// shown with the offending line and a caret under the reported column;
+    /// other positions are listed without an excerpt.
+    #[must_use]
+    pub fn render(
+        &self,
+        sources: &[(&str, &str)],
+    ) -> String {
+        let mut out = String::new();
+        for d in &self.diagnostics {
+            let _ = writeln!(out, "error[{}]: {}", d.rule_id(), d.message);
+            let width = d
+                .positions
+                .iter()
+                .map(|pos| pos.line.to_string().len())
+                .max()
+                .unwrap_or(0);
+            let pad = " ".repeat(width);
+            for pos in &d.positions {
+                let _ = writeln!(
+                    out,
+                    "{pad}--> {}:{}:{}",
+                    pos.display_filename(),
+                    pos.line,
+                    pos.column
+                );
+                let Some(line) = sources
+                    .iter()
+                    .find(|(name, _)| *name == pos.filename || *name == pos.display_filename())
+                    .and_then(|(_, src)| src.lines().nth(pos.line.saturating_sub(1)))
+                else {
+                    continue;
+                };
+                let _ = writeln!(out, "{pad} |");
+                let _ = writeln!(out, "{:>width$} | {line}", pos.line);
+                let _ = writeln!(out, "{pad} | {}^", caret_indent(line, pos.column));
+            }
+            if let Some(path) = &d.path {
+                let _ = writeln!(out, "{pad} = path: {path}");
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the report as a SARIF 2.1.0 log with a single run.
+    ///
+    /// The rules referenced by the results are listed in the tool driver, with
+    /// the IDs returned by [`Diagnostic::rule_id`].  The primary position of
+    /// a diagnostic is the location of its result; the other positions, such
+    /// as the second value of a conflict, are related locations.
+    #[must_use]
+    pub fn to_sarif(&self) -> String {
+        let mut rules: Vec<(&str, &str)> = Vec::new();
+        for d in &self.diagnostics {
+            let rule = d.rule();
+            if !rules.contains(&rule) {
+                rules.push(rule);
+            }
+        }
+        let rules: Vec<String> = rules
+            .iter()
+            .map(|(id, description)| {
+                format!(
+                    r#"{{"id":{},"shortDescription":{{"text":{}}}}}"#,
+                    json_string(id),
+                    json_string(description)
+                )
+            })
+            .collect();
+        let results: Vec<String> = self
+            .diagnostics
+            .iter()
+            .map(|d| {
+                let mut locations = d.positions.iter().map(|pos| {
+                    format!(
+                        r#"{{"physicalLocation":{{"artifactLocation":{{"uri":{}}},"region":{{"startLine":{},"startColumn":{}}}}}}}"#,
+                        json_string(&pos.display_filename()),
+                        pos.line,
+                        pos.column,
+                    )
+                });
+                let primary = locations.next().unwrap_or_default();
+                let related: Vec<String> = locations.collect();
+                let related = if related.is_empty() {
+                    String::new()
+                } else {
+                    format!(r#","relatedLocations":[{}]"#, related.join(","))
+                };
+                format!(
+                    r#"{{"ruleId":{},"level":"error","message":{{"text":{}}},"locations":[{}]{}}}"#,
+                    json_string(d.rule_id()),
+                    json_string(&d.to_string()),
+                    primary,
+                    related,
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"$schema":"https://json.schemastore.org/sarif-2.1.0.json","version":"2.1.0","runs":[{{"tool":{{"driver":{{"name":"cue-rs","version":{},"informationUri":"https://github.com/Mr-Leshiy/cue-rs","rules":[{}]}}}},"results":[{}]}}]}}"#,
+            json_string(env!("CARGO_PKG_VERSION")),
+            rules.join(","),
+            results.join(","),
+        )
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{path}: {}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl Diagnostic {
+    /// Returns the path of the failing value as an RFC 6901 JSON Pointer
+    /// (e.g. `/spec/containers/0/image`), for locating it in the JSON or YAML
+    /// document that was validated.
+    ///
+    /// List indices become plain reference tokens, as JSON Pointer does not
+    /// tell them from field names.
+    #[must_use]
+    pub fn json_pointer(&self) -> Option<String> {
+        let path = self.path.as_deref()?;
+        let mut pointer = String::with_capacity(path.len().saturating_add(1));
+        for label in path_labels(path) {
+            pointer.push('/');
+            pointer.push_str(&label.replace('~', "~0").replace('/', "~1"));
+        }
+        Some(pointer)
+    }
+
+    /// Returns the stable rule ID classifying this diagnostic, e.g.
+    /// `cue/conflicting-values`.
+    #[must_use]
+    pub fn rule_id(&self) -> &'static str {
+        self.rule().0
+    }
+
+    /// Returns the rule ID and rule description of this diagnostic.
+    fn rule(&self) -> (&'static str, &'static str) {
+        RULES
+            .iter()
+            .find(|(_, template, _)| matches_template(&self.message, template))
+            .map_or(FALLBACK_RULE, |(id, _, description)| (id, description))
+    }
+
+    /// Returns a stable FNV-1a hash of the diagnostic, used to de-duplicate
+    /// issues across CI runs.
+    fn fingerprint(&self) -> u64 {
+        let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+        let text = self.to_string();
+        let pos = self
+            .positions
+            .first()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        for byte in text.bytes().chain(pos.bytes()) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01B3);
+        }
+        hash
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.filename, self.line, self.column)
+    }
+}
+
+impl Position {
+    /// Returns the filename without CUE's leading `./`.
+    fn display_filename(&self) -> String {
+        self.filename
+            .strip_prefix("./")
+            .unwrap_or(&self.filename)
+            .to_string()
+    }
+}
+
+/// Returns `true` if `message` is an instance of `template`, a message
+/// template of [`RULES`] in which `%` stands for any text.
+fn matches_template(
+    message: &str,
+    template: &str,
+) -> bool {
+    let mut parts = template.split('%');
+    let Some(mut rest) = parts.next().and_then(|first| message.strip_prefix(first)) else {
+        return false;
+    };
+    let Some(last) = parts.next_back() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.split_once(part) {
+            Some((_, after)) => rest = after,
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Parses a `file:line:column` position line.
+fn parse_position(s: &str) -> Option<Position> {
+    let (rest, column) = s.rsplit_once(':')?;
+    let (filename, line) = rest.rsplit_once(':')?;
+    if filename.is_empty() {
+        return None;
+    }
+    Some(Position {
+        filename: filename.to_string(),
+        line: line.parse().ok()?,
+        column: column.parse().ok()?,
+    })
+}
+
+/// Parses a `path: message` line, dropping CUE's trailing `:` (which
+/// introduces position lines) and its `(and N more errors)` suffix.
+fn parse_diagnostic(line: &str) -> Diagnostic {
+    let line = match line.rsplit_once(" (and ") {
+        Some((head, tail)) if tail.ends_with(" more errors)") || tail.ends_with(" more error)") => {
+            head
+        },
+        _ => line,
+    };
+    let line = line.strip_suffix(':').unwrap_or(line);
+    let (path, message) = match line.split_once(": ") {
+        Some((path, message)) if !path.is_empty() && !path.contains(char::is_whitespace) => {
+            (Some(path.to_string()), message)
+        },
+        _ => (None, line),
+    };
+    Diagnostic {
+        path,
+        message: message.to_string(),
+        positions: Vec::new(),
+    }
+}
+
+/// Splits a CUE path as rendered in error messages (e.g.
+/// `a."b.c".d[0]`) into its labels, unquoting quoted labels.
+pub(crate) fn path_labels(path: &str) -> Vec<String> {
+    let mut labels = Vec::new();
+    let mut label = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => label.extend(chars.next()),
+                        c => label.push(c),
+                    }
+                }
+            },
+            '.' | '[' => labels.push(std::mem::take(&mut label)),
+            ']' => {},
+            c => label.push(c),
+        }
+    }
+    labels.push(label);
+    labels.retain(|label| !label.is_empty());
+    labels
+}
+
+/// Converts the error path `path` into a path [`Value::lookup`] accepts,
+/// quoting every field name and turning list indices into `[N]`.
+///
+/// [`Value::lookup`]: crate::Value::lookup
+pub(crate) fn lookup_selector(path: &str) -> String {
+    let lookup: Vec<String> = path_labels(path)
+        .iter()
+        .map(|label| {
+            if label.bytes().all(|b| b.is_ascii_digit()) {
+                format!("[{label}]")
+            } else {
+                format!(".{}", json_string(label))
+            }
+        })
+        .collect();
+    let lookup = lookup.concat();
+    lookup
+        .strip_prefix('.')
+        .map_or_else(|| lookup.clone(), str::to_string)
+}
+
+/// Returns the whitespace placing a caret under the 1-based byte `column` of
+/// `line`, keeping tabs so the caret lines up in terminals.
+fn caret_indent(
+    line: &str,
+    column: usize,
+) -> String {
+    let offset = column.saturating_sub(1);
+    line.char_indices()
+        .take_while(|(i, _)| *i < offset)
+        .map(|(_, c)| if c == '\t' { '\t' } else { ' ' })
+        .collect()
+}
+
+/// Escapes a workflow command message (`%`, CR and LF).
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a workflow command property value (data escapes plus `:` and
+/// `,`).
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Renders `s` as a quoted JSON string, which is also a valid CUE string
+/// literal.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len().saturating_add(2));
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{Diagnostic, Position, Report};
+
+    const MULTI: &str = "a: conflicting values 2 and 1:\n    ./x.cue:1:4\n    ./x.cue:2:4\nb.c: \
+                         incomplete value int (and 1 more errors)";
+
+    #[test]
+    fn test_parse() {
+        let report = Report::parse(MULTI);
+        assert_eq!(report.diagnostics(), &[
+            Diagnostic {
+                path: Some("a".to_string()),
+                message: "conflicting values 2 and 1".to_string(),
+                positions: vec![
+                    Position {
+                        filename: "./x.cue".to_string(),
+                        line: 1,
+                        column: 4,
+                    },
+                    Position {
+                        filename: "./x.cue".to_string(),
+                        line: 2,
+                        column: 4,
+                    },
+                ],
+            },
+            Diagnostic {
+                path: Some("b.c".to_string()),
+                message: "incomplete value int".to_string(),
+                positions: vec![],
+            },
+        ]);
+    }
+
+    #[test_case("invalid value 5 (out of bound >10)" => None; "message with spaces before colon")]
+    #[test_case("#Def.x: field not allowed" => Some("#Def.x".to_string()); "definition path")]
+    fn test_parse_path(line: &str) -> Option<String> {
+        Report::parse(line).diagnostics()[0].path.clone()
+    }
+
+    #[test_case("spec.containers.0.image" => Some("/spec/containers/0/image".to_string()); "nested")]
+    #[test_case("a[1].b"                  => Some("/a/1/b".to_string());                  "index")]
+    #[test_case(r#"a."b.c"."d/e~f""#        => Some("/a/b.c/d~1e~0f".to_string());          "quoted")]
+    #[test_case(r#""a\"b".x"#                => Some(r#"/a"b/x"#.to_string());               "escaped quote")]
+    #[test_case("conflicting values 1 and 2" => None;                                    "no path")]
+    fn test_json_pointer(line: &str) -> Option<String> {
+        Report::parse(&format!("{line}: invalid value")).diagnostics()[0].json_pointer()
+    }
+
+    #[test]
+    fn test_github_annotations() {
+        assert_eq!(
+            Report::parse(MULTI).to_github_annotations(),
+            "::error file=x.cue,line=1,col=4,title=a::conflicting values 2 and 1\n::error \
+             title=b.c::incomplete value int\n"
+        );
+    }
+
+    #[test]
+    fn test_with_document() {
+        let report = Report::parse(MULTI).with_document("data.yaml", "a: 2\nb:\n  d: 1\n");
+        let primary: Vec<_> = report
+            .diagnostics()
+            .iter()
+            .map(|d| d.positions.first().map(ToString::to_string))
+            .collect();
+        assert_eq!(primary, [
+            Some("data.yaml:1:4".to_string()),
+            Some("data.yaml:3:3".to_string())
+        ]);
+        assert_eq!(report.diagnostics()[0].positions.len(), 3);
+    }
+
+    #[test]
+    fn test_render() {
+        let sources = [("x.cue", "a: 2\n\ta: 1\n")];
+        assert_eq!(
+            Report::parse(MULTI).render(&sources),
+            "error[cue/conflicting-values]: conflicting values 2 and 1\n --> x.cue:1:4\n  |\n1 \
+             | a: 2\n  |    ^\n --> x.cue:2:4\n  |\n2 | \ta: 1\n  | \t  ^\n  = path: \
+             a\n\nerror[cue/incomplete-value]: incomplete value int\n = path: b.c\n\n"
+        );
+    }
+
+    #[test_case("conflicting values 2 and 1" => "cue/conflicting-values"; "conflict")]
+    #[test_case("invalid value 5 (out of bound >10)" => "cue/out-of-bound"; "bound")]
+    #[test_case("field not allowed" => "cue/field-not-allowed"; "closed")]
+    #[test_case("expected operand, found '}'" => "cue/syntax-error"; "syntax")]
+    #[test_case(r#"reference "b" not found"# => "cue/reference-not-found"; "reference")]
+    #[test_case(r#"conflicting values "not found" and 1"# => "cue/conflicting-values"; "fragment in value")]
+    #[test_case(r#"invalid value "expected x, found y" (does not satisfy =~"^a")"# => "cue/invalid-value"; "template in value")]
+    #[test_case("field not allowed: x" => "cue/error"; "partial match")]
+    #[test_case("something else" => "cue/error"; "fallback")]
+    fn test_rule_id(message: &str) -> &'static str {
+        Report::parse(message).diagnostics()[0].rule_id()
+    }
+
+    #[test]
+    fn test_sarif() {
+        let json: serde_json::Value =
+            serde_json::from_str(&Report::parse(MULTI).to_sarif()).unwrap();
+        assert_eq!(json["version"], "2.1.0");
+        let run = &json["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 2);
+        assert_eq!(run["results"][0]["ruleId"], "cue/conflicting-values");
+        assert_eq!(run["results"][0]["locations"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            run["results"][0]["relatedLocations"][0]["physicalLocation"]["region"]["startLine"],
+            2
+        );
+        assert_eq!(run["results"][1]["ruleId"], "cue/incomplete-value");
+        assert_eq!(run["results"][1]["locations"], serde_json::json!([]));
+        assert!(run["results"][1].get("relatedLocations").is_none());
+    }
+
+    #[test_case("a: 1\na: 2" => "cue/conflicting-values"; "conflict")]
+    #[test_case("#A: {a: int}\nb: #A & {c: 1}" => "cue/field-not-allowed"; "closed")]
+    #[test_case("a: int & >10\na: 5" => "cue/out-of-bound"; "bound")]
+    #[test_case("import \"strings\"\na: strings.MinRunes(3)\na: \"ab\"" => "cue/invalid-value"; "invalid")]
+    #[test_case("a: b" => "cue/reference-not-found"; "reference")]
+    #[test_case("a: }" => "cue/syntax-error"; "syntax")]
+    fn test_rule_id_of_evaluator(src: &str) -> &'static str {
+        let ctx = crate::Ctx::new().unwrap();
+        let err = crate::Value::compile_string(&ctx, src)
+            .and_then(|v| v.is_valid())
+            .unwrap_err();
+        Report::from(&err).diagnostics()[0].rule_id()
+    }
+
+    #[test]
+    fn test_rule_id_of_incomplete_export() {
+        let ctx = crate::Ctx::new().unwrap();
+        let err = crate::Value::compile_string(&ctx, "a: int")
+            .unwrap()
+            .to_json_vec()
+            .unwrap_err();
+        assert_eq!(
+            Report::from(&err).diagnostics()[0].rule_id(),
+            "cue/incomplete-value"
+        );
+    }
+
+    #[test]
+    fn test_code_climate_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&Report::parse(MULTI).to_code_climate_json()).unwrap();
+        assert_eq!(json[0]["description"], "a: conflicting values 2 and 1");
+        assert_eq!(json[0]["location"]["path"], "x.cue");
+        assert_eq!(json[0]["location"]["lines"]["begin"], 1);
+        assert_eq!(json[1]["location"]["path"], "");
+        assert_ne!(json[0]["fingerprint"], json[1]["fingerprint"]);
+    }
+}
@@ -0,0 +1,346 @@
+//! Per-field outcome of validating a document, for UIs and API responses.
+
+use super::{Diagnostic, Report, json_string, locate::locate, lookup_selector, path_labels};
+use crate::{Ctx, Path, Value, error::Error};
+
+/// How serious a [`Violation`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// The document contradicts the schema.
+    Error,
+    /// The document does not contradict the schema but leaves a value
+    /// incomplete, such as a required field it does not set.
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        })
+    }
+}
+
+/// A single constraint violation of a [`ValidationReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// CUE path of the failing field, if reported.
+    pub path: Option<String>,
+    /// The path as a JSON Pointer, see [`Diagnostic::json_pointer`].
+    pub pointer: Option<String>,
+    /// The constraint the field failed, such as `>=0` or `string`, if it
+    /// could be told from the message.
+    pub expected: Option<String>,
+    /// JSON encoding of the value the document gives the field, if any.
+    pub actual: Option<String>,
+    /// Human readable error message, without the path prefix.
+    pub message: String,
+    /// How serious the violation is.
+    pub severity: Severity,
+    /// 1-based line and byte column of the failing field in the validated
+    /// document, if known; see [`ValidationReport::with_document`].
+    pub location: Option<(usize, usize)>,
+}
+
+/// The outcome of validating a document against a schema, listing every
+/// violation found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Violations in the order CUE reported them.
+    violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Builds a report from a validation error, looking the actual values
+    /// up in `data`, the document that was validated, if given.
+    #[must_use]
+    pub fn from_error(
+        err: &Error,
+        data: Option<&Value>,
+    ) -> Self {
+        let violations = Report::from(err)
+            .diagnostics()
+            .iter()
+            .map(|d| violation(d, data))
+            .collect();
+        Self { violations }
+    }
+
+    /// Locates the violations in `document`, the JSON or YAML text that was
+    /// validated, as [`Report::with_document`] does.
+    #[must_use]
+    pub fn with_document(
+        mut self,
+        document: &str,
+    ) -> Self {
+        for v in &mut self.violations {
+            if let Some(path) = &v.path {
+                v.location = locate(document, &path_labels(path));
+            }
+        }
+        self
+    }
+
+    /// Returns `true` if no violation was found.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Returns `true` if a violation of [`Severity::Error`] was found.
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        self.violations
+            .iter()
+            .any(|v| v.severity == Severity::Error)
+    }
+
+    /// Returns the violations of this report.
+    #[must_use]
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Returns an iterator over the violations of this report.
+    pub fn iter(&self) -> std::slice::Iter<'_, Violation> {
+        self.violations.iter()
+    }
+
+    /// Renders the report as a JSON object of the form
+    /// `{"valid":false,"violations":[{"path":..,"pointer":..,..}]}`.
+    ///
+    /// Located violations carry `line` and `column` members.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let opt = |s: &Option<String>| s.as_deref().map_or("null".to_string(), json_string);
+        let location = |v: &Violation| {
+            v.location.map_or(String::new(), |(line, column)| {
+                format!(r#","line":{line},"column":{column}"#)
+            })
+        };
+        let violations: Vec<String> = self
+            .violations
+            .iter()
+            .map(|v| {
+                format!(
+                    r#"{{"path":{},"pointer":{},"expected":{},"actual":{},"message":{},"severity":"{}"{}}}"#,
+                    opt(&v.path),
+                    opt(&v.pointer),
+                    opt(&v.expected),
+                    v.actual.as_deref().unwrap_or("null"),
+                    json_string(&v.message),
+                    v.severity,
+                    location(v),
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"valid":{},"violations":[{}]}}"#,
+            self.is_ok(),
+            violations.join(",")
+        )
+    }
+}
+
+impl FromIterator<Violation> for ValidationReport {
+    fn from_iter<I: IntoIterator<Item = Violation>>(iter: I) -> Self {
+        Self {
+            violations: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a ValidationReport {
+    type IntoIter = std::slice::Iter<'a, Violation>;
+    type Item = &'a Violation;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Value {
+    /// Validates the JSON document `data` against this schema as
+    /// [`Value::validate_json`] does, reporting every violation located in
+    /// `data`.
+    ///
+    /// `ctx` must be the context the schema was compiled in.
+    #[must_use]
+    pub fn validate_json_report(
+        &self,
+        ctx: &Ctx,
+        data: &[u8],
+    ) -> ValidationReport {
+        let document = String::from_utf8_lossy(data);
+        let data = match Self::compile_json(ctx, data) {
+            Ok(data) => data,
+            Err(err) => return ValidationReport::from_error(&err, None),
+        };
+        match self.validate_data(&data, crate::value::ValidateOptions::default()) {
+            Ok(_) => ValidationReport::default(),
+            Err(err) => ValidationReport::from_error(&err, Some(&data)).with_document(&document),
+        }
+    }
+}
+
+/// Builds the violation for a diagnostic.
+fn violation(
+    d: &Diagnostic,
+    data: Option<&Value>,
+) -> Violation {
+    let actual = d
+        .path
+        .as_deref()
+        .zip(data)
+        .and_then(|(path, data)| lookup_json(data, path));
+    let incomplete = d.rule_id() == "cue/incomplete-value";
+    Violation {
+        path: d.path.clone(),
+        pointer: d.json_pointer(),
+        expected: expected(&d.message, actual.as_deref()),
+        actual,
+        message: d.message.clone(),
+        severity: if incomplete {
+            Severity::Warning
+        } else {
+            Severity::Error
+        },
+        location: None,
+    }
+}
+
+/// Returns the JSON encoding of the value at the error path `path` of
+/// `data`, if it has one.
+fn lookup_json(
+    data: &Value,
+    path: &str,
+) -> Option<String> {
+    let lookup = lookup_selector(path);
+    let value = data.lookup_path(&Path::new(&lookup).ok()?).ok()?;
+    String::from_utf8(value.to_json_vec().ok()?).ok()
+}
+
+/// Extracts the failed constraint from a CUE error message.
+///
+/// `actual` is the JSON encoding of the document's value, used to tell the
+/// constraint from the value in `conflicting values` messages.
+fn expected(
+    message: &str,
+    actual: Option<&str>,
+) -> Option<String> {
+    let paren = |s: &str| {
+        let (_, tail) = s.split_once(" (")?;
+        tail.strip_suffix(')').map(str::to_string)
+    };
+    if let Some(rest) = message.strip_prefix("invalid value ") {
+        let inner = paren(rest)?;
+        let constraint = inner
+            .strip_prefix("out of bound ")
+            .or_else(|| inner.strip_prefix("does not satisfy "))
+            .unwrap_or(&inner);
+        return Some(constraint.to_string());
+    }
+    if let Some(rest) = message.strip_prefix("incomplete value ") {
+        return Some(rest.to_string());
+    }
+    if let Some(rest) = message.strip_prefix("conflicting values ") {
+        let values = rest.split_once(" (").map_or(rest, |(values, _)| values);
+        let (a, b) = values.split_once(" and ")?;
+        return Some(if Some(a) == actual { b } else { a }.to_string());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{Severity, ValidationReport, Violation, expected};
+    use crate::{Ctx, Value};
+
+    #[test_case("invalid value -1 (out of bound >=0)", None              => Some(">=0".to_string());    "bound")]
+    #[test_case(r#"invalid value "x" (does not satisfy strings.MinRunes(2))"#, None => Some("strings.MinRunes(2)".to_string()); "validator")]
+    #[test_case("incomplete value string", None                          => Some("string".to_string()); "incomplete")]
+    #[test_case(
+        "conflicting values 42 and string (mismatched types int and string)", Some("42")
+        => Some("string".to_string());
+        "conflict value first"
+    )]
+    #[test_case(
+        "conflicting values string and 42 (mismatched types string and int)", Some("42")
+        => Some("string".to_string());
+        "conflict value second"
+    )]
+    #[test_case("field not allowed", None                                => None;                       "other")]
+    fn test_expected(
+        message: &str,
+        actual: Option<&str>,
+    ) -> Option<String> {
+        expected(message, actual)
+    }
+
+    #[test]
+    fn test_to_json() {
+        let report = ValidationReport {
+            violations: vec![Violation {
+                path: Some("a.b".to_string()),
+                pointer: Some("/a/b".to_string()),
+                expected: Some(">=0".to_string()),
+                actual: Some("-1".to_string()),
+                message: "invalid value -1 (out of bound >=0)".to_string(),
+                severity: Severity::Error,
+                location: Some((3, 7)),
+            }],
+        };
+        assert!(!report.is_ok());
+        assert!(report.has_errors());
+        assert_eq!(
+            report.to_json(),
+            r#"{"valid":false,"violations":[{"path":"a.b","pointer":"/a/b","expected":">=0","actual":-1,"message":"invalid value -1 (out of bound >=0)","severity":"error","line":3,"column":7}]}"#
+        );
+        assert_eq!(
+            ValidationReport::default().to_json(),
+            r#"{"valid":true,"violations":[]}"#
+        );
+    }
+
+    #[test]
+    fn test_validate_json_report() {
+        let ctx = Ctx::new().unwrap();
+        let schema =
+            Value::compile_string(&ctx, "{ name: string, items: [...{ n: int & >=0 }] }").unwrap();
+        assert!(
+            schema
+                .validate_json_report(&ctx, br#"{ "name": "a", "items": [] }"#)
+                .is_ok()
+        );
+
+        let report =
+            schema.validate_json_report(&ctx, br#"{ "name": "a", "items": [{ "n": -1 }] }"#);
+        let violations: Vec<_> = report
+            .iter()
+            .map(|v| (v.pointer.as_deref(), v.actual.as_deref(), v.location))
+            .collect();
+        assert_eq!(violations, [(
+            Some("/items/0/n"),
+            Some("-1"),
+            Some((1, 33))
+        )]);
+
+        let report = schema.validate_json_report(&ctx, br#"{ "items": [] }"#);
+        let violations: Vec<_> = report
+            .iter()
+            .map(|v| (v.pointer.as_deref(), v.expected.as_deref(), v.severity))
+            .collect();
+        assert_eq!(violations, [(
+            Some("/name"),
+            Some("string"),
+            Severity::Warning
+        )]);
+        assert!(!report.has_errors());
+    }
+}
@@ -0,0 +1,71 @@
+//! A small deterministic random number generator shared by the data
+//! generators.
+
+/// A splitmix64 random number generator.
+#[derive(Debug)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    /// Creates a generator from `seed`.
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Returns the next random number.
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a random number in `0..n`, or `0` if `n` is `0`.
+    pub(crate) fn below(
+        &mut self,
+        n: u64,
+    ) -> u64 {
+        self.next().checked_rem(n).unwrap_or(0)
+    }
+
+    /// Returns a random index into a collection of `len` elements.
+    pub(crate) fn index(
+        &mut self,
+        len: usize,
+    ) -> usize {
+        usize::try_from(self.below(len as u64)).unwrap_or(0)
+    }
+
+    /// Returns `true` with probability `1 / n`.
+    pub(crate) fn one_in(
+        &mut self,
+        n: u64,
+    ) -> bool {
+        self.below(n) == 0
+    }
+
+    /// Returns a random integer in `lo..=hi`.
+    pub(crate) fn range(
+        &mut self,
+        lo: i64,
+        hi: i64,
+    ) -> i64 {
+        lo.saturating_add_unsigned(self.below(hi.abs_diff(lo).saturating_add(1)))
+    }
+
+    /// Returns a random word of lowercase ASCII letters.
+    pub(crate) fn word(&mut self) -> String {
+        let len = self.range(1, 8);
+        self.letters(usize::try_from(len).unwrap_or(1))
+    }
+
+    /// Returns `len` random lowercase ASCII letters.
+    pub(crate) fn letters(
+        &mut self,
+        len: usize,
+    ) -> String {
+        (0..len)
+            .map(|_| char::from(b'a'.saturating_add(u8::try_from(self.below(26)).unwrap_or(0))))
+            .collect()
+    }
+}
@@ -0,0 +1,190 @@
+//! Schema selection by a discriminator field of the data.
+//!
+//! Services accepting several message types usually tag each message with a
+//! field such as `kind` or `type` and validate it against the schema for that
+//! type.  A [`SchemaRouter`] reads the tag and picks the definition from a
+//! compiled package:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! use cue_rs::{Ctx, Value, router::SchemaRouter};
+//!
+//! let ctx = Ctx::new()?;
+//! let package = Value::compile_string(
+//!     &ctx,
+//!     r#"
+//!     #Created: { type: "Created", id: int }
+//!     #Deleted: { type: "Deleted", id: int, reason?: string }
+//!     "#,
+//! )?;
+//! let router = SchemaRouter::new(package, "type")?;
+//! router.validate(&Value::compile_string(
+//!     &ctx,
+//!     r#"{ type: "Created", id: 1 }"#,
+//! )?)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Path, Value, error::Error};
+
+/// Routes data to the definition of a package matching its discriminator.
+///
+/// A string discriminator `T` selects the route registered for it with
+/// [`SchemaRouter::with_route`], or else the definition `#T` of the package.
+#[derive(Debug)]
+pub struct SchemaRouter {
+    /// Compiled package holding the definitions.
+    package: Value,
+    /// Path of the discriminator field in the data.
+    discriminator: Path,
+    /// Explicit routes from a discriminator value to a definition path.
+    routes: Vec<(String, Path)>,
+}
+
+impl SchemaRouter {
+    /// Creates a router over the definitions of `package`, reading the
+    /// discriminator at `discriminator` (e.g. `kind` or `meta.type`).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StringContainsNul`] if `discriminator` contains
+    /// interior nul bytes.
+    pub fn new(
+        package: Value,
+        discriminator: &str,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            package,
+            discriminator: Path::new(discriminator)?,
+            routes: Vec::new(),
+        })
+    }
+
+    /// Routes data whose discriminator is `value` to the definition at
+    /// `definition` (e.g. `#DeploymentV1`), for values that are not
+    /// definition names such as `apps/v1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StringContainsNul`] if `definition` contains interior
+    /// nul bytes.
+    pub fn with_route(
+        mut self,
+        value: impl Into<String>,
+        definition: &str,
+    ) -> Result<Self, Error> {
+        self.routes.push((value.into(), Path::new(definition)?));
+        Ok(self)
+    }
+
+    /// Returns the definition `data` is routed to.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnknownType`] if `data` has no discriminator field,
+    /// reported with the value `_|_`, or if the discriminator is not a
+    /// concrete string matching a definition.
+    pub fn route(
+        &self,
+        data: &Value,
+    ) -> Result<Value, Error> {
+        let unknown_type = |value: String| {
+            Error::UnknownType {
+                path: self.discriminator.to_string(),
+                value,
+            }
+        };
+        let tag = data
+            .lookup_path(&self.discriminator)
+            .map_err(|_| unknown_type("_|_".to_string()))?;
+        let unknown = || {
+            unknown_type(tag.to_json_vec().map_or_else(
+                |_| "_".to_string(),
+                |json| String::from_utf8_lossy(&json).into_owned(),
+            ))
+        };
+        let Ok(name) = tag.to_string() else {
+            return Err(unknown());
+        };
+        if let Some((_, definition)) = self.routes.iter().find(|(value, _)| *value == name) {
+            return self.package.lookup_path(definition);
+        }
+        if !is_identifier(&name) {
+            return Err(unknown());
+        }
+        self.package.lookup_def(&name).map_err(|_| unknown())
+    }
+
+    /// Validates `data` against the definition it is routed to and returns
+    /// their unification.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`SchemaRouter::route`], or [`Error::Cue`] if
+    /// the data does not satisfy the definition.
+    pub fn validate(
+        &self,
+        data: &Value,
+    ) -> Result<Value, Error> {
+        let value = Value::unify(&self.route(data)?, data);
+        value.is_valid()?;
+        Ok(value)
+    }
+}
+
+/// Returns `true` if `name` is a CUE identifier, so `#name` is a definition
+/// label.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{SchemaRouter, is_identifier};
+    use crate::{Ctx, Value, error::Error};
+
+    #[test_case("Created" => true; "name")]
+    #[test_case("_v1" => true; "underscore")]
+    #[test_case("1a" => false; "leading digit")]
+    #[test_case("apps/v1" => false; "slash")]
+    #[test_case("" => false; "empty")]
+    fn test_is_identifier(name: &str) -> bool {
+        is_identifier(name)
+    }
+
+    #[test_case(r#"{ type: "Created", id: 1 }"# => Ok(()); "created")]
+    #[test_case(r#"{ type: "Deleted", id: 1, reason: "spam" }"# => Ok(()); "deleted")]
+    #[test_case(r#"{ type: "v2/Created", id: 1 }"# => Ok(()); "explicit route")]
+    #[test_case(r#"{ type: "Created", id: "1" }"# => Err("invalid".to_string()); "invalid")]
+    #[test_case(r#"{ type: "Updated", id: 1 }"# => Err(r#"no schema for type = "Updated""#.to_string()); "unknown")]
+    #[test_case("{ type: 3, id: 1 }" => Err("no schema for type = 3".to_string()); "not a string")]
+    #[test_case("{ type: string, id: 1 }" => Err("no schema for type = _".to_string()); "not concrete")]
+    #[test_case("{ id: 1 }" => Err("no schema for type = _|_".to_string()); "missing")]
+    fn test_validate(data: &str) -> Result<(), String> {
+        let ctx = Ctx::new().unwrap();
+        let package = Value::compile_string(
+            &ctx,
+            r#"
+            #Created: { type: "Created" | "v2/Created", id: int }
+            #Deleted: { type: "Deleted", id: int, reason?: string }
+            "#,
+        )
+        .unwrap();
+        let router = SchemaRouter::new(package, "type")
+            .unwrap()
+            .with_route("v2/Created", "#Created")
+            .unwrap();
+        match router.validate(&Value::compile_string(&ctx, data).unwrap()) {
+            Ok(_) => Ok(()),
+            Err(err @ Error::UnknownType { .. }) => Err(err.to_string()),
+            Err(_) => Err("invalid".to_string()),
+        }
+    }
+}
@@ -0,0 +1,484 @@
+//! Deterministic random schemas for load and regression testing.
+//!
+//! [`generate_schema`] builds a random but valid CUE schema from a seed, and
+//! the returned [`Schema`] produces JSON documents that satisfy it or that
+//! violate exactly one of its constraints.  The same seed always yields the
+//! same schema and documents, so a benchmark or a failing case can be
+//! replayed across libcue upgrades:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! use cue_rs::{Ctx, Value, stress};
+//!
+//! let ctx = Ctx::new()?;
+//! let schema = stress::generate_schema(42, 20);
+//! let package = Value::compile_string(&ctx, schema.source())?;
+//! let definition = package.lookup(stress::DEFINITION)?;
+//! let data = Value::compile_string(&ctx, &schema.valid(1))?;
+//! Value::unify(&definition, &data).is_valid()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write as _;
+
+use crate::{report::json_string, rng::Rng};
+
+/// Name of the definition holding the generated schema.
+pub const DEFINITION: &str = "#Schema";
+
+/// Maximum nesting depth of generated structs and lists.
+const MAX_DEPTH: usize = 3;
+
+/// A generated schema.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    /// Fields of the top-level definition.
+    fields: Vec<Field>,
+    /// CUE source of the schema.
+    source: String,
+}
+
+/// Generates a random schema with `complexity` fields in total (at least
+/// one), spread over nested structs and lists.
+#[must_use]
+pub fn generate_schema(
+    seed: u64,
+    complexity: usize,
+) -> Schema {
+    let mut rng = Rng::new(seed);
+    let mut budget = complexity.max(1);
+    let mut fields = Vec::new();
+    while budget > 0 {
+        fields.extend(rng.fields(&mut budget, 0, fields.len()));
+    }
+    let mut source = format!("{DEFINITION}: ");
+    render_struct(&fields, 0, &mut source);
+    source.push('\n');
+    Schema { fields, source }
+}
+
+impl Schema {
+    /// Returns the CUE source of the schema, defining [`DEFINITION`].
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Generates a JSON document satisfying the schema.
+    #[must_use]
+    pub fn valid(
+        &self,
+        seed: u64,
+    ) -> String {
+        let mut rng = Rng::new(seed);
+        rng.data_struct(&self.fields, 0).to_json()
+    }
+
+    /// Generates a JSON document violating exactly one constraint of the
+    /// schema: a value of the wrong type or out of range, a missing required
+    /// field, or a field the closed definition does not allow.
+    #[must_use]
+    pub fn near_miss(
+        &self,
+        seed: u64,
+    ) -> String {
+        let mut rng = Rng::new(seed);
+        let ty = Type::Struct(self.fields.clone());
+        let mut data = rng.data(&ty, 0);
+        let mut target = rng.below(sites(&data, &ty));
+        rng.mutate(&mut data, &ty, &mut target);
+        data.to_json()
+    }
+}
+
+/// A field of a generated struct.
+#[derive(Debug, Clone)]
+struct Field {
+    /// Label of the field.
+    name: String,
+    /// Whether the field is optional.
+    optional: bool,
+    /// Type of the field.
+    ty: Type,
+}
+
+/// Type of a generated field.
+#[derive(Debug, Clone)]
+enum Type {
+    /// An integer in `min..=max`.
+    Int {
+        /// Lower bound.
+        min: i64,
+        /// Upper bound.
+        max: i64,
+    },
+    /// Any number.
+    Number,
+    /// A non-empty string of lowercase ASCII letters.
+    Word,
+    /// A boolean.
+    Bool,
+    /// One of a few string literals.
+    Enum(Vec<String>),
+    /// A list of elements of the given type.
+    List(Box<Type>),
+    /// A closed struct.
+    Struct(Vec<Field>),
+}
+
+/// A generated JSON value.
+#[derive(Debug, Clone)]
+enum Data {
+    /// A number, already formatted.
+    Number(String),
+    /// A string.
+    String(String),
+    /// A boolean.
+    Bool(bool),
+    /// A list.
+    List(Vec<Data>),
+    /// An object.
+    Object(Vec<(String, Data)>),
+}
+
+impl Data {
+    /// Encodes the value as JSON.
+    fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    /// Appends the JSON encoding of the value to `out`.
+    fn write_json(
+        &self,
+        out: &mut String,
+    ) {
+        match self {
+            Self::Number(n) => out.push_str(n),
+            Self::String(s) => out.push_str(&json_string(s)),
+            Self::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Self::List(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    item.write_json(out);
+                }
+                out.push(']');
+            },
+            Self::Object(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    out.push_str(&json_string(key));
+                    out.push(':');
+                    value.write_json(out);
+                }
+                out.push('}');
+            },
+        }
+    }
+}
+
+/// Appends the CUE source of a struct with `fields`, indented `depth` tabs.
+fn render_struct(
+    fields: &[Field],
+    depth: usize,
+    out: &mut String,
+) {
+    out.push_str("{\n");
+    let indent = "\t".repeat(depth.saturating_add(1));
+    for field in fields {
+        out.push_str(&indent);
+        out.push_str(&field.name);
+        if field.optional {
+            out.push('?');
+        }
+        out.push_str(": ");
+        render_type(&field.ty, depth.saturating_add(1), out);
+        out.push('\n');
+    }
+    out.push_str(&"\t".repeat(depth));
+    out.push('}');
+}
+
+/// Appends the CUE source of `ty`, nested `depth` levels deep.
+fn render_type(
+    ty: &Type,
+    depth: usize,
+    out: &mut String,
+) {
+    match ty {
+        Type::Int { min, max } => {
+            let _ = write!(out, "int & >={min} & <={max}");
+        },
+        Type::Number => out.push_str("number"),
+        Type::Word => out.push_str(r#"string & =~"^[a-z]+$""#),
+        Type::Bool => out.push_str("bool"),
+        Type::Enum(members) => {
+            let members: Vec<String> = members.iter().map(|m| json_string(m)).collect();
+            out.push_str(&members.join(" | "));
+        },
+        Type::List(element) => {
+            out.push_str("[...");
+            render_type(element, depth, out);
+            out.push(']');
+        },
+        Type::Struct(fields) => render_struct(fields, depth, out),
+    }
+}
+
+/// Returns the number of places in `data` where [`Rng::mutate`] can
+/// introduce an error.
+fn sites(
+    data: &Data,
+    ty: &Type,
+) -> u64 {
+    let nested = match (data, ty) {
+        (Data::List(items), Type::List(element)) => {
+            items.iter().map(|item| sites(item, element)).sum()
+        },
+        (Data::Object(entries), Type::Struct(fields)) => {
+            entries
+                .iter()
+                .zip(
+                    fields
+                        .iter()
+                        .filter(|f| entries.iter().any(|(k, _)| *k == f.name)),
+                )
+                .map(|((_, value), field)| sites(value, &field.ty))
+                .sum()
+        },
+        _ => 0,
+    };
+    nested.saturating_add(1)
+}
+
+impl Rng {
+    /// Generates up to four fields of a struct nested `depth` levels deep,
+    /// named from `first` on, taking each from `budget`.
+    fn fields(
+        &mut self,
+        budget: &mut usize,
+        depth: usize,
+        first: usize,
+    ) -> Vec<Field> {
+        let count = self.index(4).saturating_add(1).min(*budget);
+        let mut fields = Vec::with_capacity(count);
+        for i in 0..count {
+            if *budget == 0 {
+                break;
+            }
+            *budget = budget.saturating_sub(1);
+            fields.push(Field {
+                name: format!("f{}", first.saturating_add(i)),
+                optional: self.one_in(4),
+                ty: self.ty(budget, depth),
+            });
+        }
+        fields
+    }
+
+    /// Generates a field type nested `depth` levels deep.
+    fn ty(
+        &mut self,
+        budget: &mut usize,
+        depth: usize,
+    ) -> Type {
+        let nested = depth < MAX_DEPTH && *budget > 0;
+        match self.below(8) {
+            0 if nested => Type::Struct(self.fields(budget, depth.saturating_add(1), 0)),
+            1 if nested => Type::List(Box::new(self.ty(budget, depth.saturating_add(1)))),
+            0..=2 => {
+                let min = self.range(-100, 0);
+                let max = self.range(0, 1000);
+                Type::Int { min, max }
+            },
+            3 => Type::Number,
+            4 => Type::Bool,
+            5 => {
+                let len = self.range(2, 5);
+                Type::Enum((0..len).map(|i| format!("v{i}")).collect())
+            },
+            _ => Type::Word,
+        }
+    }
+
+    /// Generates a value of type `ty` nested `depth` levels deep.
+    fn data(
+        &mut self,
+        ty: &Type,
+        depth: usize,
+    ) -> Data {
+        match ty {
+            Type::Int { min, max } => Data::Number(self.range(*min, *max).to_string()),
+            Type::Number => Data::Number(format!("{}.5", self.range(-1000, 1000))),
+            Type::Word => Data::String(self.word()),
+            Type::Bool => Data::Bool(self.one_in(2)),
+            Type::Enum(members) => {
+                Data::String(
+                    members
+                        .get(self.index(members.len()))
+                        .cloned()
+                        .unwrap_or_default(),
+                )
+            },
+            Type::List(element) => {
+                let len = if depth < MAX_DEPTH { self.index(4) } else { 0 };
+                Data::List(
+                    (0..len)
+                        .map(|_| self.data(element, depth.saturating_add(1)))
+                        .collect(),
+                )
+            },
+            Type::Struct(fields) => self.data_struct(fields, depth),
+        }
+    }
+
+    /// Generates an object with the required and some of the optional
+    /// `fields`.
+    fn data_struct(
+        &mut self,
+        fields: &[Field],
+        depth: usize,
+    ) -> Data {
+        let mut entries = Vec::with_capacity(fields.len());
+        for field in fields {
+            if !field.optional || self.one_in(2) {
+                entries.push((
+                    field.name.clone(),
+                    self.data(&field.ty, depth.saturating_add(1)),
+                ));
+            }
+        }
+        Data::Object(entries)
+    }
+
+    /// Introduces an error at the `target`-th site of `data` in pre-order,
+    /// as counted by [`sites`].
+    ///
+    /// Returns `true` once the error was introduced.
+    fn mutate(
+        &mut self,
+        data: &mut Data,
+        ty: &Type,
+        target: &mut u64,
+    ) -> bool {
+        if *target == 0 {
+            *data = self.invalid(data, ty);
+            return true;
+        }
+        *target = target.saturating_sub(1);
+        match (data, ty) {
+            (Data::List(items), Type::List(element)) => {
+                items
+                    .iter_mut()
+                    .any(|item| self.mutate(item, element, target))
+            },
+            (Data::Object(entries), Type::Struct(fields)) => {
+                entries.iter_mut().any(|(key, value)| {
+                    fields
+                        .iter()
+                        .find(|f| f.name == *key)
+                        .is_some_and(|field| self.mutate(value, &field.ty, target))
+                })
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns a variant of `data` that does not satisfy `ty`.
+    fn invalid(
+        &mut self,
+        data: &Data,
+        ty: &Type,
+    ) -> Data {
+        let wrong_type = self.one_in(2);
+        match ty {
+            Type::Int { max, .. } if !wrong_type => {
+                max.checked_add(1).map_or_else(
+                    || Data::String("NaN".to_string()),
+                    |n| Data::Number(n.to_string()),
+                )
+            },
+            Type::Int { .. } | Type::Number | Type::Enum(_) => Data::Bool(true),
+            Type::Word if !wrong_type => Data::String("Not-A-Word".to_string()),
+            Type::Word => Data::Number("1".to_string()),
+            Type::Bool => Data::String("true".to_string()),
+            Type::List(_) => Data::String("[]".to_string()),
+            Type::Struct(fields) => {
+                let Data::Object(mut entries) = data.clone() else {
+                    return Data::List(Vec::new());
+                };
+                let required: Vec<usize> = entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (key, _))| fields.iter().any(|f| f.name == *key && !f.optional))
+                    .map(|(i, _)| i)
+                    .collect();
+                if !wrong_type && let Some(&i) = required.get(self.index(required.len())) {
+                    entries.remove(i);
+                } else {
+                    entries.push(("unknown".to_string(), Data::Number("1".to_string())));
+                }
+                Data::Object(entries)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DEFINITION, generate_schema};
+    use crate::{Ctx, Value};
+
+    #[test]
+    fn test_deterministic() {
+        let a = generate_schema(7, 30);
+        let b = generate_schema(7, 30);
+        assert_eq!(a.source(), b.source());
+        assert_eq!(a.valid(1), b.valid(1));
+        assert_eq!(a.near_miss(1), b.near_miss(1));
+        assert_ne!(a.source(), generate_schema(8, 30).source());
+    }
+
+    #[test]
+    fn test_complexity() {
+        let count = |seed| generate_schema(seed, 25).source().matches(": ").count();
+        for seed in 0..20 {
+            assert_eq!(count(seed), 26, "seed {seed}");
+        }
+        assert!(generate_schema(0, 0).source().contains("f0"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let ctx = Ctx::new().unwrap();
+        for seed in 0..20 {
+            let schema = generate_schema(seed, 15);
+            let definition = Value::compile_string(&ctx, schema.source())
+                .unwrap()
+                .lookup(DEFINITION)
+                .unwrap();
+            for data_seed in 0..5 {
+                let valid = Value::compile_string(&ctx, &schema.valid(data_seed)).unwrap();
+                assert!(
+                    Value::unify(&definition, &valid).is_valid().is_ok(),
+                    "seed {seed}/{data_seed}: {}",
+                    schema.valid(data_seed)
+                );
+                let invalid = Value::compile_string(&ctx, &schema.near_miss(data_seed)).unwrap();
+                assert!(
+                    Value::unify(&definition, &invalid).is_valid().is_err(),
+                    "seed {seed}/{data_seed}: {}",
+                    schema.near_miss(data_seed)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Validation of many documents against one schema.
+//!
+//! A [`Validator`] holds the source of a schema and validates documents
+//! against it, compiling the schema once per batch rather than once per
+//! document:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), cue_rs::error::Error> {
+//! use cue_rs::{Ctx, validator::Validator};
+//!
+//! let ctx = Ctx::new()?;
+//! let validator = Validator::new("{ port: int & >0 }");
+//! let results = validator.validate_batch(&ctx, &[r#"{ "port": 80 }"#, r#"{ "port": 0 }"#])?;
+//! assert!(results[0].is_ok());
+//! assert!(results[1].is_err());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With the `rayon` feature, `Validator::validate_batch_par` spreads a
+//! batch over the rayon thread pool.  libcue values are not shared between
+//! threads, so each worker thread compiles its own copy of the schema into the
+//! default context (see [`Ctx::global`]) the first time it validates against
+//! it, and keeps it for later batches.
+
+#[cfg(any(feature = "rayon", feature = "actix-web"))]
+use std::{cell::RefCell, rc::Rc};
+
+#[cfg(any(feature = "rayon", feature = "actix-web"))]
+use crate::cache::SchemaCache;
+use crate::{Ctx, Value, error::Error};
+
+#[cfg(any(feature = "rayon", feature = "actix-web"))]
+thread_local! {
+    /// Schemas compiled on this thread, in the default context.
+    static SCHEMAS: RefCell<Option<SchemaCache<'static>>> = const { RefCell::new(None) };
+}
+
+/// Validates documents against a schema.
+#[derive(Debug, Clone)]
+pub struct Validator {
+    /// CUE source of the schema.
+    schema: String,
+}
+
+impl Validator {
+    /// Creates a validator for the CUE schema `schema`.
+    ///
+    /// The schema is compiled on use, so a schema that does not compile is
+    /// reported by the validation methods.
+    #[must_use]
+    pub fn new(schema: impl Into<String>) -> Self {
+        Self {
+            schema: schema.into(),
+        }
+    }
+
+    /// Returns the source of the schema.
+    #[must_use]
+    pub fn schema(&self) -> &str {
+        &self.schema
+    }
+
+    /// Validates the CUE or JSON document `data` against the schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the schema or the document cannot be
+    /// compiled, or the document does not satisfy the schema.
+    pub fn validate(
+        &self,
+        ctx: &Ctx,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        validate(&self.compile(ctx)?, ctx, data)
+    }
+
+    /// Validates every document of `docs`, returning one result per
+    /// document in the same order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the schema cannot be compiled.
+    pub fn validate_batch(
+        &self,
+        ctx: &Ctx,
+        docs: &[impl AsRef<[u8]>],
+    ) -> Result<Vec<Result<(), Error>>, Error> {
+        let schema = self.compile(ctx)?;
+        Ok(docs
+            .iter()
+            .map(|doc| validate(&schema, ctx, doc.as_ref()))
+            .collect())
+    }
+
+    /// Validates every document of `docs` on the rayon thread pool,
+    /// returning one result per document in the same order.
+    ///
+    /// Documents are compiled in the default context by the worker thread
+    /// validating them, against the copy of the schema compiled on that
+    /// thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the schema cannot be compiled, or
+    /// [`Error::ContextCreationFailed`] if the default context cannot be
+    /// created.
+    #[cfg(feature = "rayon")]
+    pub fn validate_batch_par(
+        &self,
+        docs: &[impl AsRef<[u8]> + Sync],
+    ) -> Result<Vec<Result<(), Error>>, Error> {
+        use rayon::prelude::*;
+
+        self.compile_global()?;
+        Ok(docs
+            .par_iter()
+            .map_init(
+                || Ctx::global().and_then(|ctx| Ok((ctx, self.compile_global()?))),
+                |schema, doc| {
+                    match schema {
+                        Ok((ctx, schema)) => validate(schema, ctx, doc.as_ref()),
+                        Err(_) => Err(Error::ContextCreationFailed),
+                    }
+                },
+            )
+            .collect())
+    }
+
+    /// Returns the schema compiled in the default context by this thread,
+    /// compiling it on the thread's first use.
+    #[cfg(any(feature = "rayon", feature = "actix-web"))]
+    pub(crate) fn compile_global(&self) -> Result<Rc<Value>, Error> {
+        let ctx = Ctx::global()?;
+        SCHEMAS.with_borrow_mut(|cache| {
+            cache
+                .get_or_insert_with(|| SchemaCache::new(ctx))
+                .compile_string(&self.schema)
+        })
+    }
+
+    /// Compiles the schema in `ctx`.
+    fn compile(
+        &self,
+        ctx: &Ctx,
+    ) -> Result<Value, Error> {
+        Value::compile_string(ctx, &self.schema)
+    }
+}
+
+/// Validates `data` against the compiled `schema`.
+fn validate(
+    schema: &Value,
+    ctx: &Ctx,
+    data: &[u8],
+) -> Result<(), Error> {
+    Value::unify(schema, &Value::compile_bytes(ctx, data)?).is_valid()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Validator;
+    use crate::Ctx;
+
+    const DOCS: [&str; 4] = [
+        r#"{ "port": 80 }"#,
+        r#"{ "port": 0 }"#,
+        r#"{ "port": "80" }"#,
+        "{ port: ",
+    ];
+
+    #[test]
+    fn test_validate_batch() {
+        let ctx = Ctx::new().unwrap();
+        let validator = Validator::new("{ port: int & >0 }");
+        let results = validator.validate_batch(&ctx, &DOCS).unwrap();
+        let valid: Vec<bool> = results.iter().map(Result::is_ok).collect();
+        assert_eq!(valid, [true, false, false, false]);
+        assert!(validator.validate(&ctx, DOCS[0].as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_validate_batch_bad_schema() {
+        let ctx = Ctx::new().unwrap();
+        assert!(
+            Validator::new("{ port: }")
+                .validate_batch(&ctx, &DOCS)
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_validate_batch_par() {
+        let validator = Validator::new("{ port: int & >0 }");
+        let docs: Vec<String> = (0..200)
+            .map(|i| format!(r#"{{ "port": {} }}"#, i % 2))
+            .collect();
+        let results = validator.validate_batch_par(&docs).unwrap();
+        let valid: Vec<bool> = results.iter().map(Result::is_ok).collect();
+        let expected: Vec<bool> = (0..200).map(|i| i % 2 == 1).collect();
+        assert_eq!(valid, expected);
+    }
+}
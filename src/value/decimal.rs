@@ -0,0 +1,159 @@
+//! Exact comparison of JSON number literals, as exported by libcue.
+
+use std::cmp::Ordering;
+
+/// A decimal number `±0.d₁d₂…dₙ × 10^exponent`, normalized so that equal
+/// numbers have equal representations.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct Decimal {
+    /// Whether the number is below zero; `false` for zero.
+    negative: bool,
+    /// Significant digits, without leading or trailing zeros; empty for zero.
+    digits: Vec<u8>,
+    /// Position of the decimal point relative to the first digit.
+    exponent: i64,
+}
+
+impl Decimal {
+    /// Parses a JSON number literal, returning `None` if `text` is not one.
+    pub(super) fn parse(text: &[u8]) -> Option<Self> {
+        let (negative, text) = match text.strip_prefix(b"-") {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (mantissa, exp) = match text.iter().position(|&b| b == b'e' || b == b'E') {
+            Some(at) => {
+                (
+                    text.get(..at)?,
+                    parse_exponent(text.get(at.saturating_add(1)..)?)?,
+                )
+            },
+            None => (text, 0),
+        };
+        let (int, frac) = match mantissa.iter().position(|&b| b == b'.') {
+            Some(at) => (mantissa.get(..at)?, mantissa.get(at.saturating_add(1)..)?),
+            None => (mantissa, &[][..]),
+        };
+        if int.is_empty() || !int.iter().chain(frac).all(u8::is_ascii_digit) {
+            return None;
+        }
+
+        let mut point = i64::try_from(int.len()).ok()?;
+        let mut digits: Vec<u8> = int
+            .iter()
+            .chain(frac)
+            .map(|d| d.saturating_sub(b'0'))
+            .collect();
+        let leading = digits.iter().take_while(|&&d| d == 0).count();
+        digits.drain(..leading);
+        point = point.saturating_sub(i64::try_from(leading).ok()?);
+        let trailing = digits.iter().rev().take_while(|&&d| d == 0).count();
+        digits.truncate(digits.len().saturating_sub(trailing));
+        if digits.is_empty() {
+            return Some(Self {
+                negative: false,
+                digits,
+                exponent: 0,
+            });
+        }
+        Some(Self {
+            negative,
+            digits,
+            exponent: point.saturating_add(exp),
+        })
+    }
+
+    /// Compares the absolute values of two numbers.
+    fn cmp_magnitude(
+        &self,
+        other: &Self,
+    ) -> Ordering {
+        match (self.digits.is_empty(), other.digits.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (false, false) => {
+                self.exponent
+                    .cmp(&other.exponent)
+                    .then_with(|| self.digits.cmp(&other.digits))
+            },
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(
+        &self,
+        other: &Self,
+    ) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.cmp_magnitude(other),
+            (true, true) => other.cmp_magnitude(self),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(
+        &self,
+        other: &Self,
+    ) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Parses the exponent of a number literal, saturating on overflow.
+fn parse_exponent(text: &[u8]) -> Option<i64> {
+    let (negative, digits) = match text.first() {
+        Some(b'-') => (true, text.get(1..)?),
+        Some(b'+') => (false, text.get(1..)?),
+        _ => (false, text),
+    };
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let magnitude = digits.iter().fold(0i64, |acc, d| {
+        acc.saturating_mul(10)
+            .saturating_add(i64::from(d.saturating_sub(b'0')))
+    });
+    Some(if negative {
+        magnitude.saturating_neg()
+    } else {
+        magnitude
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use test_case::test_case;
+
+    use super::Decimal;
+
+    #[test_case("1", "2"                 => Some(Ordering::Less);    "ints")]
+    #[test_case("10", "9"                => Some(Ordering::Greater); "digit count")]
+    #[test_case("1.50", "1.5"            => Some(Ordering::Equal);   "trailing zeros")]
+    #[test_case("0.1", "1e-1"            => Some(Ordering::Equal);   "exponent")]
+    #[test_case("1E+3", "999.9"          => Some(Ordering::Greater); "upper exponent")]
+    #[test_case("-0", "0.0"              => Some(Ordering::Equal);   "zeros")]
+    #[test_case("-2", "-10"              => Some(Ordering::Greater); "negatives")]
+    #[test_case("-1", "0"                => Some(Ordering::Less);    "negative and zero")]
+    #[test_case("0", "0.001"             => Some(Ordering::Less);    "zero and positive")]
+    #[test_case(
+        "9007199254740993", "9007199254740992"
+        => Some(Ordering::Greater);
+        "beyond f64 precision"
+    )]
+    #[test_case("1e99999999999999999999", "1e9" => Some(Ordering::Greater); "huge exponent")]
+    #[test_case("1", "\"1\""             => None;                    "string")]
+    #[test_case("e1", "1"                => None;                    "missing mantissa")]
+    fn test_compare(
+        a: &str,
+        b: &str,
+    ) -> Option<Ordering> {
+        Some(Decimal::parse(a.as_bytes())?.cmp(&Decimal::parse(b.as_bytes())?))
+    }
+}
@@ -0,0 +1,491 @@
+//! Building values from Rust data through [`serde::Serialize`].
+//!
+//! Scalars are passed straight to libcue's value constructors
+//! (`cue_from_int64`, `cue_from_string`, ...), with no encoding or parsing
+//! in between.  libcue has no constructors for structs and lists, so
+//! composite values are encoded as JSON and compiled instead.
+
+use core::ffi::c_char;
+use std::ffi::CString;
+
+use serde::{Serialize, Serializer, ser};
+use serde_json::ser::{CompactFormatter, Compound};
+
+use super::{CueValueHandle, Value};
+use crate::{Ctx, error::Error};
+
+unsafe extern "C" {
+    fn cue_from_int64(
+        ctx: usize,
+        v: i64,
+    ) -> CueValueHandle;
+    fn cue_from_uint64(
+        ctx: usize,
+        v: u64,
+    ) -> CueValueHandle;
+    fn cue_from_bool(
+        ctx: usize,
+        v: bool,
+    ) -> CueValueHandle;
+    fn cue_from_double(
+        ctx: usize,
+        v: f64,
+    ) -> CueValueHandle;
+    fn cue_from_string(
+        ctx: usize,
+        v: *mut c_char,
+    ) -> CueValueHandle;
+}
+
+/// A `Serialize` implementation failed, or `serde_json` rejected its output.
+#[derive(Debug)]
+struct EncodeError(String);
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+impl ser::Error for EncodeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl From<serde_json::Error> for EncodeError {
+    fn from(err: serde_json::Error) -> Self {
+        Self(err.to_string())
+    }
+}
+
+impl Value {
+    /// Builds a value from Rust data, as CUE's `Context.Encode` does.
+    ///
+    /// Scalars are constructed directly by libcue; structs, lists and other
+    /// composite values are encoded as JSON and compiled, so the result is
+    /// the same as compiling the output of `serde_json::to_vec`, at the same
+    /// cost.  Either way `value` is serialized once.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Serialize`] if `value` cannot be serialized or is a
+    /// non-finite float, or the errors of [`Value::compile_bytes`] for
+    /// composite values.
+    pub fn encode<T: Serialize + ?Sized>(
+        ctx: &Ctx,
+        value: &T,
+    ) -> Result<Self, Error> {
+        let mut json = serde_json::Serializer::new(Vec::new());
+        match value.serialize(Encoder {
+            ctx,
+            json: &mut json,
+        }) {
+            Ok(Encoded::Value(value)) => Ok(value),
+            Ok(Encoded::Json) => Self::compile_bytes(ctx, &json.into_inner()),
+            Err(EncodeError(msg)) => Err(Error::Serialize(msg)),
+        }
+    }
+}
+
+/// What [`Encoder`] built.
+enum Encoded {
+    /// A value built by a libcue constructor.
+    Value(Value),
+    /// JSON written to the encoder's buffer, left to compile.
+    Json,
+}
+
+/// Maps the outcome of writing a value as JSON to what [`Encoder`] built.
+fn written(result: Result<(), serde_json::Error>) -> Result<Encoded, EncodeError> {
+    result.map(|()| Encoded::Json).map_err(EncodeError::from)
+}
+
+/// A serializer building scalars with libcue's value constructors and
+/// writing anything else as JSON.
+struct Encoder<'a> {
+    /// Context the value is built in.
+    ctx: &'a Ctx,
+    /// Serializer writing the JSON of a value libcue cannot construct.
+    json: &'a mut serde_json::Serializer<Vec<u8>>,
+}
+
+impl Encoder<'_> {
+    /// Wraps a handle returned by a `cue_from_*` constructor.
+    #[expect(
+        clippy::unnecessary_wraps,
+        reason = "every serializer method returns a Result"
+    )]
+    fn wrap(
+        &self,
+        handle: CueValueHandle,
+    ) -> Result<Encoded, EncodeError> {
+        Ok(Encoded::Value(Value::new(self.ctx, handle)))
+    }
+}
+
+/// A composite value being written by `serde_json`.
+struct Json<'a>(Compound<'a, Vec<u8>, CompactFormatter>);
+
+impl<'a> Serializer for Encoder<'a> {
+    type Error = EncodeError;
+    type Ok = Encoded;
+    type SerializeMap = Json<'a>;
+    type SerializeSeq = Json<'a>;
+    type SerializeStruct = Json<'a>;
+    type SerializeStructVariant = Json<'a>;
+    type SerializeTuple = Json<'a>;
+    type SerializeTupleStruct = Json<'a>;
+    type SerializeTupleVariant = Json<'a>;
+
+    fn serialize_bool(
+        self,
+        v: bool,
+    ) -> Result<Encoded, EncodeError> {
+        self.wrap(unsafe { cue_from_bool(self.ctx.handle(), v) })
+    }
+
+    fn serialize_i8(
+        self,
+        v: i8,
+    ) -> Result<Encoded, EncodeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(
+        self,
+        v: i16,
+    ) -> Result<Encoded, EncodeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(
+        self,
+        v: i32,
+    ) -> Result<Encoded, EncodeError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(
+        self,
+        v: i64,
+    ) -> Result<Encoded, EncodeError> {
+        self.wrap(unsafe { cue_from_int64(self.ctx.handle(), v) })
+    }
+
+    fn serialize_u8(
+        self,
+        v: u8,
+    ) -> Result<Encoded, EncodeError> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u16(
+        self,
+        v: u16,
+    ) -> Result<Encoded, EncodeError> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(
+        self,
+        v: u32,
+    ) -> Result<Encoded, EncodeError> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(
+        self,
+        v: u64,
+    ) -> Result<Encoded, EncodeError> {
+        self.wrap(unsafe { cue_from_uint64(self.ctx.handle(), v) })
+    }
+
+    fn serialize_f32(
+        self,
+        v: f32,
+    ) -> Result<Encoded, EncodeError> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(
+        self,
+        v: f64,
+    ) -> Result<Encoded, EncodeError> {
+        if !v.is_finite() {
+            return Err(EncodeError(format!("{v} is not a CUE number")));
+        }
+        self.wrap(unsafe { cue_from_double(self.ctx.handle(), v) })
+    }
+
+    fn serialize_char(
+        self,
+        v: char,
+    ) -> Result<Encoded, EncodeError> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(
+        self,
+        v: &str,
+    ) -> Result<Encoded, EncodeError> {
+        // Strings with nul bytes cannot be passed as C strings; the JSON
+        // path handles them.
+        match CString::new(v) {
+            Ok(s) => {
+                self.wrap(unsafe { cue_from_string(self.ctx.handle(), s.as_ptr().cast_mut()) })
+            },
+            Err(_) => written(self.json.serialize_str(v)),
+        }
+    }
+
+    fn serialize_bytes(
+        self,
+        v: &[u8],
+    ) -> Result<Encoded, EncodeError> {
+        // Encoded as by `serde_json`, as a list of numbers.
+        written(self.json.serialize_bytes(v))
+    }
+
+    fn serialize_none(self) -> Result<Encoded, EncodeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(
+        self,
+        value: &T,
+    ) -> Result<Encoded, EncodeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Encoded, EncodeError> {
+        written(self.json.serialize_unit())
+    }
+
+    fn serialize_unit_struct(
+        self,
+        _name: &'static str,
+    ) -> Result<Encoded, EncodeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Encoded, EncodeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Encoded, EncodeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Encoded, EncodeError> {
+        written(
+            self.json
+                .serialize_newtype_variant(name, variant_index, variant, value),
+        )
+    }
+
+    fn serialize_seq(
+        self,
+        len: Option<usize>,
+    ) -> Result<Json<'a>, EncodeError> {
+        Ok(Json(self.json.serialize_seq(len)?))
+    }
+
+    fn serialize_tuple(
+        self,
+        len: usize,
+    ) -> Result<Json<'a>, EncodeError> {
+        Ok(Json(self.json.serialize_tuple(len)?))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Json<'a>, EncodeError> {
+        Ok(Json(self.json.serialize_tuple_struct(name, len)?))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Json<'a>, EncodeError> {
+        let json = self
+            .json
+            .serialize_tuple_variant(name, variant_index, variant, len)?;
+        Ok(Json(json))
+    }
+
+    fn serialize_map(
+        self,
+        len: Option<usize>,
+    ) -> Result<Json<'a>, EncodeError> {
+        Ok(Json(self.json.serialize_map(len)?))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Json<'a>, EncodeError> {
+        Ok(Json(self.json.serialize_struct(name, len)?))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Json<'a>, EncodeError> {
+        let json = self
+            .json
+            .serialize_struct_variant(name, variant_index, variant, len)?;
+        Ok(Json(json))
+    }
+}
+
+impl ser::SerializeSeq for Json<'_> {
+    type Error = EncodeError;
+    type Ok = Encoded;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        ser::SerializeSeq::serialize_element(&mut self.0, value).map_err(EncodeError::from)
+    }
+
+    fn end(self) -> Result<Encoded, EncodeError> {
+        written(ser::SerializeSeq::end(self.0))
+    }
+}
+
+impl ser::SerializeTuple for Json<'_> {
+    type Error = EncodeError;
+    type Ok = Encoded;
+
+    fn serialize_element<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        ser::SerializeTuple::serialize_element(&mut self.0, value).map_err(EncodeError::from)
+    }
+
+    fn end(self) -> Result<Encoded, EncodeError> {
+        written(ser::SerializeTuple::end(self.0))
+    }
+}
+
+impl ser::SerializeTupleStruct for Json<'_> {
+    type Error = EncodeError;
+    type Ok = Encoded;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        ser::SerializeTupleStruct::serialize_field(&mut self.0, value).map_err(EncodeError::from)
+    }
+
+    fn end(self) -> Result<Encoded, EncodeError> {
+        written(ser::SerializeTupleStruct::end(self.0))
+    }
+}
+
+impl ser::SerializeTupleVariant for Json<'_> {
+    type Error = EncodeError;
+    type Ok = Encoded;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        ser::SerializeTupleVariant::serialize_field(&mut self.0, value).map_err(EncodeError::from)
+    }
+
+    fn end(self) -> Result<Encoded, EncodeError> {
+        written(ser::SerializeTupleVariant::end(self.0))
+    }
+}
+
+impl ser::SerializeMap for Json<'_> {
+    type Error = EncodeError;
+    type Ok = Encoded;
+
+    fn serialize_key<T: Serialize + ?Sized>(
+        &mut self,
+        key: &T,
+    ) -> Result<(), EncodeError> {
+        ser::SerializeMap::serialize_key(&mut self.0, key).map_err(EncodeError::from)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        ser::SerializeMap::serialize_value(&mut self.0, value).map_err(EncodeError::from)
+    }
+
+    fn end(self) -> Result<Encoded, EncodeError> {
+        written(ser::SerializeMap::end(self.0))
+    }
+}
+
+impl ser::SerializeStruct for Json<'_> {
+    type Error = EncodeError;
+    type Ok = Encoded;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        ser::SerializeStruct::serialize_field(&mut self.0, key, value).map_err(EncodeError::from)
+    }
+
+    fn end(self) -> Result<Encoded, EncodeError> {
+        written(ser::SerializeStruct::end(self.0))
+    }
+}
+
+impl ser::SerializeStructVariant for Json<'_> {
+    type Error = EncodeError;
+    type Ok = Encoded;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), EncodeError> {
+        ser::SerializeStructVariant::serialize_field(&mut self.0, key, value)
+            .map_err(EncodeError::from)
+    }
+
+    fn end(self) -> Result<Encoded, EncodeError> {
+        written(ser::SerializeStructVariant::end(self.0))
+    }
+}
@@ -0,0 +1,260 @@
+//! Rewriting of JSON5 documents into CUE source.
+//!
+//! CUE already accepts most JSON5 extensions: `//` comments, trailing
+//! commas, unquoted keys, hexadecimal numbers and leading or trailing
+//! decimal points.  The rest is rewritten token by token:
+//!
+//! - `/* */` comments are blanked out, keeping their line breaks so positions in CUE
+//!   errors still match the input;
+//! - strings, single or double quoted, are decoded with JSON5 escapes and re-encoded as
+//!   CUE strings (a single-quoted CUE literal would be bytes);
+//! - unquoted keys are quoted, as they may be CUE keywords such as `if`.
+//!
+//! Outside of strings and comments only brackets, commas, colons,
+//! whitespace, numbers, keys and the literals `true`, `false` and `null`
+//! are accepted, so no CUE beyond JSON5, such as an `import`, reaches the
+//! compiler.
+
+use std::{iter::Peekable, str::Chars};
+
+use crate::{error::Error, report::json_string};
+
+/// Rewrites the JSON5 document `src` into equivalent CUE source.
+///
+/// # Errors
+///
+/// Returns [`Error::Syntax`] if `src` is not JSON5.
+pub(super) fn to_cue(src: &str) -> Result<String, Error> {
+    let mut out = String::with_capacity(src.len());
+    let mut chars = src.chars().peekable();
+    loop {
+        // Kept to locate an error in the token starting here.
+        let start = chars.clone();
+        let Some(c) = chars.next() else { break };
+        let fail = |message: &str| syntax_error(src, start, message);
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if !block_comment(&mut chars, &mut out) {
+                    return Err(fail("comment not terminated"));
+                }
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                out.push(c);
+                while let Some(c) = chars.next_if(|&c| c != '\n') {
+                    out.push(c);
+                }
+            },
+            '"' | '\'' => string(c, &mut chars, &mut out).map_err(fail)?,
+            c if c.is_ascii_digit() || matches!(c, '+' | '-' | '.') => {
+                number(c, &mut chars, &mut out);
+            },
+            c if is_identifier_start(c) => {
+                let mut ident = String::from(c);
+                while let Some(c) = chars.next_if(|&c| is_identifier_start(c) || c.is_ascii_digit())
+                {
+                    ident.push(c);
+                }
+                let mut rest = chars.clone();
+                while rest.next_if(|c| c.is_whitespace()).is_some() {}
+                if rest.peek() == Some(&':') {
+                    out.push_str(&json_string(&ident));
+                } else if matches!(ident.as_str(), "true" | "false" | "null") {
+                    out.push_str(&ident);
+                } else {
+                    return Err(fail(&format!("unexpected identifier {ident}")));
+                }
+            },
+            '{' | '}' | '[' | ']' | ',' | ':' => out.push(c),
+            c if c.is_whitespace() => out.push(c),
+            c => return Err(fail(&format!("unexpected character {c:?}"))),
+        }
+    }
+    Ok(out)
+}
+
+/// Builds the [`Error::Syntax`] of a syntax error in `src` at the token
+/// `rest` starts with.
+fn syntax_error(
+    src: &str,
+    rest: Peekable<Chars<'_>>,
+    message: &str,
+) -> Error {
+    let offset = src.chars().count().saturating_sub(rest.count());
+    let (mut line, mut column) = (1usize, 1usize);
+    for c in src.chars().take(offset) {
+        if c == '\n' {
+            line = line.saturating_add(1);
+            column = 1;
+        } else {
+            column = column.saturating_add(1);
+        }
+    }
+    Error::Syntax {
+        line,
+        column,
+        message: message.to_string(),
+    }
+}
+
+/// Blanks out a `/* */` comment whose opening delimiter was consumed,
+/// returning `false` if it is not terminated.
+fn block_comment(
+    chars: &mut Peekable<Chars<'_>>,
+    out: &mut String,
+) -> bool {
+    let mut comment = String::new();
+    while let Some(c) = chars.next() {
+        if c == '*' && chars.next_if_eq(&'/').is_some() {
+            out.push_str("  ");
+            out.extend(comment.chars().map(|c| if c == '\n' { '\n' } else { ' ' }));
+            out.push_str("  ");
+            return true;
+        }
+        comment.push(c);
+    }
+    false
+}
+
+/// Copies a number token, whose first character was consumed.
+fn number(
+    first: char,
+    chars: &mut Peekable<Chars<'_>>,
+    out: &mut String,
+) {
+    out.push(first);
+    let mut prev = first;
+    let mut hex = false;
+    while let Some(c) = chars.next_if(|&c| {
+        c.is_ascii_alphanumeric()
+            || c == '.'
+            || (matches!(c, '+' | '-') && matches!(prev, 'e' | 'E') && !hex)
+    }) {
+        hex |= matches!(c, 'x' | 'X') && prev == '0';
+        out.push(c);
+        prev = c;
+    }
+}
+
+/// Decodes a string whose opening `quote` was consumed and writes it as a
+/// CUE string.
+///
+/// # Errors
+///
+/// Returns why the string is not valid JSON5, if it is not terminated or
+/// has an invalid escape.
+fn string(
+    quote: char,
+    chars: &mut Peekable<Chars<'_>>,
+    out: &mut String,
+) -> Result<(), &'static str> {
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c == quote => {
+                out.push_str(&json_string(&value));
+                return Ok(());
+            },
+            '\\' => {
+                let e = chars.next().ok_or("string not terminated")?;
+                let decoded = match e {
+                    'n' => Some('\n'),
+                    'r' => Some('\r'),
+                    't' => Some('\t'),
+                    'b' => Some('\u{8}'),
+                    'f' => Some('\u{c}'),
+                    'v' => Some('\u{b}'),
+                    '0' if !chars.peek().is_some_and(char::is_ascii_digit) => Some('\0'),
+                    'x' => Some(hex_char(chars, 2).ok_or("invalid escape")?),
+                    'u' => Some(unicode_char(chars).ok_or("invalid escape")?),
+                    '\r' => {
+                        chars.next_if_eq(&'\n');
+                        None
+                    },
+                    '\n' | '\u{2028}' | '\u{2029}' => None,
+                    c if c.is_ascii_digit() => return Err("invalid escape"),
+                    c => Some(c),
+                };
+                value.extend(decoded);
+            },
+            c => value.push(c),
+        }
+    }
+    Err("string not terminated")
+}
+
+/// Reads `len` hex digits and returns the code point they encode, if valid.
+fn hex_char(
+    chars: &mut Peekable<Chars<'_>>,
+    len: usize,
+) -> Option<char> {
+    hex_unit(chars, len).and_then(char::from_u32)
+}
+
+/// Reads `len` hex digits and returns their value.
+fn hex_unit(
+    chars: &mut Peekable<Chars<'_>>,
+    len: usize,
+) -> Option<u32> {
+    let mut digits = String::with_capacity(len);
+    for _ in 0..len {
+        digits.push(chars.next_if(char::is_ascii_hexdigit)?);
+    }
+    u32::from_str_radix(&digits, 16).ok()
+}
+
+/// Reads the four hex digits of a `\u` escape, combining a surrogate pair
+/// split over two escapes.  Unpaired surrogates decode to U+FFFD.
+fn unicode_char(chars: &mut Peekable<Chars<'_>>) -> Option<char> {
+    let high = u16::try_from(hex_unit(chars, 4)?).ok()?;
+    let mut units = vec![high];
+    if (0xD800..0xDC00).contains(&high) {
+        let mut rest = chars.clone();
+        if rest.next() == Some('\\')
+            && rest.next() == Some('u')
+            && let Some(low) = hex_unit(&mut rest, 4).and_then(|u| u16::try_from(u).ok())
+            && (0xDC00..0xE000).contains(&low)
+        {
+            *chars = rest;
+            units.push(low);
+        }
+    }
+    char::decode_utf16(units)
+        .next()
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+}
+
+/// Returns `true` if `c` can start a JSON5 identifier.
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::to_cue;
+
+    #[test_case(r#"{"a": 1}"# => Ok(r#"{"a": 1}"#.to_string()); "plain json")]
+    #[test_case("{a: 1, if: true,}" => Ok(r#"{"a": 1, "if": true,}"#.to_string()); "unquoted keys")]
+    #[test_case("// c\n[1, /* a\nb */ 2]" => Ok("// c\n[1,     \n     2]".to_string()); "comments")]
+    #[test_case("['it\\'s', \"q\\\"\"]" => Ok(r#"["it's", "q\""]"#.to_string()); "quotes")]
+    #[test_case(r"'\x41é\0\v'" => Ok(r#""Aé\u0000\u000b""#.to_string()); "escapes")]
+    #[test_case(r"'\ud83d\ude00'" => Ok("\"\u{1F600}\"".to_string()); "surrogate pair")]
+    #[test_case(r"'\ud83d'" => Ok("\"\u{FFFD}\"".to_string()); "lone surrogate")]
+    #[test_case("'a\\\nb'" => Ok(r#""ab""#.to_string()); "line continuation")]
+    #[test_case("[0x1F, 1e+5, .5, 5., +1, -2]" => Ok("[0x1F, 1e+5, .5, 5., +1, -2]".to_string()); "numbers")]
+    #[test_case("[0x1e+5]" => Ok("[0x1e+5]".to_string()); "hex is not an exponent")]
+    #[test_case("[true, false, null]" => Ok("[true, false, null]".to_string()); "literals")]
+    #[test_case("'open" => Err("syntax error at 1:1: string not terminated".to_string()); "unterminated string")]
+    #[test_case("[1,\n /* open" => Err("syntax error at 2:2: comment not terminated".to_string()); "unterminated comment")]
+    #[test_case(r"'\1'" => Err("syntax error at 1:1: invalid escape".to_string()); "octal escape")]
+    #[test_case(r"'\xZZ'" => Err("syntax error at 1:1: invalid escape".to_string()); "invalid hex escape")]
+    #[test_case("[Infinity]" => Err("syntax error at 1:2: unexpected identifier Infinity".to_string()); "unsupported literal")]
+    #[test_case("import \"tool/exec\"\n{}" => Err("syntax error at 1:1: unexpected identifier import".to_string()); "import")]
+    #[test_case("{a: b}" => Err("syntax error at 1:5: unexpected identifier b".to_string()); "reference")]
+    #[test_case("{a: 1 & 2}" => Err("syntax error at 1:7: unexpected character '&'".to_string()); "operator")]
+    fn test_to_cue(src: &str) -> Result<String, String> {
+        to_cue(src).map_err(|err| err.to_string())
+    }
+}
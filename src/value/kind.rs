@@ -0,0 +1,73 @@
+//! The kind of a CUE value, mirroring `cue_kind` from libcue.
+
+use core::ffi::c_int;
+
+/// The kind of a CUE value (`cue_kind`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// The bottom value `_|_`, i.e. an error.
+    Bottom,
+    /// `null`.
+    Null,
+    /// `bool`.
+    Bool,
+    /// `int`.
+    Int,
+    /// `float`.
+    Float,
+    /// `string`.
+    String,
+    /// `bytes`.
+    Bytes,
+    /// A struct.
+    Struct,
+    /// A list.
+    List,
+    /// `number`, i.e. `int | float`.
+    Number,
+    /// The top value `_`, which may be of any kind.
+    Top,
+}
+
+impl Kind {
+    /// Converts a raw `cue_kind` value returned by libcue.
+    ///
+    /// Values outside of the `cue_kind` enumeration describe a value that may
+    /// be of several kinds, and are reported as [`Kind::Top`].
+    pub(crate) fn from_raw(raw: c_int) -> Self {
+        match raw {
+            0 => Self::Bottom,
+            1 => Self::Null,
+            2 => Self::Bool,
+            3 => Self::Int,
+            4 => Self::Float,
+            5 => Self::String,
+            6 => Self::Bytes,
+            7 => Self::Struct,
+            8 => Self::List,
+            9 => Self::Number,
+            _ => Self::Top,
+        }
+    }
+}
+
+impl std::fmt::Display for Kind {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Bottom => "_|_",
+            Self::Null => "null",
+            Self::Bool => "bool",
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::String => "string",
+            Self::Bytes => "bytes",
+            Self::Struct => "struct",
+            Self::List => "list",
+            Self::Number => "number",
+            Self::Top => "_",
+        })
+    }
+}
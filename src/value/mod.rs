@@ -1,23 +1,65 @@
 //! CUE value type, wrapping the `cue_value` handle from libcue.
 
+mod decimal;
+#[cfg(feature = "serde")]
+mod encode;
+mod json5;
+mod kind;
+mod pattern;
+#[cfg(feature = "serde")]
+mod ser;
 #[cfg(test)]
 mod tests;
+mod validate;
 
-use core::ffi::c_char;
+use core::ffi::{c_char, c_int};
+use std::{
+    cmp::Ordering,
+    sync::{Arc, OnceLock},
+};
+
+use decimal::Decimal;
+pub use kind::Kind;
+pub use validate::ValidateOptions;
 
 use crate::{
     Ctx, drop,
     error::{CueError, Error},
+    hooks::{Hooks, Operation},
+    limits::{self, Limits},
+    path::Path,
 };
 
 /// Opaque handle to a libcue value (`cue_value` = `uintptr_t`).
 type CueValueHandle = usize;
 
+/// `CUE_BUILD_NONE`, ending a list of build options.
+const CUE_BUILD_NONE: c_int = 0;
+
+/// `CUE_BUILD_FILENAME`, naming the compiled source in error positions.
+const CUE_BUILD_FILENAME: c_int = 1;
+
+/// A libcue build option (`cue_bopt`); a list of them ends with
+/// [`CUE_BUILD_NONE`].
+#[repr(C)]
+struct CueBuildOption {
+    /// The option (`cue_bopt_tag`).
+    tag: c_int,
+    /// Value argument, unused by the filename option.
+    value: CueValueHandle,
+    /// String argument, copied by libcue.
+    str: *mut c_char,
+    /// Boolean argument, unused by the filename option.
+    b: bool,
+}
+
 unsafe extern "C" {
     fn cue_validate(
         v: CueValueHandle,
         opts: *mut core::ffi::c_void,
     ) -> usize;
+    fn cue_concrete_kind(v: CueValueHandle) -> c_int;
+    fn cue_incomplete_kind(v: CueValueHandle) -> c_int;
     fn cue_is_equal(
         a: CueValueHandle,
         b: CueValueHandle,
@@ -26,19 +68,47 @@ unsafe extern "C" {
         a: CueValueHandle,
         b: CueValueHandle,
     ) -> CueValueHandle;
-    fn cue_compile_string(
-        ctx: usize,
-        src: *mut c_char,
-        opts: *mut core::ffi::c_void,
-        out: *mut CueValueHandle,
-    ) -> usize;
     fn cue_compile_bytes(
         ctx: usize,
         data: *mut core::ffi::c_void,
         len: usize,
-        opts: *mut core::ffi::c_void,
+        opts: *mut CueBuildOption,
+        out: *mut CueValueHandle,
+    ) -> usize;
+    fn cue_default(
+        v: CueValueHandle,
+        ok: *mut bool,
+    ) -> CueValueHandle;
+    fn cue_lookup_string(
+        v: CueValueHandle,
+        path: *mut c_char,
         out: *mut CueValueHandle,
     ) -> usize;
+    fn cue_dec_int64(
+        v: CueValueHandle,
+        res: *mut i64,
+    ) -> usize;
+    fn cue_dec_uint64(
+        v: CueValueHandle,
+        res: *mut u64,
+    ) -> usize;
+    fn cue_dec_bool(
+        v: CueValueHandle,
+        res: *mut bool,
+    ) -> usize;
+    fn cue_dec_double(
+        v: CueValueHandle,
+        res: *mut f64,
+    ) -> usize;
+    fn cue_dec_string(
+        v: CueValueHandle,
+        res: *mut *mut c_char,
+    ) -> usize;
+    fn cue_dec_bytes(
+        v: CueValueHandle,
+        res: *mut *mut core::ffi::c_void,
+        size: *mut usize,
+    ) -> usize;
     fn cue_dec_json(
         v: CueValueHandle,
         res: *mut *mut core::ffi::c_void,
@@ -55,11 +125,22 @@ unsafe extern "C" {
 /// value (e.g. a bottom value produced by a conflicting unification).
 /// Call [`Value::is_valid`] to confirm the value is error-free before using it.
 #[derive(Debug)]
-pub struct Value(CueValueHandle);
+pub struct Value {
+    /// Raw libcue value handle.
+    handle: CueValueHandle,
+    /// Lifecycle hooks of the context this value was created in.
+    hooks: Option<Arc<Hooks>>,
+    /// Resource limits of the context this value was created in.
+    limits: Limits,
+    /// Error handle returned by `cue_validate` (0 if valid), once computed.
+    validity: OnceLock<usize>,
+    /// Path of this value from the value it was looked up in.
+    path: Path,
+}
 
 impl Drop for Value {
     fn drop(&mut self) {
-        unsafe { drop::cue_free(self.0) }
+        unsafe { drop::cue_free(self.handle) }
     }
 }
 
@@ -68,88 +149,622 @@ impl PartialEq for Value {
         &self,
         other: &Self,
     ) -> bool {
-        unsafe { cue_is_equal(self.0, other.0) }
+        unsafe { cue_is_equal(self.handle, other.handle) }
     }
 }
 
 impl Value {
     /// Compiles a CUE source string into a [`Value`].
     ///
+    /// The source is passed to libcue by pointer and length, without copying
+    /// it into a C string; it is compiled exactly like
+    /// [`Value::compile_bytes`].
+    ///
     /// # Errors
     ///
-    /// Returns [`Error::StringContainsNul`] if `src` contains interior nul
-    /// bytes, or [`Error::Cue`] if libcue reports a compilation error.
+    /// Returns [`Error::LimitExceeded`] if `src` is larger than the context's
+    /// source size limit, or [`Error::Cue`] if libcue reports a compilation
+    /// error.
     pub fn compile_string(
         ctx: &Ctx,
         src: &str,
     ) -> Result<Self, Error> {
-        let cstr = std::ffi::CString::new(src).map_err(Error::StringContainsNul)?;
-        let mut handle: CueValueHandle = 0;
-        let err = unsafe {
-            cue_compile_string(
-                ctx.handle(),
-                cstr.as_ptr().cast_mut(),
-                core::ptr::null_mut(),
-                &raw mut handle,
-            )
-        };
-        if err != 0 {
-            return Err(Error::Cue(CueError(err)));
-        }
-        Ok(Self(handle))
+        Self::compile_bytes(ctx, src.as_bytes())
     }
 
     /// Compiles a CUE source byte slice into a [`Value`].
     ///
-    /// Unlike [`Value::compile_string`], this accepts source that may contain
-    /// interior nul bytes (since it is passed by pointer and length rather than
-    /// as a C string).
+    /// The source is passed to `cue_compile_bytes` by pointer and length, so
+    /// it need not be UTF-8 checked or nul terminated.
     ///
     /// # Errors
     ///
-    /// Returns [`Error::Cue`] if libcue reports a compilation error.
+    /// Returns [`Error::LimitExceeded`] if `src` is larger than the context's
+    /// source size limit, or [`Error::Cue`] if libcue reports a compilation
+    /// error.
     pub fn compile_bytes(
         ctx: &Ctx,
         src: &[u8],
     ) -> Result<Self, Error> {
+        ctx.limits().check_source(src)?;
+        Self::observe_compile(ctx, src, || Self::compile_unchecked(ctx, src))
+    }
+
+    /// Reads and compiles the CUE file at `path`.
+    ///
+    /// The source is compiled under the name `path`, so positions in errors,
+    /// and the [`Diagnostic`](crate::report::Diagnostic)s parsed from them,
+    /// name the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Io`] if the file cannot be read,
+    /// [`Error::StringContainsNul`] if `path` contains a nul byte, otherwise
+    /// the same errors as [`Value::compile_bytes`].
+    pub fn compile_file(
+        ctx: &Ctx,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let src = std::fs::read(path).map_err(|source| {
+            Error::Io {
+                path: path.to_path_buf(),
+                source,
+            }
+        })?;
+        let filename = std::ffi::CString::new(path.to_string_lossy().as_bytes())
+            .map_err(Error::StringContainsNul)?;
+        ctx.limits().check_source(&src)?;
+        Self::observe_compile(ctx, &src, || {
+            Self::compile_named(ctx, &src, Some(&filename))
+        })
+    }
+
+    /// Reads and compiles the CUE files at `paths` and unifies them, in
+    /// order, into a single value; no files give top (`_`).
+    ///
+    /// Each file is compiled on its own under its name, as by
+    /// [`Value::compile_file`].  Unlike the files of a package loaded with
+    /// [`LoadOptions::load`](crate::load::LoadOptions::load), they cannot
+    /// refer to each other's fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first file that fails to compile, see
+    /// [`Value::compile_file`].
+    pub fn compile_files(
+        ctx: &Ctx,
+        paths: &[impl AsRef<std::path::Path>],
+    ) -> Result<Self, Error> {
+        let values = paths
+            .iter()
+            .map(|path| Self::compile_file(ctx, path))
+            .collect::<Result<Vec<_>, _>>()?;
+        match Self::unify_all(values) {
+            Some(value) => Ok(value),
+            None => Self::compile_string(ctx, "_"),
+        }
+    }
+
+    /// Compiles a YAML document into a [`Value`].
+    ///
+    /// The document is decoded by CUE's own `encoding/yaml` package rather
+    /// than converted to JSON first, so YAML-specific scalars (e.g. `0x1F`
+    /// integers or `.inf`) keep their CUE types.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::LimitExceeded`] if `yaml` is larger than the context's
+    /// source size limit, or [`Error::Cue`] if the document cannot be
+    /// decoded.
+    pub fn compile_yaml(
+        ctx: &Ctx,
+        yaml: &str,
+    ) -> Result<Self, Error> {
+        Self::compile_encoded(ctx, "yaml", yaml)
+    }
+
+    /// Compiles a TOML document (e.g. a `Cargo.toml`) into a [`Value`].
+    ///
+    /// The document is decoded by CUE's own `encoding/toml` package, so
+    /// integers and floats keep their TOML types.  Date and time values are
+    /// decoded as strings.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::LimitExceeded`] if `toml` is larger than the context's
+    /// source size limit, or [`Error::Cue`] if the document cannot be
+    /// decoded.
+    pub fn compile_toml(
+        ctx: &Ctx,
+        toml: &str,
+    ) -> Result<Self, Error> {
+        Self::compile_encoded(ctx, "toml", toml)
+    }
+
+    /// Compiles a JSON5 document (or JSON with comments) into a [`Value`].
+    ///
+    /// CUE accepts most of JSON5 as is; block comments, single-quoted
+    /// strings and JSON5-only string escapes are rewritten before compiling.
+    /// Anything that is not JSON5, such as CUE expressions or imports, is
+    /// rejected before reaching libcue.  `Infinity` and `NaN` have no CUE
+    /// equivalent and are rejected too.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::LimitExceeded`] if `src` is larger than the context's
+    /// source size limit, [`Error::Syntax`] if the document is not JSON5, or
+    /// [`Error::Cue`] if libcue rejects it, e.g. for a malformed number.
+    pub fn compile_json5(
+        ctx: &Ctx,
+        src: &str,
+    ) -> Result<Self, Error> {
+        ctx.limits().check_source(src.as_bytes())?;
+        let cue = json5::to_cue(src)?;
+        Self::observe_compile(ctx, src.as_bytes(), || {
+            Self::compile_unchecked(ctx, cue.as_bytes())
+        })
+    }
+
+    /// Decodes the JSON document `data` with CUE's `encoding/json` package,
+    /// so that input which is not JSON is rejected rather than evaluated as
+    /// CUE.
+    pub(crate) fn compile_json(
+        ctx: &Ctx,
+        data: &[u8],
+    ) -> Result<Self, Error> {
+        let text = std::str::from_utf8(data).map_err(Error::InvalidUtf8)?;
+        Self::compile_encoded(ctx, "json", text)
+    }
+
+    /// Decodes `text` with the `Unmarshal` function of CUE's
+    /// `encoding/<encoding>` package.
+    fn compile_encoded(
+        ctx: &Ctx,
+        encoding: &str,
+        text: &str,
+    ) -> Result<Self, Error> {
+        ctx.limits().check_source(text.as_bytes())?;
+        let src = format!(
+            "import \"encoding/{encoding}\"\n\n{encoding}.Unmarshal({})\n",
+            crate::report::json_string(text)
+        );
+        Self::observe_compile(ctx, text.as_bytes(), || {
+            let value = Self::compile_unchecked(ctx, src.as_bytes())?;
+            value.validate()?;
+            Ok(value)
+        })
+    }
+
+    /// Compiles a CUE source byte slice without checking the context's
+    /// size limit; the imports are still checked in a sandbox.
+    fn compile_unchecked(
+        ctx: &Ctx,
+        src: &[u8],
+    ) -> Result<Self, Error> {
+        Self::compile_named(ctx, src, None)
+    }
+
+    /// Compiles a CUE source byte slice under the file name `filename`, if
+    /// given, without checking the context's size limit.
+    ///
+    /// Every compilation goes through here, so this is where a sandbox
+    /// checks the imports, see [`Limits::with_sandbox`].
+    fn compile_named(
+        ctx: &Ctx,
+        src: &[u8],
+        filename: Option<&std::ffi::CStr>,
+    ) -> Result<Self, Error> {
+        #[cfg(feature = "sandbox")]
+        ctx.limits().check_imports(src)?;
+        let mut opts = filename.map(|name| {
+            [
+                CueBuildOption {
+                    tag: CUE_BUILD_FILENAME,
+                    value: 0,
+                    str: name.as_ptr().cast_mut(),
+                    b: false,
+                },
+                CueBuildOption {
+                    tag: CUE_BUILD_NONE,
+                    value: 0,
+                    str: core::ptr::null_mut(),
+                    b: false,
+                },
+            ]
+        });
+        let opts = opts
+            .as_mut()
+            .map_or(core::ptr::null_mut(), |opts| opts.as_mut_ptr());
         let mut handle: CueValueHandle = 0;
         let err = unsafe {
             cue_compile_bytes(
                 ctx.handle(),
                 src.as_ptr().cast::<core::ffi::c_void>().cast_mut(),
                 src.len(),
-                core::ptr::null_mut(),
+                opts,
                 &raw mut handle,
             )
         };
         if err != 0 {
             return Err(Error::Cue(CueError(err)));
         }
-        Ok(Self(handle))
+        Ok(Self::new(ctx, handle))
+    }
+
+    /// Wraps a handle created in `ctx`.
+    fn new(
+        ctx: &Ctx,
+        handle: CueValueHandle,
+    ) -> Self {
+        Self {
+            handle,
+            hooks: ctx.hooks().cloned(),
+            limits: *ctx.limits(),
+            validity: OnceLock::new(),
+            path: Path::default(),
+        }
+    }
+
+    /// Makes this value a root, with an empty path, for a value looked up in
+    /// a source compiled internally rather than given by the caller.
+    fn into_root(mut self) -> Self {
+        self.path = Path::default();
+        self
+    }
+
+    /// Runs the compilation `f` of `src`, reporting it to the hooks of `ctx`.
+    fn observe_compile(
+        ctx: &Ctx,
+        src: &[u8],
+        f: impl FnOnce() -> Result<Self, Error>,
+    ) -> Result<Self, Error> {
+        Hooks::observe(
+            ctx.hooks().map(Arc::as_ref),
+            Operation::Compile,
+            Some(src),
+            f,
+            |res| res.as_ref().err(),
+        )
+    }
+
+    /// Compiles a CUE source string into a [`Value`] using the default
+    /// context (see [`Ctx::global`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ContextCreationFailed`] if the default context cannot
+    /// be created, otherwise the same errors as [`Value::compile_string`].
+    pub fn compile_string_default(src: &str) -> Result<Self, Error> {
+        Self::compile_string(Ctx::global()?, src)
+    }
+
+    /// Compiles a CUE source byte slice into a [`Value`] using the default
+    /// context (see [`Ctx::global`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ContextCreationFailed`] if the default context cannot
+    /// be created, otherwise the same errors as [`Value::compile_bytes`].
+    pub fn compile_bytes_default(src: &[u8]) -> Result<Self, Error> {
+        Self::compile_bytes(Ctx::global()?, src)
+    }
+
+    /// Decodes this CUE value as an `i64`.
+    ///
+    /// Calls `cue_dec_int64` from libcue.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::IntegerOutOfRange`] (carrying the decimal text of the
+    /// number) if the value is an integer that does not fit in an `i64`, or
+    /// [`Error::Cue`] if it is not a concrete integer.
+    pub fn to_int64(&self) -> Result<i64, Error> {
+        let mut res: i64 = 0;
+        let err = unsafe { cue_dec_int64(self.handle, &raw mut res) };
+        if err != 0 {
+            return Err(self.int_error(err, "i64"));
+        }
+        Ok(res)
+    }
+
+    /// Decodes this CUE value as a `u64`.
+    ///
+    /// Calls `cue_dec_uint64` from libcue.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::IntegerOutOfRange`] (carrying the decimal text of the
+    /// number) if the value is an integer that does not fit in a `u64`, or
+    /// [`Error::Cue`] if it is not a concrete integer.
+    pub fn to_uint64(&self) -> Result<u64, Error> {
+        let mut res: u64 = 0;
+        let err = unsafe { cue_dec_uint64(self.handle, &raw mut res) };
+        if err != 0 {
+            return Err(self.int_error(err, "u64"));
+        }
+        Ok(res)
+    }
+
+    /// Decodes this CUE value as a `bool`.
+    ///
+    /// Calls `cue_dec_bool` from libcue.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the value is not a concrete boolean.
+    pub fn to_bool(&self) -> Result<bool, Error> {
+        let mut res = false;
+        let err = unsafe { cue_dec_bool(self.handle, &raw mut res) };
+        if err != 0 {
+            return Err(Error::Cue(CueError(err)));
+        }
+        Ok(res)
+    }
+
+    /// Decodes this CUE value as an `f64`.
+    ///
+    /// Calls `cue_dec_double` from libcue; integers are converted, possibly
+    /// losing precision.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the value is not a concrete number.
+    pub fn to_double(&self) -> Result<f64, Error> {
+        let mut res: f64 = 0.0;
+        let err = unsafe { cue_dec_double(self.handle, &raw mut res) };
+        if err != 0 {
+            return Err(Error::Cue(CueError(err)));
+        }
+        Ok(res)
+    }
+
+    /// Decodes this CUE value as a string.
+    ///
+    /// Calls `cue_dec_string` from libcue; the C-allocated string is freed
+    /// before returning.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the value is not a concrete string, or
+    /// [`Error::InvalidUtf8`] if libcue returns invalid UTF-8.
+    pub fn to_string(&self) -> Result<String, Error> {
+        let mut ptr: *mut c_char = core::ptr::null_mut();
+        let err = unsafe { cue_dec_string(self.handle, &raw mut ptr) };
+        if err != 0 {
+            return Err(Error::Cue(CueError(err)));
+        }
+        let result = unsafe { core::ffi::CStr::from_ptr(ptr) }
+            .to_str()
+            .map(str::to_owned)
+            .map_err(Error::InvalidUtf8);
+        unsafe { drop::libc_free(ptr.cast()) };
+        result
+    }
+
+    /// Decodes this CUE value as bytes.
+    ///
+    /// Calls `cue_dec_bytes` from libcue; the C-allocated buffer is freed
+    /// before returning.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the value is not concrete bytes.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.dec_bytes().map(|buf| buf.as_ref().to_vec())
+    }
+
+    /// Calls `cue_dec_bytes`, returning the buffer allocated by libcue.
+    fn dec_bytes(&self) -> Result<drop::CBuffer, Error> {
+        let mut ptr: *mut core::ffi::c_void = core::ptr::null_mut();
+        let mut size: usize = 0;
+        let err = unsafe { cue_dec_bytes(self.handle, &raw mut ptr, &raw mut size) };
+        if err != 0 {
+            return Err(Error::Cue(CueError(err)));
+        }
+        Ok(unsafe { drop::CBuffer::new(ptr, size) })
+    }
+
+    /// Returns the length of this value as defined by CUE's `len()`: the
+    /// number of bytes of a string or bytes value, of elements of a list, or
+    /// of regular fields of a struct.
+    ///
+    /// libcue has no length query, so lists and structs are exported to
+    /// JSON to be counted: they must be concrete, and the cost grows with
+    /// the size of the whole value, nested values included.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::KindMismatch`] if the value is of another kind, or
+    /// [`Error::Cue`] if it is not concrete.
+    pub fn len(&self) -> Result<usize, Error> {
+        match self.kind() {
+            Kind::String => self.to_string().map(|s| s.len()),
+            Kind::Bytes => self.to_bytes().map(|b| b.len()),
+            Kind::List | Kind::Struct => {
+                self.dec_json_unchecked()
+                    .map(|json| count_entries(json.as_ref()))
+            },
+            Kind::Bottom => {
+                self.validate()?;
+                Err(self.kind_mismatch("string, bytes, list or struct"))
+            },
+            _ => Err(self.kind_mismatch("string, bytes, list or struct")),
+        }
+    }
+
+    /// Returns `true` if [`Value::len`] is zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Value::len`].
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Compares two concrete numbers exactly, as CUE's `<` and `>` do.
+    ///
+    /// Numbers are compared by their decimal value, so `1.0` equals `1` and
+    /// integers beyond the precision of `f64` still compare correctly.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::KindMismatch`] if either value is not a concrete
+    /// number, or [`Error::Cue`] if it is erroneous.
+    pub fn cmp_num(
+        &self,
+        other: &Self,
+    ) -> Result<Ordering, Error> {
+        Ok(self.to_decimal()?.cmp(&other.to_decimal()?))
+    }
+
+    /// Exports a concrete number as a [`Decimal`].
+    fn to_decimal(&self) -> Result<Decimal, Error> {
+        if !matches!(self.kind(), Kind::Int | Kind::Float) {
+            self.validate()?;
+            return Err(self.kind_mismatch("number"));
+        }
+        Decimal::parse(&self.to_json_vec()?).ok_or_else(|| self.kind_mismatch("number"))
+    }
+
+    /// Builds the error for a value that is not of the `expected` kind.
+    fn kind_mismatch(
+        &self,
+        expected: &'static str,
+    ) -> Error {
+        Error::KindMismatch {
+            expected,
+            found: self.incomplete_kind(),
+        }
+    }
+
+    /// Builds the error for a failed integer decode.
+    ///
+    /// If this value is a concrete integer, the failure can only be a range
+    /// error, so libcue's generic error is replaced by
+    /// [`Error::IntegerOutOfRange`] carrying the exported number text.
+    fn int_error(
+        &self,
+        err: usize,
+        target: &'static str,
+    ) -> Error {
+        match self.to_json_vec() {
+            Ok(text) if is_integer_literal(&text) => {
+                unsafe { drop::cue_free(err) };
+                Error::IntegerOutOfRange {
+                    value: String::from_utf8_lossy(&text).into_owned(),
+                    target,
+                }
+            },
+            _ => Error::Cue(CueError(err)),
+        }
     }
 
     /// Encodes this CUE value as JSON.
     ///
     /// Calls `cue_dec_json` from libcue and copies the result into an owned
-    /// [`bytes::Bytes`] buffer containing the raw JSON bytes. The C-allocated
-    /// buffer is freed before returning.
+    /// buffer containing the raw JSON bytes. The C-allocated buffer is freed
+    /// before returning.
+    ///
+    /// Every export of a value, by this method or any other, is checked
+    /// against the output size and depth limits of the context the value was
+    /// created in, see [`Limits`].
     ///
     /// # Errors
     ///
     /// Returns [`Error::Cue`] if libcue reports an error (e.g. the value
-    /// cannot be represented as JSON).
-    pub fn to_json_bytes(&self) -> Result<bytes::Bytes, Error> {
+    /// cannot be represented as JSON), or [`Error::LimitExceeded`] if the
+    /// document exceeds a limit of the context.
+    pub fn to_json_vec(&self) -> Result<Vec<u8>, Error> {
+        self.dec_json().map(|buf| buf.as_ref().to_vec())
+    }
+
+    /// Calls `cue_dec_json`, returning the buffer allocated by libcue once
+    /// it is checked against the output limits of the context.
+    fn dec_json(&self) -> Result<drop::CBuffer, Error> {
+        let json = self.dec_json_unchecked()?;
+        self.limits.check_json(json.as_ref())?;
+        Ok(json)
+    }
+
+    /// Calls `cue_dec_json`, returning the buffer allocated by libcue.
+    fn dec_json_unchecked(&self) -> Result<drop::CBuffer, Error> {
         let mut ptr: *mut core::ffi::c_void = core::ptr::null_mut();
         let mut size: usize = 0;
-        let err = unsafe { cue_dec_json(self.0, &raw mut ptr, &raw mut size) };
+        let err = unsafe { cue_dec_json(self.handle, &raw mut ptr, &raw mut size) };
         if err != 0 {
             return Err(Error::Cue(CueError(err)));
         }
-        let result = bytes::Bytes::copy_from_slice(unsafe {
-            core::slice::from_raw_parts(ptr.cast::<u8>(), size)
-        });
-        unsafe { drop::libc_free(ptr) };
-        Ok(result)
+        Ok(unsafe { drop::CBuffer::new(ptr, size) })
+    }
+
+    /// Encodes this CUE value as JSON into a [`bytes::Bytes`] buffer.
+    ///
+    /// The buffer is the one allocated by libcue, without a copy; it is freed
+    /// when the last clone of the returned `Bytes` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Value::to_json_vec`].
+    #[cfg(feature = "bytes")]
+    pub fn to_json_bytes(&self) -> Result<bytes::Bytes, Error> {
+        self.dec_json().map(bytes::Bytes::from_owner)
+    }
+
+    /// Encodes this CUE value as JSON, rejecting documents that exceed the
+    /// output size or depth of `limits`, on top of the limits of the context.
+    ///
+    /// The limits are checked on the exported document, so they bound what
+    /// reaches the caller rather than the work done by the evaluator.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::LimitExceeded`] if a limit is exceeded, otherwise the
+    /// same errors as [`Value::to_json_vec`].
+    pub fn to_json_vec_limited(
+        &self,
+        limits: &Limits,
+    ) -> Result<Vec<u8>, Error> {
+        let json = self.to_json_vec()?;
+        limits.check_json(&json)?;
+        Ok(json)
+    }
+
+    /// Encodes this CUE value as JSON into a [`bytes::Bytes`] buffer,
+    /// rejecting documents that exceed the output size or depth of `limits`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Value::to_json_vec_limited`].
+    #[cfg(feature = "bytes")]
+    pub fn to_json_bytes_limited(
+        &self,
+        limits: &Limits,
+    ) -> Result<bytes::Bytes, Error> {
+        let json = self.to_json_bytes()?;
+        limits.check_json(&json)?;
+        Ok(json)
+    }
+
+    /// Encodes this CUE value as MessagePack.
+    ///
+    /// libcue only exports JSON, so the exported document is transcoded to
+    /// MessagePack in a single streaming pass, without building an
+    /// intermediate tree.  As with [`Deserializer`](crate::de::Deserializer),
+    /// `bytes` values are encoded as base64 strings and the nesting depth is
+    /// limited to [`DEFAULT_MAX_DEPTH`](crate::de::DEFAULT_MAX_DEPTH).
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Deserializer::new`](crate::de::Deserializer::new),
+    /// or [`Error::Msgpack`] if the document cannot be transcoded.
+    #[cfg(feature = "rmp")]
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, Error> {
+        let de = crate::de::Deserializer::new(self)?;
+        let mut out = Vec::new();
+        serde_transcode::transcode(&de, &mut rmp_serde::Serializer::new(&mut out))
+            .map_err(Error::Msgpack)?;
+        Ok(out)
     }
 
     /// Unifies two CUE values, returning the meet of the two.
@@ -163,8 +778,310 @@ impl Value {
         v1: &Value,
         v2: &Value,
     ) -> Self {
-        let handle = unsafe { cue_unify(v1.0, v2.0) };
-        Self(handle)
+        let hooks = v1.hooks.as_ref().or(v2.hooks.as_ref());
+        let handle = Hooks::observe(
+            hooks.map(Arc::as_ref),
+            Operation::Unify,
+            None,
+            || unsafe { cue_unify(v1.handle, v2.handle) },
+            |_| None,
+        );
+        Self {
+            handle,
+            hooks: hooks.cloned(),
+            limits: v1.limits,
+            validity: OnceLock::new(),
+            path: v1.path.clone(),
+        }
+    }
+
+    /// Unifies this value with `other`, consuming it.
+    ///
+    /// Equivalent to [`Value::unify`], but frees the handle of `self` right
+    /// away, so folding many fragments does not keep every intermediate
+    /// result alive.
+    #[must_use]
+    pub fn unify_with(
+        self,
+        other: &Value,
+    ) -> Self {
+        Self::unify(&self, other)
+    }
+
+    /// Unifies all `values` in order, returning `None` if there are none.
+    #[must_use]
+    pub fn unify_all(values: impl IntoIterator<Item = Value>) -> Option<Self> {
+        values
+            .into_iter()
+            .reduce(|acc, value| acc.unify_with(&value))
+    }
+
+    /// Looks up the value at `path` (e.g. `a.b[0]`) inside this value.
+    ///
+    /// Convenience wrapper around [`Value::lookup_path`] for one-off lookups;
+    /// prefer building [`Path`]s once when the same paths are read repeatedly.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StringContainsNul`] if `path` contains interior nul
+    /// bytes, otherwise the same errors as [`Value::lookup_path`].
+    pub fn lookup(
+        &self,
+        path: &str,
+    ) -> Result<Self, Error> {
+        self.lookup_path(&Path::new(path)?)
+    }
+
+    /// Looks up the value at `path` inside this value.
+    ///
+    /// Calls `cue_lookup_string` from libcue.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the path is malformed or libcue cannot
+    /// resolve it.
+    pub fn lookup_path(
+        &self,
+        path: &Path,
+    ) -> Result<Self, Error> {
+        let mut handle: CueValueHandle = 0;
+        let err = unsafe {
+            cue_lookup_string(
+                self.handle,
+                path.as_c_str().as_ptr().cast_mut(),
+                &raw mut handle,
+            )
+        };
+        if err != 0 {
+            return Err(Error::Cue(CueError(err)));
+        }
+        Ok(Self {
+            handle,
+            hooks: self.hooks.clone(),
+            limits: self.limits,
+            validity: OnceLock::new(),
+            path: self.path.join(path),
+        })
+    }
+
+    /// Returns the path of this value from the root it was looked up in,
+    /// such as `spec.ports[0]`.
+    ///
+    /// libcue does not report paths, so the path is tracked as values are
+    /// looked up: each lookup appends its path to the one of the value it
+    /// started from.  Compiled values are roots, with an empty path, and
+    /// unified values keep the path of the first operand.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the label under which this value sits in its parent: the
+    /// field name, or the index of a list element; `None` for a root value.
+    ///
+    /// This is the last selector of [`Value::path`], see [`Path::label`].
+    #[must_use]
+    pub fn label(&self) -> Option<String> {
+        self.path.label()
+    }
+
+    /// Looks up the definition `#name` declared in this value.
+    ///
+    /// Definitions are addressed with a leading `#`, which
+    /// [`Value::lookup`] also accepts (e.g. `#Config.port`); this spells out
+    /// the common case of a top-level definition.  A `name` that already
+    /// starts with `#` is used as is.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Value::lookup`].
+    pub fn lookup_def(
+        &self,
+        name: &str,
+    ) -> Result<Self, Error> {
+        if name.starts_with('#') {
+            return self.lookup(name);
+        }
+        self.lookup(&format!("#{name}"))
+    }
+
+    /// Returns the default of this value, if it has one: `"c"` for
+    /// `"a" | "b" | *"c"`, or `8080` for `int | *8080`.
+    ///
+    /// Calls `cue_default` from libcue.  libcue has no counterpart to
+    /// `cue.Value.Expr`, so the branches of a disjunction cannot be
+    /// enumerated and there is no `disjuncts` method: only the default is
+    /// available.
+    #[must_use]
+    pub fn default_value(&self) -> Option<Self> {
+        let mut ok = false;
+        let handle = unsafe { cue_default(self.handle, &raw mut ok) };
+        if !ok {
+            if handle != 0 {
+                unsafe { drop::cue_free(handle) };
+            }
+            return None;
+        }
+        Some(Self {
+            handle,
+            hooks: self.hooks.clone(),
+            limits: self.limits,
+            validity: OnceLock::new(),
+            path: self.path.clone(),
+        })
+    }
+
+    /// Looks up the value at `path`, returning `None` if nothing exists
+    /// there.
+    ///
+    /// Unlike [`Value::lookup`], this tells a field that is not present from
+    /// a field that is present but erroneous, which is reported as an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::StringContainsNul`] if `path` contains interior nul
+    /// bytes, otherwise the same errors as [`Value::lookup_path_opt`].
+    pub fn lookup_opt(
+        &self,
+        path: &str,
+    ) -> Result<Option<Self>, Error> {
+        self.lookup_path_opt(&Path::new(path)?)
+    }
+
+    /// Looks up the value at `path`, returning `None` if nothing exists
+    /// there.
+    ///
+    /// libcue reports a missing field with the same error as an erroneous
+    /// one, so a failed lookup is told apart by the value `path` selects
+    /// from: if that exists and is valid, nothing exists at `path`.  A
+    /// missing field next to an erroneous one is therefore reported as an
+    /// error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if the value at `path`, or the one it is
+    /// selected from, exists but does not pass [`Value::is_valid`].
+    pub fn lookup_path_opt(
+        &self,
+        path: &Path,
+    ) -> Result<Option<Self>, Error> {
+        match self.lookup_path(path) {
+            Ok(value) => {
+                value.validate()?;
+                Ok(Some(value))
+            },
+            Err(err) => self.check_missing(path, err).map(|()| None),
+        }
+    }
+
+    /// Returns `Ok` if the failed lookup of `path`, which failed with `err`,
+    /// means nothing exists there: the value `path` selects from is missing,
+    /// or exists and is valid so the failure cannot come from an erroneous
+    /// field or element.
+    fn check_missing(
+        &self,
+        path: &Path,
+        err: Error,
+    ) -> Result<(), Error> {
+        match path.parent() {
+            Some(parent) => self.lookup_path_opt(&parent).map(drop),
+            None => Err(err),
+        }
+    }
+
+    /// Returns the regular field called `name` of a struct.
+    ///
+    /// Unlike [`Value::lookup`], `name` is a plain field name rather than a
+    /// path, so names such as `"a.b"` or `"#x"` select the field of that
+    /// exact name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::KindMismatch`] if the value is not a struct,
+    /// [`Error::NotFound`] if it has no such field, [`Error::Cue`] if the
+    /// field cannot be looked up and the struct does not pass
+    /// [`Value::is_valid`], or [`Error::StringContainsNul`] if `name`
+    /// contains nul bytes.
+    pub fn get_field(
+        &self,
+        name: &str,
+    ) -> Result<Self, Error> {
+        if self.incomplete_kind() != Kind::Struct {
+            self.validate()?;
+            return Err(self.kind_mismatch("struct"));
+        }
+        match self.lookup_path(&Path::field(name)?) {
+            Err(Error::Cue(_)) if self.validate().is_ok() => Err(Error::NotFound(name.to_string())),
+            result => result,
+        }
+    }
+
+    /// Returns `true` if a field or element exists at `path`, even if its
+    /// value is erroneous.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if nothing can be looked up at `path` and the
+    /// value it is selected from does not pass [`Value::is_valid`], as
+    /// libcue then does not tell a missing field from an erroneous one.
+    pub fn exists(
+        &self,
+        path: &Path,
+    ) -> Result<bool, Error> {
+        match self.lookup_path(path) {
+            Ok(_) => Ok(true),
+            Err(err) => self.check_missing(path, err).map(|()| false),
+        }
+    }
+
+    /// Returns the kind of this value if it is concrete, or [`Kind::Bottom`]
+    /// otherwise.
+    ///
+    /// Calls `cue_concrete_kind` from libcue.
+    #[must_use]
+    pub fn kind(&self) -> Kind {
+        Kind::from_raw(unsafe { cue_concrete_kind(self.handle) })
+    }
+
+    /// Returns the kind this value will have once it is made concrete (e.g.
+    /// [`Kind::Int`] for `>0`), without requiring it to be concrete.
+    ///
+    /// Calls `cue_incomplete_kind` from libcue.
+    #[must_use]
+    pub fn incomplete_kind(&self) -> Kind {
+        Kind::from_raw(unsafe { cue_incomplete_kind(self.handle) })
+    }
+
+    /// Returns `true` if this value is bottom (`_|_`), e.g. the result of a
+    /// failed unification.
+    ///
+    /// Unlike [`Value::is_valid`], this does not build an error describing
+    /// why the value is bottom.
+    #[must_use]
+    pub fn is_bottom(&self) -> bool {
+        self.incomplete_kind() == Kind::Bottom
+    }
+
+    /// Returns `true` if this value is top (`_`), i.e. it is unconstrained.
+    #[must_use]
+    pub fn is_top(&self) -> bool {
+        self.incomplete_kind() == Kind::Top
+    }
+
+    /// Returns `true` if this value is concrete data rather than a
+    /// constraint: a scalar such as `1` or `"a"`, or a struct or list whose
+    /// regular fields and elements are all concrete, so `{ a: int }` is not.
+    ///
+    /// Unlike `cue.Value.IsConcrete`, this is deep.  libcue validates without
+    /// a concreteness check, so a struct or list is exported to JSON, which
+    /// fails on the first incomplete value: the cost grows with its size.
+    #[must_use]
+    pub fn is_concrete(&self) -> bool {
+        match self.kind() {
+            Kind::Bottom => false,
+            Kind::Struct | Kind::List => self.dec_json_unchecked().is_ok(),
+            _ => true,
+        }
     }
 
     /// Validates this CUE value, returning an error if it is not valid.
@@ -172,14 +1089,87 @@ impl Value {
     /// Calls `cue_validate` from libcue with no export options.  A value is
     /// valid when it contains no errors (e.g. it is not a bottom value).
     ///
+    /// Values are immutable, so the outcome is computed once and cached;
+    /// only the first call reaches libcue and is reported to the hooks.
+    ///
     /// # Errors
     ///
     /// Returns [`Error::Cue`] if libcue reports a validation error.
     pub fn is_valid(&self) -> Result<(), Error> {
-        let err = unsafe { cue_validate(self.0, core::ptr::null_mut()) };
+        if self.validity.get().is_some() {
+            return self.validate();
+        }
+        Hooks::observe(
+            self.hooks.as_deref(),
+            Operation::Validate,
+            None,
+            || self.validate(),
+            |res| res.as_ref().err(),
+        )
+    }
+
+    /// Calls `cue_validate` on first use, without reporting to the hooks.
+    fn validate(&self) -> Result<(), Error> {
+        let err = *self
+            .validity
+            .get_or_init(|| unsafe { cue_validate(self.handle, core::ptr::null_mut()) });
         if err != 0 {
             return Err(Error::Cue(CueError(err)));
         }
         Ok(())
     }
 }
+
+/// Implements `TryFrom<&Value>` for a type in terms of a decoding method.
+macro_rules! impl_try_from {
+    ($($ty:ty => $method:ident),* $(,)?) => {$(
+        impl TryFrom<&Value> for $ty {
+            type Error = Error;
+
+            fn try_from(value: &Value) -> Result<Self, Self::Error> {
+                value.$method().map(Into::into)
+            }
+        }
+    )*};
+}
+
+impl_try_from! {
+    i64 => to_int64,
+    u64 => to_uint64,
+    bool => to_bool,
+    f64 => to_double,
+    String => to_string,
+    Vec<u8> => to_bytes,
+}
+
+#[cfg(feature = "bytes")]
+impl TryFrom<&Value> for bytes::Bytes {
+    type Error = Error;
+
+    /// Converts a bytes value without copying the buffer allocated by
+    /// libcue.
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        value.dec_bytes().map(Self::from_owner)
+    }
+}
+
+/// Returns the number of elements or members of a JSON array or object.
+fn count_entries(json: &[u8]) -> usize {
+    let mut commas = 0usize;
+    let mut empty = true;
+    for (depth, b) in limits::json_tokens(json) {
+        match (depth, b) {
+            (1, b',') => commas = commas.saturating_add(1),
+            (0, _) | (1, b'[' | b'{' | b']' | b'}') => {},
+            (1, b) if b.is_ascii_whitespace() => {},
+            _ => empty = false,
+        }
+    }
+    if empty { 0 } else { commas.saturating_add(1) }
+}
+
+/// Returns `true` if `text` is a JSON integer literal (`-?[0-9]+`).
+fn is_integer_literal(text: &[u8]) -> bool {
+    let digits = text.strip_prefix(b"-").unwrap_or(text);
+    !digits.is_empty() && digits.iter().all(u8::is_ascii_digit)
+}
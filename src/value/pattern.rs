@@ -0,0 +1,103 @@
+//! String constraints such as `=~"^[a-z]+$"`, for assembling validators.
+
+use super::Value;
+use crate::{Ctx, error::Error, expr::Expr};
+
+impl Value {
+    /// Returns the constraint `=~pattern`: any string matching the RE2
+    /// regular expression `pattern`.
+    ///
+    /// The pattern is checked when the constraint is built, rather than when
+    /// it is first applied to a string:
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), cue_rs::error::Error> {
+    /// use cue_rs::{Ctx, Value};
+    ///
+    /// let ctx = Ctx::new()?;
+    /// let name = Value::matching(&ctx, "^[a-z][a-z0-9-]*$")?;
+    /// let doc = Value::compile_string(&ctx, r#""api-gateway""#)?;
+    /// Value::unify(&name, &doc).is_valid()?;
+    ///
+    /// assert!(Value::matching(&ctx, "[a-z").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Cue`] if `pattern` is not a valid RE2 regular
+    /// expression, otherwise the errors of [`Value::compile_string`].
+    pub fn matching(
+        ctx: &Ctx,
+        pattern: &str,
+    ) -> Result<Self, Error> {
+        Self::regex(ctx, "=~", pattern)
+    }
+
+    /// Returns the constraint `!~pattern`: any string not matching the RE2
+    /// regular expression `pattern`.
+    ///
+    /// See [`Value::matching`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Value::matching`].
+    pub fn not_matching(
+        ctx: &Ctx,
+        pattern: &str,
+    ) -> Result<Self, Error> {
+        Self::regex(ctx, "!~", pattern)
+    }
+
+    /// Returns the constraint `strings.MinRunes(n)`: any string of at least
+    /// `n` characters.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Value::compile_string`].
+    pub fn min_runes(
+        ctx: &Ctx,
+        n: usize,
+    ) -> Result<Self, Error> {
+        Self::compile_string(
+            ctx,
+            &format!("import \"strings\"\n\nstrings.MinRunes({n})\n"),
+        )
+    }
+
+    /// Returns the constraint `strings.MaxRunes(n)`: any string of at most
+    /// `n` characters.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Value::compile_string`].
+    pub fn max_runes(
+        ctx: &Ctx,
+        n: usize,
+    ) -> Result<Self, Error> {
+        Self::compile_string(
+            ctx,
+            &format!("import \"strings\"\n\nstrings.MaxRunes({n})\n"),
+        )
+    }
+
+    /// Returns the constraint `op` `pattern`, after checking that `pattern`
+    /// compiles by matching it against the empty string.
+    fn regex(
+        ctx: &Ctx,
+        op: &'static str,
+        pattern: &str,
+    ) -> Result<Self, Error> {
+        let pattern = Expr::from(pattern);
+        let value = Self::compile_string(
+            ctx,
+            &format!(
+                "check: \"\" =~ {pattern}\nconstraint: {}\n",
+                Expr::unary(op, pattern.clone())
+            ),
+        )?;
+        value.lookup("check")?.is_valid()?;
+        value.lookup("constraint").map(Self::into_root)
+    }
+}
@@ -0,0 +1,21 @@
+//! [`serde::Serialize`] for [`Value`], serializing its evaluated content.
+
+use serde::{Serialize, Serializer, ser::Error as _};
+
+use super::Value;
+use crate::de::Deserializer;
+
+impl Serialize for Value {
+    /// Serializes the evaluated content of the value, as exported by libcue,
+    /// by transcoding it through a [`Deserializer`].
+    ///
+    /// A value that cannot be exported, e.g. because it is not concrete,
+    /// fails with a custom serializer error carrying the libcue message.
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let de = Deserializer::new(self).map_err(S::Error::custom)?;
+        serde_transcode::transcode(&de, serializer)
+    }
+}
@@ -1,7 +1,18 @@
+use std::{
+    cmp::Ordering,
+    sync::{Arc, Mutex},
+};
+
 use serde_json::json;
 use test_case::test_case;
 
-use crate::{Ctx, Value};
+use crate::{
+    Ctx, Path, Value,
+    error::Error,
+    hooks::{Event, Hooks, Operation},
+    limits::{Limit, Limits},
+    value::{Kind, ValidateOptions},
+};
 
 // ── int64 ──────────────────────────────────────────────────────────
 
@@ -85,9 +96,9 @@ fn value_test(val: &str) -> serde_json::Value {
     let v = Value::compile_string(&ctx, val).unwrap();
     let v_from_bytes = Value::compile_bytes(&ctx, val.as_bytes()).unwrap();
     assert_eq!(v, v_from_bytes);
-    let v_json = serde_json::from_slice::<serde_json::Value>(&v.to_json_bytes().unwrap()).unwrap();
+    let v_json = serde_json::from_slice::<serde_json::Value>(&v.to_json_vec().unwrap()).unwrap();
     let v_from_bytes_json =
-        serde_json::from_slice::<serde_json::Value>(&v.to_json_bytes().unwrap()).unwrap();
+        serde_json::from_slice::<serde_json::Value>(&v.to_json_vec().unwrap()).unwrap();
     assert_eq!(v_json, v_from_bytes_json);
     v_json
 }
@@ -107,7 +118,7 @@ fn value_unify_test(
     let va = Value::compile_string(&ctx, a).unwrap();
     let vb = Value::compile_string(&ctx, b).unwrap();
     let v = Value::unify(&va, &vb);
-    serde_json::from_slice::<serde_json::Value>(&v.to_json_bytes().unwrap()).unwrap()
+    serde_json::from_slice::<serde_json::Value>(&v.to_json_vec().unwrap()).unwrap()
 }
 
 #[test_case("1",      "2"      ; "conflicting ints produce bottom")]
@@ -122,6 +133,28 @@ fn value_unify_bottom_test(
     assert!(Value::unify(&va, &vb).is_valid().is_err());
 }
 
+#[test_case(&[]                                  => None;                               "empty")]
+#[test_case(&["{ a: 1 }"]                        => Some(json!({ "a": 1 }));            "single")]
+#[test_case(&["{ a: int }", "{ a: 1 }", "{ b: 2 }"] => Some(json!({ "a": 1, "b": 2 })); "several")]
+fn value_unify_all_test(srcs: &[&str]) -> Option<serde_json::Value> {
+    let ctx = Ctx::new().unwrap();
+    let values = srcs
+        .iter()
+        .map(|src| Value::compile_string(&ctx, src).unwrap());
+    let v = Value::unify_all(values)?;
+    Some(serde_json::from_slice(&v.to_json_vec().unwrap()).unwrap())
+}
+
+#[test]
+fn value_unify_with_test() {
+    let ctx = Ctx::new().unwrap();
+    let a = Value::compile_string(&ctx, "{ a: int }").unwrap();
+    let b = Value::compile_string(&ctx, "{ a: 1 }").unwrap();
+    let c = Value::compile_string(&ctx, "{ a: 2 }").unwrap();
+    assert!(a.unify_with(&b).is_valid().is_ok());
+    assert!(b.unify_with(&c).is_valid().is_err());
+}
+
 // ── is_valid ─────────────────────────────────────────────────────────
 
 #[test_case("42"        => true;  "int is valid")]
@@ -137,3 +170,775 @@ fn value_valid_test(src: &str) -> bool {
         Ok(v) => v.is_valid().is_ok(),
     }
 }
+
+#[test]
+fn value_compile_string_nul_test() {
+    let ctx = Ctx::new().unwrap();
+    assert!(matches!(
+        Value::compile_string(&ctx, "{ a: 1 }\0"),
+        Err(Error::Cue(_))
+    ));
+}
+
+#[test]
+fn value_compile_file_test() {
+    let ctx = Ctx::new().unwrap();
+    let dir = std::env::temp_dir().join(format!("cue-rs-compile-file-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.cue"), "a: int\nb: a + 1\n").unwrap();
+    std::fs::write(dir.join("b.cue"), "a: 1\n").unwrap();
+    std::fs::write(dir.join("bad.cue"), "a: {\n").unwrap();
+
+    let value = Value::compile_files(&ctx, &[dir.join("a.cue"), dir.join("b.cue")]).unwrap();
+    let bad = Value::compile_file(&ctx, dir.join("bad.cue"));
+    let missing = Value::compile_file(&ctx, dir.join("missing.cue"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(value.lookup("b").unwrap().to_int64().unwrap(), 2);
+    assert!(bad.unwrap_err().to_string().contains("bad.cue"));
+    assert!(matches!(missing, Err(Error::Io { .. })));
+    let empty: [&str; 0] = [];
+    assert!(
+        Value::compile_files(&ctx, &empty)
+            .unwrap()
+            .is_valid()
+            .is_ok()
+    );
+}
+
+// ── validate ─────────────────────────────────────────────────────────
+
+#[test_case(br#"{ "name": "a", "age": 1 }"#, true  => true;  "valid")]
+#[test_case(br#"{ "name": "a", "age": -1 }"#, true => false; "out of bound")]
+#[test_case(br#"{ "age": 1 }"#, true                => false; "missing field")]
+#[test_case(br#"{ "age": 1 }"#, false               => true;  "missing field allowed")]
+#[test_case(b"{ ", false                            => false; "malformed")]
+#[test_case(br#"{ name: "a", age: 1 }"#, true       => false; "not json")]
+#[test_case(br#"{ "name": "a", "age": 1 + 1 }"#, true => false; "expression")]
+fn value_validate_json_test(
+    data: &[u8],
+    concrete: bool,
+) -> bool {
+    let ctx = Ctx::new().unwrap();
+    let schema = Value::compile_string(&ctx, "{ name: string, age: int & >=0 }").unwrap();
+    let opts = ValidateOptions::new().with_concrete(concrete);
+    schema.validate_json_with(&ctx, data, opts).is_ok()
+}
+
+#[test]
+fn value_validate_json_result_test() {
+    let ctx = Ctx::new().unwrap();
+    let schema = Value::compile_string(&ctx, "{ port: int | *80, host: string }").unwrap();
+    let v = schema.validate_json(&ctx, br#"{ "host": "h" }"#).unwrap();
+    assert_eq!(v.lookup("port").unwrap().to_int64().unwrap(), 80);
+}
+
+#[test_case("name: a\nage: 0x1F" => true;  "valid")]
+#[test_case("name: a\nage: -1"   => false; "out of bound")]
+#[test_case("age: 1"             => false; "missing field")]
+#[test_case("name: [a"           => false; "malformed")]
+fn value_validate_yaml_test(data: &str) -> bool {
+    let ctx = Ctx::new().unwrap();
+    let schema = Value::compile_string(&ctx, "{ name: string, age: int & >=0 }").unwrap();
+    schema.validate_yaml(&ctx, data).is_ok()
+}
+
+// ── default context ──────────────────────────────────────────────────
+
+#[test]
+fn value_compile_default_test() {
+    let v = Value::compile_string_default("{ a: 1 }").unwrap();
+    assert_eq!(v, Value::compile_bytes_default(b"{ a: 1 }").unwrap());
+    assert_eq!(v.to_json_vec().unwrap(), br#"{"a":1}"#);
+}
+
+// ── limits ───────────────────────────────────────────────────────────
+
+#[test]
+fn value_limits_test() {
+    let ctx = Ctx::with_limits(Limits::default().with_max_source_size(8)).unwrap();
+    assert!(Value::compile_string(&ctx, "{ a: 1 }").is_ok());
+    assert!(matches!(
+        Value::compile_bytes(&ctx, b"{ a: 100 }"),
+        Err(Error::LimitExceeded {
+            limit: Limit::SourceSize,
+            actual: 10,
+            max: 8
+        })
+    ));
+
+    let v = Value::compile_string(&ctx, "{a:[1]}").unwrap();
+    assert!(
+        v.to_json_vec_limited(&Limits::default().with_max_depth(2))
+            .is_ok()
+    );
+    assert!(matches!(
+        v.to_json_vec_limited(&Limits::default().with_max_depth(1)),
+        Err(Error::LimitExceeded {
+            limit: Limit::Depth,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn value_ctx_output_limits_test() {
+    let ctx = Ctx::with_limits(Limits::default().with_max_depth(2)).unwrap();
+    let v = Value::compile_string(&ctx, "{a: {b: [1]}}").unwrap();
+    let a = v.lookup("a").unwrap();
+    assert_eq!(a.to_json_vec().unwrap(), br#"{"b":[1]}"#);
+    let deep = Value::unify(&a, &Value::compile_string(&ctx, "{c: [[1]]}").unwrap());
+    for v in [v, deep] {
+        assert!(matches!(
+            v.to_json_vec(),
+            Err(Error::LimitExceeded {
+                limit: Limit::Depth,
+                actual: 3,
+                max: 2
+            })
+        ));
+    }
+
+    let ctx = Ctx::with_limits(Limits::default().with_max_output_size(11)).unwrap();
+    let v = Value::compile_string(&ctx, r#""0123456789""#).unwrap();
+    assert!(matches!(
+        v.to_json_vec(),
+        Err(Error::LimitExceeded {
+            limit: Limit::OutputSize,
+            actual: 12,
+            max: 11
+        })
+    ));
+}
+
+#[cfg(feature = "sandbox")]
+#[test]
+fn value_sandbox_test() {
+    let ctx = Ctx::with_limits(Limits::default().with_sandbox(true)).unwrap();
+    assert!(matches!(
+        Value::compile_json5(&ctx, "import \"tool/exec\"\n{}"),
+        Err(Error::Syntax {
+            line: 1,
+            column: 1,
+            ..
+        })
+    ));
+    assert!(Value::compile_json5(&ctx, "{ a: 1 }").is_ok());
+    assert!(Value::compile_yaml(&ctx, "a: 1").is_ok());
+    assert!(matches!(
+        ctx.builtin_pkg("tool/exec").get("Run"),
+        Err(Error::ForbiddenImport(path)) if path == "tool/exec"
+    ));
+}
+
+// ── lookup ───────────────────────────────────────────────────────────
+
+#[test_case("a"       => json!(1);     "top level field")]
+#[test_case("b.c"     => json!("x");   "nested field")]
+#[test_case("d[1]"    => json!(true);  "list index")]
+#[test_case(r#""e-f""# => json!(null);  "quoted label")]
+fn value_lookup_test(path: &str) -> serde_json::Value {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(
+        &ctx,
+        r#"{ a: 1, b: { c: "x" }, d: [false, true], "e-f": null }"#,
+    )
+    .unwrap();
+    serde_json::from_slice(&v.lookup(path).unwrap().to_json_vec().unwrap()).unwrap()
+}
+
+#[test]
+fn value_lookup_path_reuse_test() {
+    let ctx = Ctx::new().unwrap();
+    let paths = ["a", "b.c", "missing"].map(|p| Path::new(p).unwrap());
+    for src in ["{ a: 1, b: { c: 2 } }", "{ a: 3, b: { c: 4 } }"] {
+        let v = Value::compile_string(&ctx, src).unwrap();
+        let res = paths.each_ref().map(|path| v.lookup_path(path));
+        assert!(res[0].as_ref().unwrap().is_valid().is_ok());
+        assert!(res[1].as_ref().unwrap().is_valid().is_ok());
+        assert!(res[2].is_err());
+    }
+}
+
+#[test_case("Config"     => Some(json!({ "port": 80 })); "definition")]
+#[test_case("#Config"    => Some(json!({ "port": 80 })); "leading hash")]
+#[test_case("Config.port" => Some(json!(80));            "nested field")]
+#[test_case("config"     => None;                        "regular field")]
+#[test_case("Missing"    => None;                        "missing")]
+fn value_lookup_def_test(name: &str) -> Option<serde_json::Value> {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(&ctx, "{ #Config: { port: 80 }, config: #Config }").unwrap();
+    let def = v.lookup_def(name).ok()?;
+    Some(serde_json::from_slice(&def.to_json_vec().ok()?).unwrap())
+}
+
+#[test_case("a"         => matches (Some(true), Some(true));   "present")]
+#[test_case("b.c"       => matches (Some(true), Some(true));   "nested")]
+#[test_case("d[1]"      => matches (Some(true), Some(true));   "element")]
+#[test_case("e.bad"     => matches (Some(true) | None, None);  "erroneous")]
+#[test_case("e.missing" => matches (None, None);               "next to erroneous")]
+#[test_case("b.x"       => matches (Some(false), Some(false)); "missing")]
+#[test_case("b.x.y"     => matches (Some(false), Some(false)); "missing parent")]
+#[test_case("d[5]"      => matches (Some(false), Some(false)); "missing element")]
+fn value_exists_test(path: &str) -> (Option<bool>, Option<bool>) {
+    let ctx = Ctx::new().unwrap();
+    let v =
+        Value::compile_string(&ctx, "{ a: 1, b: { c: 2 }, d: [1, 2], e: { bad: 1 & 2 } }").unwrap();
+    let exists = v.exists(&Path::new(path).unwrap()).ok();
+    (exists, v.lookup_opt(path).ok().map(|v| v.is_some()))
+}
+
+#[test_case("a"   => Ok(json!(1));                          "plain")]
+#[test_case("a.b" => Ok(json!(2));                          "dotted name")]
+#[test_case("#x"  => Ok(json!(3));                          "hash name")]
+#[test_case("b"   => Err("field not found: b".to_string()); "missing")]
+fn value_get_field_test(name: &str) -> Result<serde_json::Value, String> {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(&ctx, r##"{ a: 1, "a.b": 2, "#x": 3 }"##).unwrap();
+    v.get_field(name)
+        .map(|field| serde_json::from_slice(&field.to_json_vec().unwrap()).unwrap())
+        .map_err(|err| err.to_string())
+}
+
+#[test]
+fn value_get_field_not_struct_test() {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(&ctx, "[1, 2]").unwrap();
+    assert!(matches!(
+        v.get_field("a"),
+        Err(Error::KindMismatch {
+            expected: "struct",
+            found: Kind::List
+        })
+    ));
+}
+
+#[test_case("a"      => r#""a""#;          "plain")]
+#[test_case("a.b"    => r#""a.b""#;        "dotted")]
+#[test_case("say \"hi\"\\" => r#""say \"hi\"\\""#; "escaped")]
+#[test_case("\u{1}\n" => r#""\u0001\n""#;    "control")]
+fn path_field_test(name: &str) -> String {
+    Path::field(name).unwrap().to_string()
+}
+
+// ── default ──────────────────────────────────────────────────────────
+
+#[test_case(r#""a" | "b" | *"c""# => Some(json!("c")); "enum like")]
+#[test_case("int | *8080"           => Some(json!(8080)); "type with default")]
+#[test_case(r#""a" | "b""#          => None;              "no default")]
+#[test_case("1"                     => None;              "concrete")]
+fn value_default_value_test(src: &str) -> Option<serde_json::Value> {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(&ctx, src).unwrap();
+    let default = v.default_value()?;
+    Some(serde_json::from_slice(&default.to_json_vec().unwrap()).unwrap())
+}
+
+// ── integers ─────────────────────────────────────────────────────────
+
+#[test_case("42" => 42; "positive")]
+#[test_case("-42" => -42; "negative")]
+#[test_case("9223372036854775807" => i64::MAX; "max")]
+fn value_to_int64_test(src: &str) -> i64 {
+    let ctx = Ctx::new().unwrap();
+    Value::compile_string(&ctx, src)
+        .unwrap()
+        .to_int64()
+        .unwrap()
+}
+
+#[test_case("9223372036854775808", true  => "9223372036854775808"; "i64 above max")]
+#[test_case("-9223372036854775809", true => "-9223372036854775809"; "i64 below min")]
+#[test_case("-1", false => "-1"; "u64 negative")]
+#[test_case("18446744073709551616", false => "18446744073709551616"; "u64 above max")]
+fn value_int_out_of_range_test(
+    src: &str,
+    signed: bool,
+) -> String {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(&ctx, src).unwrap();
+    let (err, expected_target) = if signed {
+        (v.to_int64().unwrap_err(), "i64")
+    } else {
+        (v.to_uint64().unwrap_err(), "u64")
+    };
+    match err {
+        Error::IntegerOutOfRange { value, target } => {
+            assert_eq!(target, expected_target);
+            value
+        },
+        err => panic!("unexpected error: {err}"),
+    }
+}
+
+#[test_case("1.5"; "float")]
+#[test_case("int"; "non concrete")]
+#[test_case(r#""1""#; "string")]
+fn value_to_int64_not_int_test(src: &str) {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(&ctx, src).unwrap();
+    assert!(matches!(v.to_int64(), Err(Error::Cue(_))));
+}
+
+#[test_case("1", "1.0"                    => Some(Ordering::Equal);   "int and float")]
+#[test_case("2.5", "3"                    => Some(Ordering::Less);    "float and int")]
+#[test_case("18446744073709551617", "18446744073709551616" => Some(Ordering::Greater); "big ints")]
+#[test_case("1", r#""1""#                 => None;                    "string")]
+#[test_case("int", "1"                    => None;                    "non concrete")]
+fn value_cmp_num_test(
+    a: &str,
+    b: &str,
+) -> Option<Ordering> {
+    let ctx = Ctx::new().unwrap();
+    let a = Value::compile_string(&ctx, a).unwrap();
+    let b = Value::compile_string(&ctx, b).unwrap();
+    a.cmp_num(&b).ok()
+}
+
+#[test_case("true"  => Some(true);  "true literal")]
+#[test_case("false" => Some(false); "false literal")]
+#[test_case("bool"  => None;        "non concrete")]
+#[test_case("1"     => None;        "int")]
+fn value_to_bool_test(src: &str) -> Option<bool> {
+    let ctx = Ctx::new().unwrap();
+    Value::compile_string(&ctx, src).unwrap().to_bool().ok()
+}
+
+#[test_case("1.5"    => Some(1.5);  "float")]
+#[test_case("-2"     => Some(-2.0); "int")]
+#[test_case("number" => None;       "non concrete")]
+#[test_case(r#""1""# => None;       "string")]
+fn value_to_double_test(src: &str) -> Option<f64> {
+    let ctx = Ctx::new().unwrap();
+    Value::compile_string(&ctx, src).unwrap().to_double().ok()
+}
+
+#[test]
+fn value_try_from_test() {
+    let ctx = Ctx::new().unwrap();
+    let v =
+        Value::compile_string(&ctx, r#"{ i: -1, u: 1, b: true, f: 0.5, s: "x", y: 'y' }"#).unwrap();
+    let get = |path: &str| v.lookup(path).unwrap();
+    assert_eq!(i64::try_from(&get("i")).unwrap(), -1);
+    assert_eq!(u64::try_from(&get("u")).unwrap(), 1);
+    assert!(bool::try_from(&get("b")).unwrap());
+    assert!((f64::try_from(&get("f")).unwrap() - 0.5).abs() < f64::EPSILON);
+    assert_eq!(String::try_from(&get("s")).unwrap(), "x");
+    assert_eq!(Vec::<u8>::try_from(&get("y")).unwrap(), b"y");
+    #[cfg(feature = "bytes")]
+    assert_eq!(bytes::Bytes::try_from(&get("y")).unwrap(), "y");
+    assert!(matches!(i64::try_from(&get("s")), Err(Error::Cue(_))));
+}
+
+// ── kind ─────────────────────────────────────────────────────────────
+
+#[test_case("null"      => (Kind::Null, Kind::Null);     "null")]
+#[test_case("42"        => (Kind::Int, Kind::Int);       "int")]
+#[test_case("1.5"       => (Kind::Float, Kind::Float);   "float")]
+#[test_case(r#""a""#    => (Kind::String, Kind::String); "string")]
+#[test_case("'a'"       => (Kind::Bytes, Kind::Bytes);   "bytes")]
+#[test_case("{ a: 1 }"  => (Kind::Struct, Kind::Struct); "struct literal")]
+#[test_case("[1]"       => (Kind::List, Kind::List);     "list literal")]
+#[test_case(">0"        => (Kind::Bottom, Kind::Number); "bound")]
+#[test_case("int"       => (Kind::Bottom, Kind::Int);    "int type")]
+#[test_case("_"         => (Kind::Bottom, Kind::Top);    "top")]
+#[test_case("_|_"       => (Kind::Bottom, Kind::Bottom); "bottom")]
+fn value_kind_test(src: &str) -> (Kind, Kind) {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(&ctx, src).unwrap();
+    (v.kind(), v.incomplete_kind())
+}
+
+#[test_case("_|_" => (true, false);  "bottom")]
+#[test_case("_"   => (false, true);  "top")]
+#[test_case("int" => (false, false); "int type")]
+#[test_case("1"   => (false, false); "concrete")]
+fn value_is_bottom_top_test(src: &str) -> (bool, bool) {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(&ctx, src).unwrap();
+    (v.is_bottom(), v.is_top())
+}
+
+#[test]
+fn value_unify_is_bottom_test() {
+    let ctx = Ctx::new().unwrap();
+    let a = Value::compile_string(&ctx, "1").unwrap();
+    let b = Value::compile_string(&ctx, "2").unwrap();
+    assert!(Value::unify(&a, &b).is_bottom());
+    assert!(!Value::unify(&a, &a).is_bottom());
+}
+
+#[test_case("1"          => true;  "int")]
+#[test_case(r#""a""#      => true;  "string")]
+#[test_case("null"       => true;  "null")]
+#[test_case("{ a: 1, b: [2] }" => true;  "concrete struct")]
+#[test_case("{ a: int }"       => false; "struct with a constraint")]
+#[test_case("{ a: { b: int } }" => false; "nested constraint")]
+#[test_case("[int]"            => false; "list with a constraint")]
+#[test_case("int"        => false; "type constraint")]
+#[test_case(">0"         => false; "bound")]
+#[test_case("1 | 2"      => false; "disjunction")]
+#[test_case("_"          => false; "top")]
+#[test_case("1 & 2"      => false; "bottom")]
+fn value_is_concrete_test(src: &str) -> bool {
+    let ctx = Ctx::new().unwrap();
+    Value::compile_string(&ctx, src).unwrap().is_concrete()
+}
+
+// ── len ──────────────────────────────────────────────────────────────
+
+#[test_case(r#""héllo""#                  => Some(6); "string counts bytes")]
+#[test_case("'\\x00\\x01'"                => Some(2); "bytes")]
+#[test_case(r#"[1, "a,b", [2, 3], {}]"#   => Some(4); "list")]
+#[test_case("[]"                          => Some(0); "empty list")]
+#[test_case("{ a: 1, b: { c: 2 }, _h: 3, #D: 4, o?: 5 }" => Some(2); "struct counts regular fields")]
+#[test_case("{}"                          => Some(0); "empty struct")]
+#[test_case("1"                           => None;    "int")]
+#[test_case("[int]"                       => None;    "non concrete list")]
+fn value_len_test(src: &str) -> Option<usize> {
+    let ctx = Ctx::new().unwrap();
+    Value::compile_string(&ctx, src).unwrap().len().ok()
+}
+
+#[test]
+fn value_len_kind_mismatch_test() {
+    let ctx = Ctx::new().unwrap();
+    let err = Value::compile_string(&ctx, "true")
+        .unwrap()
+        .len()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected string, bytes, list or struct, found bool"
+    );
+}
+
+#[test_case(b"[]" => 0; "empty array")]
+#[test_case(b"[ ]" => 0; "blank array")]
+#[test_case(b"[1]" => 1; "one")]
+#[test_case(br#"["a,\"]", [1, 2], {"k": [3]}]"# => 3; "nested and strings")]
+#[test_case(br#"{"a":1,"b":{"c":2}}"# => 2; "object")]
+fn count_entries_test(json: &[u8]) -> usize {
+    super::count_entries(json)
+}
+
+// ── yaml ─────────────────────────────────────────────────────────────
+
+#[test_case("name: alice\nage: 30" => r#"{"name":"alice","age":30}"#; "mapping")]
+#[test_case("- 1\n- two\n- true"   => r#"[1,"two",true]"#;           "sequence")]
+#[test_case("n: 0x1F"              => r#"{"n":31}"#;                 "hex int")]
+#[test_case("s: \"a \\\"b\\\"\""   => r#"{"s":"a \"b\""}"#;          "quoted string")]
+fn value_compile_yaml_test(yaml: &str) -> String {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_yaml(&ctx, yaml).unwrap();
+    String::from_utf8(v.to_json_vec().unwrap()).unwrap()
+}
+
+#[test]
+fn value_compile_yaml_error_test() {
+    let ctx = Ctx::new().unwrap();
+    assert!(matches!(
+        Value::compile_yaml(&ctx, "a: [1, 2"),
+        Err(Error::Cue(_))
+    ));
+
+    let ctx = Ctx::with_limits(Limits::default().with_max_source_size(4)).unwrap();
+    assert!(matches!(
+        Value::compile_yaml(&ctx, "a: 1\n"),
+        Err(Error::LimitExceeded {
+            limit: Limit::SourceSize,
+            ..
+        })
+    ));
+}
+
+// ── toml ─────────────────────────────────────────────────────────────
+
+#[test_case("name = \"alice\"\nage = 30"             => r#"{"name":"alice","age":30}"#;             "table")]
+#[test_case("[package]\nname = \"cue-rs\"\nv = 1.5" => r#"{"package":{"name":"cue-rs","v":1.5}}"#; "nested table")]
+#[test_case("xs = [1, 2]"                            => r#"{"xs":[1,2]}"#;                          "array")]
+fn value_compile_toml_test(toml: &str) -> String {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_toml(&ctx, toml).unwrap();
+    String::from_utf8(v.to_json_vec().unwrap()).unwrap()
+}
+
+#[test]
+fn value_compile_toml_error_test() {
+    let ctx = Ctx::new().unwrap();
+    assert!(matches!(
+        Value::compile_toml(&ctx, "a = "),
+        Err(Error::Cue(_))
+    ));
+}
+
+// ── hooks ────────────────────────────────────────────────────────────
+
+type Events = Arc<Mutex<Vec<(Operation, Option<usize>, bool)>>>;
+
+fn record(events: &Events) -> impl Fn(&Event<'_>) + Send + Sync + 'static {
+    let events = Arc::clone(events);
+    move |event| {
+        events.lock().unwrap().push((
+            event.operation,
+            event.source.map(<[u8]>::len),
+            event.error.is_some(),
+        ));
+    }
+}
+
+#[test]
+fn value_hooks_test() {
+    let events = Events::default();
+    let mut ctx = Ctx::new().unwrap();
+    ctx.set_hooks(
+        Hooks::default()
+            .on_compile(record(&events))
+            .on_unify(record(&events))
+            .on_validate(record(&events)),
+    );
+
+    let a = Value::compile_string(&ctx, "1").unwrap();
+    let b = Value::compile_bytes(&ctx, b"22").unwrap();
+    let v = Value::unify(&a, &b);
+    assert!(v.is_valid().is_err());
+    // The outcome is cached, so asking again does not reach libcue.
+    assert!(v.is_valid().is_err());
+
+    assert_eq!(*events.lock().unwrap(), vec![
+        (Operation::Compile, Some(1), false),
+        (Operation::Compile, Some(2), false),
+        (Operation::Unify, None, false),
+        (Operation::Validate, None, true),
+    ]);
+}
+
+// ── string ───────────────────────────────────────────────────────────
+
+#[test_case(r#""hello""#    => Some("hello".to_string()); "plain")]
+#[test_case(r#""a\nb é""#   => Some("a\nb é".to_string()); "escapes")]
+#[test_case(r#""a" + "b""#  => Some("ab".to_string());    "concatenation")]
+#[test_case("string"        => None;                      "not concrete")]
+#[test_case("1"             => None;                      "not a string")]
+fn value_to_string_test(src: &str) -> Option<String> {
+    let ctx = Ctx::new().unwrap();
+    Value::compile_string(&ctx, src).unwrap().to_string().ok()
+}
+
+// ── json5 ────────────────────────────────────────────────────────────
+
+#[test]
+fn value_compile_json5_test() {
+    let ctx = Ctx::new().unwrap();
+    let src = "
+        // Service configuration.
+        {
+            name: 'api', /* quoted with ' */
+            ports: [0x50, 443,],
+            ratio: .5,
+            if: true,
+        }
+    ";
+    let v = Value::compile_json5(&ctx, src).unwrap();
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&v.to_json_vec().unwrap()).unwrap(),
+        json!({ "name": "api", "ports": [80, 443], "ratio": 0.5, "if": true })
+    );
+    assert!(matches!(
+        Value::compile_json5(&ctx, "{ a: Infinity }"),
+        Err(Error::Syntax {
+            line: 1,
+            column: 6,
+            ..
+        })
+    ));
+}
+
+// ── msgpack ──────────────────────────────────────────────────────────
+
+#[cfg(feature = "rmp")]
+#[test]
+fn value_to_msgpack_test() {
+    let ctx = Ctx::new().unwrap();
+    let v =
+        Value::compile_string(&ctx, r#"{ a: [1, -2, 2.5, "x", null], b: { c: true } }"#).unwrap();
+    let msgpack = v.to_msgpack().unwrap();
+    assert_eq!(
+        rmp_serde::from_slice::<serde_json::Value>(&msgpack).unwrap(),
+        json!({ "a": [1, -2, 2.5, "x", null], "b": { "c": true } })
+    );
+    assert!(
+        Value::compile_string(&ctx, "{ a: int }")
+            .unwrap()
+            .to_msgpack()
+            .is_err()
+    );
+}
+
+// ── serialize ────────────────────────────────────────────────────────
+
+#[cfg(feature = "serde")]
+#[test]
+fn value_serialize_test() {
+    #[derive(serde::Serialize)]
+    struct Wrapper {
+        id: u32,
+        config: Value,
+    }
+
+    let ctx = Ctx::new().unwrap();
+    let config = Value::compile_string(&ctx, "{ a: 1 + 1, b: [true] }").unwrap();
+    let json = serde_json::to_value(Wrapper { id: 7, config }).unwrap();
+    assert_eq!(json, json!({ "id": 7, "config": { "a": 2, "b": [true] } }));
+
+    let incomplete = Value::compile_string(&ctx, "{ a: int }").unwrap();
+    assert!(serde_json::to_vec(&incomplete).is_err());
+}
+
+// ── encode ───────────────────────────────────────────────────────────
+
+#[cfg(feature = "serde")]
+#[test]
+fn value_encode_test() {
+    #[derive(serde::Serialize)]
+    struct Server {
+        host: &'static str,
+        ports: Vec<u16>,
+        tls: Option<bool>,
+    }
+
+    let ctx = Ctx::new().unwrap();
+    let json = |v: &Value| serde_json::from_slice::<serde_json::Value>(&v.to_json_vec().unwrap());
+    assert_eq!(Value::encode(&ctx, &-3i8).unwrap().to_int64().unwrap(), -3);
+    assert_eq!(
+        Value::encode(&ctx, &u64::MAX).unwrap().to_uint64().unwrap(),
+        u64::MAX
+    );
+    assert!(Value::encode(&ctx, &true).unwrap().to_bool().unwrap());
+    assert_eq!(
+        Value::encode(&ctx, "a\"b").unwrap().to_string().unwrap(),
+        "a\"b"
+    );
+    assert_eq!(
+        Value::encode(&ctx, "a\0b").unwrap().to_string().unwrap(),
+        "a\0b"
+    );
+    assert_eq!(
+        json(&Value::encode(&ctx, &()).unwrap()).unwrap(),
+        json!(null)
+    );
+    assert_eq!(
+        json(
+            &Value::encode(&ctx, &Server {
+                host: "db",
+                ports: vec![5432],
+                tls: None
+            })
+            .unwrap()
+        )
+        .unwrap(),
+        json!({ "host": "db", "ports": [5432], "tls": null })
+    );
+    assert!(matches!(
+        Value::encode(&ctx, &f64::NAN),
+        Err(Error::Serialize(_))
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn value_encode_serializes_once_test() {
+    struct Counted(std::cell::Cell<usize>);
+
+    impl serde::Serialize for Counted {
+        fn serialize<S: serde::Serializer>(
+            &self,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            self.0.set(self.0.get() + 1);
+            [1, 2].serialize(serializer)
+        }
+    }
+
+    let ctx = Ctx::new().unwrap();
+    let counted = Counted(std::cell::Cell::new(0));
+    let value = Value::encode(&ctx, &counted).unwrap();
+    assert_eq!(value.to_json_vec().unwrap(), b"[1,2]");
+    assert_eq!(counted.0.get(), 1);
+}
+
+// ── patterns ─────────────────────────────────────────────────────────
+
+#[test_case(Value::matching, "^[a-z]+$", r#""abc""#     => true;  "matching match")]
+#[test_case(Value::matching, "^[a-z]+$", r#""ABC""#     => false; "matching mismatch")]
+#[test_case(Value::matching, "^[a-z]+$", "1"            => false; "matching non string")]
+#[test_case(Value::not_matching, r"\s", r#""abc""#     => true;  "not matching match")]
+#[test_case(Value::not_matching, r"\s", r#""a b""#     => false; "not matching mismatch")]
+fn value_pattern_test(
+    constraint: fn(&Ctx, &str) -> Result<Value, Error>,
+    pattern: &str,
+    doc: &str,
+) -> bool {
+    let ctx = Ctx::new().unwrap();
+    let constraint = constraint(&ctx, pattern).unwrap();
+    assert_eq!(constraint.path(), &Path::default());
+    assert_eq!(constraint.label(), None);
+    let doc = Value::compile_string(&ctx, doc).unwrap();
+    Value::unify(&constraint, &doc).is_valid().is_ok()
+}
+
+#[test]
+fn value_pattern_invalid_test() {
+    let ctx = Ctx::new().unwrap();
+    assert!(matches!(Value::matching(&ctx, "[a-z"), Err(Error::Cue(_))));
+    assert!(matches!(
+        Value::not_matching(&ctx, "(?P<"),
+        Err(Error::Cue(_))
+    ));
+}
+
+#[test]
+fn value_runes_test() {
+    let ctx = Ctx::new().unwrap();
+    let len = Value::unify(
+        &Value::min_runes(&ctx, 2).unwrap(),
+        &Value::max_runes(&ctx, 3).unwrap(),
+    );
+    for (doc, valid) in [
+        (r#""a""#, false),
+        (r#""ab""#, true),
+        (r#""äöü""#, true),
+        (r#""abcd""#, false),
+    ] {
+        let doc = Value::compile_string(&ctx, doc).unwrap();
+        assert_eq!(
+            Value::unify(&len, &doc).is_valid().is_ok(),
+            valid,
+            "{doc:?}"
+        );
+    }
+}
+
+// ── path ─────────────────────────────────────────────────────────────
+
+#[test]
+fn value_path_test() {
+    let ctx = Ctx::new().unwrap();
+    let v = Value::compile_string(&ctx, r#"spec: { ports: [80, 443], "x-y": { z: 1 } }"#).unwrap();
+    assert_eq!(v.path().to_string(), "");
+    assert_eq!(v.label(), None);
+
+    let spec = v.lookup("spec").unwrap();
+    let port = spec.lookup("ports[1]").unwrap();
+    assert_eq!(port.path().to_string(), "spec.ports[1]");
+    assert_eq!(port.label().as_deref(), Some("1"));
+
+    let z = spec
+        .lookup_path(&Path::field("x-y").unwrap())
+        .unwrap()
+        .lookup("z")
+        .unwrap();
+    assert_eq!(z.path().to_string(), r#"spec."x-y".z"#);
+    assert_eq!(z.label().as_deref(), Some("z"));
+    assert_eq!(Value::unify(&z, &v).path(), z.path());
+}
@@ -0,0 +1,134 @@
+//! One-call validation of documents against a schema [`Value`].
+
+use super::Value;
+use crate::{Ctx, error::Error};
+
+/// Options for [`Value::validate_json_with`] and
+/// [`Value::validate_yaml_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidateOptions {
+    /// Whether every field of the result must be concrete.
+    concrete: bool,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self {
+        Self { concrete: true }
+    }
+}
+
+impl ValidateOptions {
+    /// Creates the default options, requiring a concrete result.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether every field of the result must be concrete (the
+    /// default).
+    ///
+    /// Without this, a document that leaves out a required field such as
+    /// `name: string` is accepted, as the field is merely incomplete.
+    #[must_use]
+    pub fn with_concrete(
+        mut self,
+        concrete: bool,
+    ) -> Self {
+        self.concrete = concrete;
+        self
+    }
+}
+
+impl Value {
+    /// Validates the JSON document `data` against this schema, returning
+    /// the unified value, with the default [`ValidateOptions`].
+    ///
+    /// `ctx` must be the context the schema was compiled in.  Errors can be
+    /// broken down into diagnostics with
+    /// [`Report::from`](crate::report::Report).
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Value::validate_json_with`].
+    pub fn validate_json(
+        &self,
+        ctx: &Ctx,
+        data: &[u8],
+    ) -> Result<Self, Error> {
+        self.validate_json_with(ctx, data, ValidateOptions::default())
+    }
+
+    /// Validates the JSON document `data` against this schema, returning
+    /// the unified value.
+    ///
+    /// The document is decoded by CUE's own `encoding/json` package, so
+    /// input that is not JSON, such as CUE with unquoted keys or
+    /// expressions, is rejected rather than evaluated.  `ctx` must be the
+    /// context the schema was compiled in.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidUtf8`] if `data` is not UTF-8, [`Error::Cue`]
+    /// if the document is not JSON, does not satisfy the schema or, if
+    /// required by `opts`, leaves a field incomplete, or
+    /// [`Error::LimitExceeded`] if it is larger than the context's source
+    /// size limit.
+    pub fn validate_json_with(
+        &self,
+        ctx: &Ctx,
+        data: &[u8],
+        opts: ValidateOptions,
+    ) -> Result<Self, Error> {
+        self.validate_data(&Self::compile_json(ctx, data)?, opts)
+    }
+
+    /// Validates the YAML document `data` against this schema, returning
+    /// the unified value, with the default [`ValidateOptions`].
+    ///
+    /// `ctx` must be the context the schema was compiled in.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Value::validate_yaml_with`].
+    pub fn validate_yaml(
+        &self,
+        ctx: &Ctx,
+        data: &str,
+    ) -> Result<Self, Error> {
+        self.validate_yaml_with(ctx, data, ValidateOptions::default())
+    }
+
+    /// Validates the YAML document `data` against this schema, returning
+    /// the unified value.
+    ///
+    /// The document is decoded as by [`Value::compile_yaml`].  `ctx` must be
+    /// the context the schema was compiled in.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Value::validate_json_with`].
+    pub fn validate_yaml_with(
+        &self,
+        ctx: &Ctx,
+        data: &str,
+        opts: ValidateOptions,
+    ) -> Result<Self, Error> {
+        self.validate_data(&Self::compile_yaml(ctx, data)?, opts)
+    }
+
+    /// Unifies `data` with this schema and validates the result.
+    pub(crate) fn validate_data(
+        &self,
+        data: &Self,
+        opts: ValidateOptions,
+    ) -> Result<Self, Error> {
+        let value = Self::unify(self, data);
+        value.is_valid()?;
+        if opts.concrete {
+            // libcue validates without a concreteness check; exporting fails
+            // on the first incomplete value instead.
+            value.to_json_vec()?;
+        }
+        Ok(value)
+    }
+}
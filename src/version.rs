@@ -0,0 +1,44 @@
+//! Versions of the CUE evaluator embedded in this crate.
+
+/// Go module versions baked into the statically linked libcue archive.
+///
+/// Captured from `libcue/go.mod` at build time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Version {
+    /// Version of `cuelang.org/go`, the CUE evaluator (e.g. `v0.15.3`).
+    pub cue: &'static str,
+    /// Version of `github.com/cue-lang/libcue`, the C API wrapper.
+    pub libcue: &'static str,
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "cue {} (libcue {})", self.cue, self.libcue)
+    }
+}
+
+/// Returns the versions of the CUE evaluator and libcue embedded in this
+/// crate, so applications can log and assert which evaluator semantics they
+/// run.
+#[must_use]
+pub fn version() -> Version {
+    Version {
+        cue: env!("CUE_RS_CUE_VERSION"),
+        libcue: env!("CUE_RS_LIBCUE_VERSION"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::version;
+
+    #[test]
+    fn test_version() {
+        let v = version();
+        assert!(v.cue.starts_with('v'));
+        assert!(v.libcue.starts_with('v'));
+    }
+}
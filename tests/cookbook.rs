@@ -0,0 +1,94 @@
+//! Integration tests for the `cookbook` recipes.
+
+#![cfg(feature = "cookbook")]
+#![allow(clippy::unwrap_used)]
+
+use cue_rs::{Ctx, cookbook};
+use serde_json::json;
+use test_case::test_case;
+
+#[test_case(&json!({
+    "apiVersion": "v1",
+    "kind": "ConfigMap",
+    "metadata": { "name": "app-config", "labels": { "app": "web" } },
+    "data": { "key": "value" }
+}) => true; "config map")]
+#[test_case(&json!({
+    "apiVersion": "apps/v1",
+    "kind": "Deployment",
+    "metadata": { "name": "web" },
+    "spec": {
+        "replicas": 2,
+        "selector": { "matchLabels": { "app": "web" } },
+        "template": {
+            "metadata": { "labels": { "app": "web" } },
+            "spec": { "containers": [{ "name": "web", "image": "nginx:1.27" }] }
+        }
+    }
+}) => true; "deployment")]
+#[test_case(&json!({
+    "apiVersion": "v1",
+    "kind": "ConfigMap",
+    "metadata": { "name": "Not_Valid" }
+}) => false; "invalid name")]
+#[test_case(&json!({
+    "apiVersion": "apps/v1",
+    "kind": "Deployment",
+    "metadata": { "name": "web" },
+    "spec": {
+        "selector": { "matchLabels": { "app": "web" } },
+        "template": {
+            "metadata": { "labels": { "app": "web" } },
+            "spec": { "containers": [] }
+        }
+    }
+}) => false; "deployment without containers")]
+fn validate_k8s_manifest_test(manifest: &serde_json::Value) -> bool {
+    let ctx = Ctx::new().unwrap();
+    let manifest = serde_json::to_vec(manifest).unwrap();
+    cookbook::validate_k8s_manifest(&ctx, &manifest).is_ok()
+}
+
+#[test]
+fn layered_config_test() {
+    let ctx = Ctx::new().unwrap();
+    let config = cookbook::layered_config(&ctx, &[
+        "{ port: int & >0 | *8080, host: string }",
+        r#"{ host: "localhost" }"#,
+        "{ port: 9000 }",
+    ])
+    .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&config.to_json_vec().unwrap()).unwrap();
+    assert_eq!(json, json!({ "port": 9000, "host": "localhost" }));
+
+    assert!(cookbook::layered_config(&ctx, &["{ port: 1 }", "{ port: 2 }"]).is_err());
+}
+
+#[test]
+fn schema_regressions_test() {
+    let ctx = Ctx::new().unwrap();
+    let samples: [&[u8]; 3] = [
+        br#"{ "port": 80 }"#,
+        br#"{ "port": 8080 }"#,
+        br#"{ "port": "http" }"#,
+    ];
+    let regressions =
+        cookbook::schema_regressions(&ctx, "{ port: int }", "{ port: int & >1024 }", &samples)
+            .unwrap();
+    assert_eq!(regressions, vec![0]);
+}
+
+#[test]
+fn helm_values_test() {
+    let ctx = Ctx::new().unwrap();
+    let schema = r#"{
+        replicaCount: int & >0 | *1
+        image: { repository: string | *"nginx", tag?: string }
+    }"#;
+    let values = cookbook::validate_helm_values(&ctx, schema, "image:\n  tag: \"1.27\"\n").unwrap();
+    assert_eq!(
+        cookbook::render_helm_values(&ctx, &values).unwrap(),
+        "replicaCount: 1\nimage:\n  repository: nginx\n  tag: \"1.27\"\n"
+    );
+    assert!(cookbook::validate_helm_values(&ctx, schema, "replicaCount: 0\n").is_err());
+}
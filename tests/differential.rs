@@ -0,0 +1,173 @@
+//! Differential tests against the `cue` command line tool.
+//!
+//! Every input is exported by this crate and by `cue export`; both must agree
+//! on whether the input is valid and, if it is, on the exported JSON.  The
+//! inputs are a fixed corpus followed by randomly generated expressions, so
+//! drift introduced by libcue or by these bindings shows up as a mismatch.
+//!
+//! The tests are ignored by default as they need a `cue` binary of the same
+//! version as the embedded CUE (see `cue_rs::version()`):
+//!
+//! ```text
+//! CUE_BIN=/path/to/cue cargo test --test differential -- --ignored
+//! ```
+//!
+//! `CUE_DIFF_SEED` and `CUE_DIFF_CASES` select the generated inputs; a failing
+//! case is reported with its seed so it can be replayed.
+
+#![allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::panic,
+    clippy::indexing_slicing
+)]
+
+use std::process::Command;
+
+use cue_rs::{Ctx, Value};
+
+/// Hand-written inputs covering each kind of value and common failures.
+const CORPUS: &[&str] = &[
+    "1",
+    "-9223372036854775808",
+    "18446744073709551616",
+    "1.5e300",
+    r#""aé\n""#,
+    "'bytes'",
+    "null",
+    "[1, [2, [3]]]",
+    "{ a: 1, b: { c: [true] } }",
+    "{ a: int, a: 1 }",
+    "{ a: int }",
+    "*1 | 2",
+    "1 | 2",
+    ">0 & 3",
+    "1 & 2",
+    "{ #D: { a: int }, v: #D & { a: 1 } }",
+    "{ #D: { a: int }, v: #D & { b: 1 } }",
+    r#"{ x: 2, y: x * 3, s: "v\(y)" }"#,
+    "[ for i in [1, 2, 3] if i > 1 { i * i } ]",
+    "{ a: b, b: a }",
+];
+
+/// Outcome of exporting an input: the JSON document, or `None` if the input
+/// is invalid or not concrete.
+type Outcome = Option<serde_json::Value>;
+
+/// Exports `src` with this crate.
+fn export_crate(
+    ctx: &Ctx,
+    src: &str,
+) -> Outcome {
+    let value = Value::compile_string(ctx, src).ok()?;
+    value.is_valid().ok()?;
+    let json = value.to_json_vec().ok()?;
+    Some(serde_json::from_slice(&json).expect("libcue exported invalid JSON"))
+}
+
+/// Exports `src` with the `cue` binary `cue`.
+fn export_cli(
+    cue: &str,
+    src: &str,
+    case: usize,
+) -> Outcome {
+    let file = std::env::temp_dir().join(format!("cue-rs-diff-{}-{case}.cue", std::process::id()));
+    std::fs::write(&file, src).unwrap();
+    let output = Command::new(cue)
+        .args(["export", "--out", "json"])
+        .arg(&file)
+        .output()
+        .expect("failed to run the cue binary");
+    std::fs::remove_file(&file).unwrap();
+    output
+        .status
+        .success()
+        .then(|| serde_json::from_slice(&output.stdout).expect("cue exported invalid JSON"))
+}
+
+/// A xorshift generator of random CUE expressions.
+struct Gen(u64);
+
+impl Gen {
+    /// Returns the next random number.
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a random element of `items`.
+    fn pick<'a>(
+        &mut self,
+        items: &[&'a str],
+    ) -> &'a str {
+        let i = usize::try_from(self.next())
+            .unwrap()
+            .checked_rem(items.len())
+            .unwrap();
+        items[i]
+    }
+
+    /// Returns a random expression nested at most `depth` levels deep.
+    fn expr(
+        &mut self,
+        depth: usize,
+    ) -> String {
+        let leaf = [
+            "0", "1", "-1", "2.5", r#""a""#, r#""b""#, "true", "null", "int", "string", "number",
+            ">0", "<=1", "_", "_|_",
+        ];
+        if depth == 0 || self.next().is_multiple_of(3) {
+            return self.pick(&leaf).to_string();
+        }
+        let depth = depth.saturating_sub(1);
+        match self.next() % 6 {
+            0 => format!("({} & {})", self.expr(depth), self.expr(depth)),
+            1 => format!("({} | {})", self.expr(depth), self.expr(depth)),
+            2 => format!("(*{} | {})", self.expr(depth), self.expr(depth)),
+            3 => format!("{{ a: {}, b: {} }}", self.expr(depth), self.expr(depth)),
+            4 => format!("[{}, {}]", self.expr(depth), self.expr(depth)),
+            _ => format!("{{ a: {}, a: {} }}", self.expr(depth), self.expr(depth)),
+        }
+    }
+}
+
+/// Reads a numeric environment variable, falling back to `default`.
+fn env_or(
+    name: &str,
+    default: u64,
+) -> u64 {
+    std::env::var(name).map_or(default, |v| v.parse().expect("not a number"))
+}
+
+#[test]
+#[ignore = "requires the cue CLI, see the module documentation"]
+fn differential_test() {
+    let cue = std::env::var("CUE_BIN").unwrap_or_else(|_| "cue".to_string());
+    let seed = env_or("CUE_DIFF_SEED", 0x5EED).max(1);
+    let cases = env_or("CUE_DIFF_CASES", 500);
+    let ctx = Ctx::new().unwrap();
+
+    let mut generator = Gen(seed);
+    let generated = (0..cases).map(|_| generator.expr(3));
+    let inputs = CORPUS.iter().map(ToString::to_string).chain(generated);
+
+    let mut mismatches = Vec::new();
+    for (case, src) in inputs.enumerate() {
+        let ours = export_crate(&ctx, &src);
+        let theirs = export_cli(&cue, &src, case);
+        if ours != theirs {
+            mismatches.push(format!(
+                "case {case}: {src}\n  cue-rs: {ours:?}\n  cue:    {theirs:?}"
+            ));
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "{} mismatches with {} (seed {seed}):\n{}",
+        mismatches.len(),
+        cue_rs::version(),
+        mismatches.join("\n")
+    );
+}
@@ -0,0 +1,81 @@
+//! Integration tests for the `lint` presets.
+
+#![cfg(feature = "lint")]
+#![allow(clippy::unwrap_used)]
+
+use cue_rs::{
+    Ctx,
+    lint::{self, Preset},
+};
+use test_case::test_case;
+
+#[test]
+fn preset_lookup_test() {
+    for preset in Preset::ALL {
+        assert_eq!(lint::preset(preset.name()), Some(preset));
+        assert_eq!(preset.to_string(), preset.name());
+    }
+    assert_eq!(lint::preset("unknown"), None);
+}
+
+#[test_case(Preset::Kubernetes, "
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: app-config
+data:
+  key: value
+" => true; "k8s config map")]
+#[test_case(Preset::Kubernetes, "
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: Not_Valid
+" => false; "k8s invalid name")]
+#[test_case(Preset::GithubActions, "
+name: CI
+on: [push, pull_request]
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - run: cargo test
+        env:
+          RUST_BACKTRACE: 1
+" => true; "workflow")]
+#[test_case(Preset::GithubActions, "
+on: push
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps: cargo test
+" => false; "workflow steps not a list")]
+#[test_case(Preset::GithubActions, "
+name: CI
+" => false; "workflow without jobs")]
+#[test_case(Preset::DockerCompose, "
+services:
+  web:
+    image: nginx:1.27
+    ports: ['8080:80']
+    depends_on: [db]
+    restart: unless-stopped
+  db:
+    image: postgres:17
+    environment:
+      POSTGRES_PASSWORD: example
+" => true; "compose")]
+#[test_case(Preset::DockerCompose, "
+services:
+  web:
+    image: nginx
+    restart: sometimes
+" => false; "compose invalid restart policy")]
+fn preset_check_yaml_test(
+    preset: Preset,
+    yaml: &str,
+) -> bool {
+    let ctx = Ctx::new().unwrap();
+    preset.check_yaml(&ctx, yaml).is_ok()
+}